@@ -0,0 +1,133 @@
+use crate::components::{Piece, Position};
+use crate::game_types::{BoardConfig, GameMap};
+use crate::pieces::state_cells;
+use crate::{LockDelay, can_move_horizontally};
+use bevy::prelude::*;
+
+// Number keys mapped to board columns, left to right
+const COLUMN_KEYS: [KeyCode; 10] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Digit0,
+];
+
+// Lets the number keys jump the active piece straight to a column instead of
+// relying on precise taps or DAS
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ColumnTargeting(pub bool);
+
+// Flips the board left to right for symmetric practice, applied to the
+// BoardConfig when a game starts
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MirrorBoard(pub bool);
+
+// Slides the piece toward on-screen `column` (its leftmost cell's target),
+// stopping at the last reachable column if something is in the way. On a
+// mirrored board the on-screen leftmost cell is the canonical rightmost one.
+pub fn move_to_column(
+    piece: &Piece,
+    position: &mut Position,
+    column: isize,
+    game_map: &GameMap,
+    mirrored: bool,
+) {
+    let columns = state_cells(piece, piece.current_state)
+        .iter()
+        .map(|&(x, _)| x as isize)
+        .collect::<Vec<_>>();
+    let target_x = if mirrored {
+        let rightmost = columns.iter().copied().max().unwrap_or(0);
+        game_map.width() as isize - 1 - column - rightmost
+    } else {
+        let leftmost = columns.iter().copied().min().unwrap_or(0);
+        column - leftmost
+    };
+    let step = (target_x - position.x).signum();
+
+    while position.x != target_x
+        && can_move_horizontally(piece, position, position.x + step, game_map)
+    {
+        position.x += step;
+    }
+}
+
+pub fn target_column(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    targeting: Res<ColumnTargeting>,
+    mut query: Query<(&Piece, &mut Position)>,
+    game_map: Res<GameMap>,
+    board: Res<BoardConfig>,
+    mut lock_delay: ResMut<LockDelay>,
+) {
+    if !targeting.0 {
+        return;
+    }
+    let Some(column) = COLUMN_KEYS
+        .iter()
+        .take(game_map.width())
+        .position(|key| keyboard_input.just_pressed(*key))
+    else {
+        return;
+    };
+
+    if let Ok((piece, mut position)) = query.get_single_mut() {
+        let start_x = position.x;
+        move_to_column(
+            piece,
+            &mut position,
+            column as isize,
+            &game_map,
+            board.mirrored,
+        );
+        if position.x != start_x {
+            lock_delay.move_reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_color::GameColor;
+    use crate::game_types::{PieceType, Presence};
+
+    // Presses `key` for one frame and returns where the active piece ended up
+    fn press_column_key(game_map: GameMap, key: KeyCode) -> Position {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .insert_resource(ColumnTargeting(true))
+            .insert_resource(game_map)
+            .init_resource::<BoardConfig>()
+            .init_resource::<LockDelay>()
+            .add_systems(Update, target_column);
+        let piece = app
+            .world
+            .spawn((Piece::new(PieceType::T), Position { x: 5, y: 0 }))
+            .id();
+        app.world.resource_mut::<ButtonInput<KeyCode>>().press(key);
+        app.update();
+        *app.world.get::<Position>(piece).unwrap()
+    }
+
+    #[test]
+    fn column_key_moves_leftmost_cell_to_that_column() {
+        let position = press_column_key(GameMap::default(), KeyCode::Digit4);
+        assert_eq!(position.x, 3);
+    }
+
+    #[test]
+    fn blocked_target_stops_at_nearest_reachable_column() {
+        let mut game_map = GameMap::default();
+        game_map.set_cell(2, 1, Presence::Yes(GameColor::Gray));
+
+        let position = press_column_key(game_map, KeyCode::Digit1);
+        assert_eq!(position.x, 3);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::debug::DebugSettings;
+use crate::game_constants::FONT_SIZE_LOG;
+use crate::simulation::GameAction;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+// Frames of actions kept on screen
+const ACTION_TRACE_CAPACITY: usize = 8;
+
+// Logical actions the gameplay systems consumed, grouped by frame so DAS,
+// ARR and soft drop can be checked live
+#[derive(Resource, Debug, Default)]
+pub struct ActionTrace {
+    frame: Vec<GameAction>,
+    pub history: VecDeque<Vec<GameAction>>,
+}
+
+impl ActionTrace {
+    pub fn record(&mut self, action: GameAction) {
+        self.frame.push(action);
+    }
+
+    // Actions recorded so far this frame, in the order they were consumed
+    pub fn frame(&self) -> &[GameAction] {
+        &self.frame
+    }
+
+    // Closes the current frame, keeping it only if something happened
+    pub fn end_frame(&mut self) {
+        if self.frame.is_empty() {
+            return;
+        }
+        if self.history.len() == ACTION_TRACE_CAPACITY {
+            self.history.pop_front();
+        }
+        self.history.push_back(std::mem::take(&mut self.frame));
+    }
+
+    // One line per frame, oldest first, actions in the order they were consumed
+    pub fn text(&self) -> String {
+        self.history
+            .iter()
+            .map(|actions| {
+                actions
+                    .iter()
+                    .map(|action| format!("{:?}", action))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// Marker component for the on-screen action trace panel
+#[derive(Component)]
+pub struct ActionTracePanel;
+
+pub fn setup_action_trace_panel(mut commands: Commands) {
+    let mut text_bundle = TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: FONT_SIZE_LOG,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        bottom: Val::Px(40.0),
+        right: Val::Px(10.0),
+        ..default()
+    })
+    .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6));
+    text_bundle.visibility = Visibility::Hidden;
+
+    commands.spawn((text_bundle, ActionTracePanel));
+}
+
+// Closes this frame's actions and refreshes the panel; runs after every
+// system that records actions
+pub fn update_action_trace_panel(
+    mut trace: ResMut<ActionTrace>,
+    settings: Res<DebugSettings>,
+    mut query_panel: Query<(&mut Text, &mut Visibility), With<ActionTracePanel>>,
+) {
+    // Only touch the trace mutably when there is a frame to close, so change
+    // detection stays quiet on idle frames
+    if !trace.frame.is_empty() {
+        trace.end_frame();
+    }
+    if (trace.is_changed() || settings.is_changed())
+        && let Some((mut text, mut visibility)) = query_panel.iter_mut().next()
+    {
+        text.sections[0].value = trace.text();
+        *visibility = if settings.show_actions {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panel_lists_a_frames_actions_in_order() {
+        let mut app = App::new();
+        app.init_resource::<ActionTrace>()
+            .insert_resource(DebugSettings {
+                show_actions: true,
+                ..DebugSettings::default()
+            })
+            .add_systems(Startup, setup_action_trace_panel)
+            .add_systems(Update, update_action_trace_panel);
+        let actions = [
+            GameAction::RotateCw,
+            GameAction::MoveLeft,
+            GameAction::HardDrop,
+        ];
+        for action in actions {
+            app.world.resource_mut::<ActionTrace>().record(action);
+        }
+        app.update();
+
+        let trace = app.world.resource::<ActionTrace>();
+        assert_eq!(trace.history, VecDeque::from([actions.to_vec()]));
+        assert!(trace.frame().is_empty());
+        let mut query = app
+            .world
+            .query_filtered::<(&Text, &Visibility), With<ActionTracePanel>>();
+        let (text, visibility) = query.single(&app.world);
+        assert_eq!(text.sections[0].value, "RotateCw, MoveLeft, HardDrop");
+        assert_eq!(*visibility, Visibility::Visible);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::debug::DebugSettings;
+use crate::game_constants::FONT_SIZE_LOG;
+use crate::game_types::{GameMap, Presence};
+use bevy::prelude::*;
+
+// Shape of the stack, in the terms placement heuristics score boards by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BoardMetrics {
+    /// Empty playable cells with a filled cell somewhere above them in their
+    /// column.
+    pub holes: usize,
+    /// Sum of the height differences between neighbouring columns.
+    pub bumpiness: usize,
+    /// Sum of every column's height.
+    pub aggregate_height: usize,
+    /// Column of an open well four rows deep, where an I piece would clear a
+    /// tetris.
+    pub tetris_well: Option<usize>,
+}
+
+// Rows a well must be deep for an I piece dropped into it to clear a tetris
+const TETRIS_ROWS: usize = 4;
+
+// The column of a well with nothing above it and TETRIS_ROWS stacked rows
+// beside it, each filled except for that column
+fn find_tetris_well(game_map: &GameMap) -> Option<usize> {
+    let width = game_map.width() as isize;
+    let mut run: Option<(usize, usize)> = None;
+    for y in (0..game_map.height() as isize).rev() {
+        let open: Vec<usize> = (0..width)
+            .filter(|&x| game_map.cell(x, y) == Some(Presence::No))
+            .map(|x| x as usize)
+            .collect();
+        run = match (open.as_slice(), run) {
+            (&[x], Some((well, depth))) if x == well => Some((x, depth + 1)),
+            (&[x], _) => Some((x, 1)),
+            _ => None,
+        };
+        if let Some((well, depth)) = run
+            && depth >= TETRIS_ROWS
+            && (0..y).all(|above| game_map.cell(well as isize, above) == Some(Presence::No))
+        {
+            return Some(well);
+        }
+    }
+    None
+}
+
+pub fn board_metrics(game_map: &GameMap) -> BoardMetrics {
+    let heights = game_map.column_heights();
+    let board_height = game_map.height();
+    let holes = heights
+        .iter()
+        .enumerate()
+        .map(|(x, &height)| {
+            (board_height - height..board_height)
+                .filter(|&y| !game_map.is_blocked(x, y) && game_map.rows[y][x] == Presence::No)
+                .count()
+        })
+        .sum();
+    let bumpiness = heights
+        .windows(2)
+        .map(|pair| pair[0].abs_diff(pair[1]))
+        .sum();
+    BoardMetrics {
+        holes,
+        bumpiness,
+        aggregate_height: heights.iter().sum(),
+        tetris_well: find_tetris_well(game_map),
+    }
+}
+
+// Marker component for the board metrics readout
+#[derive(Component)]
+pub struct BoardMetricsPanel;
+
+pub fn setup_board_metrics_panel(mut commands: Commands) {
+    let mut text_bundle = TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: FONT_SIZE_LOG,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        top: Val::Percent(30.0),
+        right: Val::Px(10.0),
+        ..default()
+    })
+    .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6));
+    text_bundle.visibility = Visibility::Hidden;
+
+    commands.spawn((text_bundle, BoardMetricsPanel));
+}
+
+pub fn update_board_metrics_panel(
+    game_map: Res<GameMap>,
+    settings: Res<DebugSettings>,
+    mut query_panel: Query<(&mut Text, &mut Visibility), With<BoardMetricsPanel>>,
+) {
+    if (game_map.is_changed() || settings.is_changed())
+        && let Some((mut text, mut visibility)) = query_panel.iter_mut().next()
+    {
+        let metrics = board_metrics(&game_map);
+        text.sections[0].value = format!(
+            "Holes: {}\nBumpiness: {}\nHeight: {}",
+            metrics.holes, metrics.bumpiness, metrics.aggregate_height
+        );
+        *visibility = if settings.show_metrics {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_color::GameColor;
+
+    #[test]
+    fn metrics_count_holes_bumpiness_and_height() {
+        // Heights 1, 3, 0 and 2, with a hole under the overhang in column 1
+        let game_map = GameMap::from_compact_string("..../.r../...g/br.g").unwrap();
+        assert_eq!(
+            board_metrics(&game_map),
+            BoardMetrics {
+                holes: 1,
+                bumpiness: 7,
+                aggregate_height: 6,
+                tetris_well: None,
+            }
+        );
+    }
+
+    #[test]
+    fn blocked_cell_under_the_stack_is_not_a_hole() {
+        let game_map = GameMap::from_compact_string("..../.r../.#../.rg.").unwrap();
+        assert_eq!(game_map.column_heights(), vec![0, 3, 1, 0]);
+        assert_eq!(board_metrics(&game_map).holes, 0);
+    }
+
+    // A 6x8 board stacked four high in every column but `well`
+    fn board_with_well(well: usize) -> GameMap {
+        let mut game_map = GameMap::new(6, 8);
+        for y in 4..8 {
+            for x in (0..6).filter(|&x| x != well) {
+                game_map.set_cell(x as isize, y, Presence::Yes(GameColor::Gray));
+            }
+        }
+        game_map
+    }
+
+    #[test]
+    fn open_well_beside_four_full_rows_is_tetris_ready() {
+        let game_map = board_with_well(2);
+        assert_eq!(board_metrics(&game_map).tetris_well, Some(2));
+    }
+
+    #[test]
+    fn covered_well_is_not_tetris_ready() {
+        let mut game_map = board_with_well(2);
+        game_map.set_cell(2, 3, Presence::Yes(GameColor::Gray));
+        assert_eq!(board_metrics(&game_map).tetris_well, None);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::GameState;
+use crate::event_log::EventLog;
+use crate::events::PieceLocked;
+use crate::game_constants::FONT_SIZE_LOG;
+use bevy::prelude::*;
+
+// Piece budgets offered by the menu
+const PIECE_LIMITS: [u32; 3] = [25, 40, 100];
+
+// Blitz practice: the run ends once this many pieces have locked
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PieceLimit(pub Option<u32>);
+
+impl PieceLimit {
+    pub fn name(&self) -> String {
+        match self.0 {
+            Some(limit) => format!("{} pieces", limit),
+            None => String::from("off"),
+        }
+    }
+
+    // Steps through off and every budget in turn
+    pub fn cycle(&mut self) {
+        let next = match self.0 {
+            None => Some(0),
+            Some(limit) => PIECE_LIMITS
+                .iter()
+                .position(|&budget| budget == limit)
+                .map(|index| index + 1)
+                .filter(|&index| index < PIECE_LIMITS.len()),
+        };
+        self.0 = next.map(|index| PIECE_LIMITS[index]);
+    }
+}
+
+// Pieces locked so far this run
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PiecesPlaced(pub u32);
+
+// Counts every lock and ends the run on the one that uses up the budget
+pub fn count_placed_pieces(
+    limit: Res<PieceLimit>,
+    mut locked: EventReader<PieceLocked>,
+    mut placed: ResMut<PiecesPlaced>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut log: ResMut<EventLog>,
+) {
+    for _ in locked.read() {
+        placed.0 += 1;
+        if limit.0 == Some(placed.0) {
+            log.push(format!("Piece limit of {} reached", placed.0));
+            game_state.set(GameState::GameOver);
+        }
+    }
+}
+
+// Marker component for the pieces used / budget readout
+#[derive(Component)]
+pub struct PieceLimitDisplay;
+
+pub fn setup_piece_limit_display(mut commands: Commands) {
+    let mut text_bundle = TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: FONT_SIZE_LOG,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        top: Val::Px(146.0),
+        left: Val::Px(10.0),
+        ..default()
+    });
+    text_bundle.visibility = Visibility::Hidden;
+
+    commands.spawn((text_bundle, PieceLimitDisplay));
+}
+
+pub fn update_piece_limit_display(
+    game_state: Res<State<GameState>>,
+    limit: Res<PieceLimit>,
+    placed: Res<PiecesPlaced>,
+    mut query_text: Query<(&mut Text, &mut Visibility), With<PieceLimitDisplay>>,
+) {
+    if (game_state.is_changed() || limit.is_changed() || placed.is_changed())
+        && let Some((mut text, mut visibility)) = query_text.iter_mut().next()
+    {
+        let Some(budget) = limit.0 else {
+            *visibility = Visibility::Hidden;
+            return;
+        };
+        text.sections[0].value = format!("Pieces: {}/{}", placed.0, budget);
+        *visibility = if *game_state.get() == GameState::MainMenu {
+            Visibility::Hidden
+        } else {
+            Visibility::Visible
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::Position;
+    use crate::game_types::PieceType;
+
+    fn lock_piece(app: &mut App) {
+        app.world.send_event(PieceLocked {
+            piece_type: PieceType::O,
+            position: Position { x: 3, y: 18 },
+            cells: [(4, 18), (5, 18), (4, 19), (5, 19)],
+            spin: false,
+        });
+        app.update();
+    }
+
+    #[test]
+    fn run_ends_on_the_lock_that_uses_up_the_budget() {
+        let mut app = App::new();
+        app.insert_resource(PieceLimit(Some(3)))
+            .init_resource::<EventLog>()
+            .init_resource::<PiecesPlaced>()
+            .init_resource::<NextState<GameState>>()
+            .add_event::<PieceLocked>()
+            .add_systems(Update, count_placed_pieces);
+
+        for placed in 1..=2 {
+            lock_piece(&mut app);
+            assert_eq!(app.world.resource::<PiecesPlaced>().0, placed);
+            assert_eq!(app.world.resource::<NextState<GameState>>().0, None);
+        }
+        lock_piece(&mut app);
+        assert_eq!(app.world.resource::<PiecesPlaced>().0, 3);
+        assert_eq!(
+            app.world.resource::<NextState<GameState>>().0,
+            Some(GameState::GameOver)
+        );
+    }
+}
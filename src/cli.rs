@@ -0,0 +1,171 @@
+use crate::game_types::BoardSize;
+use crate::garbage::GarbageChallenge;
+use crate::gravity_curve::LevelSelect;
+use crate::mode_summary::GameMode;
+use crate::practice::PracticeMode;
+use crate::seed::SeedEntry;
+use crate::survival::SurvivalMode;
+use bevy::prelude::*;
+
+// Smallest board a piece can spawn and turn on, and the largest worth drawing
+const MIN_BOARD: (usize, usize) = (4, 4);
+const MAX_BOARD: (usize, usize) = (40, 80);
+
+// Launch flags, each one presetting what the main menu would otherwise pick:
+// --seed N, --mode NAME, --start-level N and --board WxH. --simulate N plays
+// N pieces headless instead of opening the game. A flag that is unknown or
+// has a bad value is reported and left at its default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LaunchOptions {
+    pub seed: Option<u64>,
+    pub mode: Option<GameMode>,
+    pub start_level: Option<u32>,
+    pub board: Option<(usize, usize)>,
+    pub simulate: Option<u32>,
+}
+
+impl LaunchOptions {
+    // Reads flags from the arguments after the program name. Values may
+    // follow the flag or be joined to it with '='.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> LaunchOptions {
+        let mut options = LaunchOptions::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            if !matches!(
+                flag.as_str(),
+                "--seed" | "--mode" | "--start-level" | "--board" | "--simulate"
+            ) {
+                eprintln!("Ignoring unknown argument {}", flag);
+                continue;
+            }
+            let Some(value) = inline_value.or_else(|| args.next()) else {
+                eprintln!("Ignoring {} without a value", flag);
+                continue;
+            };
+            let parsed = match flag.as_str() {
+                "--seed" => value.parse().ok().map(|seed| options.seed = Some(seed)),
+                "--mode" => parse_mode(&value).map(|mode| options.mode = Some(mode)),
+                "--start-level" => value
+                    .parse()
+                    .ok()
+                    .map(|level| options.start_level = Some(level)),
+                "--simulate" => value
+                    .parse()
+                    .ok()
+                    .map(|pieces| options.simulate = Some(pieces)),
+                _ => parse_board(&value).map(|board| options.board = Some(board)),
+            };
+            if parsed.is_none() {
+                eprintln!("Ignoring invalid value {:?} for {}", value, flag);
+            }
+        }
+        options
+    }
+
+    // Inserts the resources the flags preset, ahead of the app's defaults
+    pub fn apply(&self, app: &mut App) {
+        if let Some(seed) = self.seed {
+            app.insert_resource(SeedEntry {
+                editing: false,
+                text: seed.to_string(),
+            });
+        }
+        if let Some(mode) = self.mode {
+            app.insert_resource(PracticeMode(mode == GameMode::Practice))
+                .insert_resource(GarbageChallenge(mode == GameMode::GarbageChallenge))
+                .insert_resource(SurvivalMode(mode == GameMode::Survival));
+        }
+        if let Some(level) = self.start_level {
+            app.insert_resource(LevelSelect(Some(level)));
+        }
+        if let Some(board) = self.board {
+            app.insert_resource(BoardSize(Some(board)));
+        }
+    }
+}
+
+// Modes a run can be launched in; puzzles are picked from the menu
+fn parse_mode(name: &str) -> Option<GameMode> {
+    [
+        GameMode::Marathon,
+        GameMode::Practice,
+        GameMode::GarbageChallenge,
+        GameMode::Survival,
+    ]
+    .into_iter()
+    .find(|mode| mode.name().eq_ignore_ascii_case(name))
+}
+
+// "WxH", with both sides inside MIN_BOARD..=MAX_BOARD
+fn parse_board(value: &str) -> Option<(usize, usize)> {
+    let (width, height) = value.split_once(['x', 'X'])?;
+    let (width, height): (usize, usize) = (width.parse().ok()?, height.parse().ok()?);
+    let fits = (MIN_BOARD.0..=MAX_BOARD.0).contains(&width)
+        && (MIN_BOARD.1..=MAX_BOARD.1).contains(&height);
+    fits.then_some((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn launch_flags_preset_the_menu_resources() {
+        let options = LaunchOptions::parse(args(
+            "--seed 42 --mode=survival --start-level 5 --board 12x24",
+        ));
+        assert_eq!(
+            options,
+            LaunchOptions {
+                seed: Some(42),
+                mode: Some(GameMode::Survival),
+                start_level: Some(5),
+                board: Some((12, 24)),
+                simulate: None,
+            }
+        );
+
+        let mut app = App::new();
+        options.apply(&mut app);
+        assert_eq!(app.world.resource::<SeedEntry>().seed(), Some(42));
+        assert_eq!(*app.world.resource::<SurvivalMode>(), SurvivalMode(true));
+        assert_eq!(*app.world.resource::<PracticeMode>(), PracticeMode(false));
+        assert_eq!(
+            *app.world.resource::<GarbageChallenge>(),
+            GarbageChallenge(false)
+        );
+        assert_eq!(*app.world.resource::<LevelSelect>(), LevelSelect(Some(5)));
+        assert_eq!(
+            *app.world.resource::<BoardSize>(),
+            BoardSize(Some((12, 24)))
+        );
+    }
+
+    #[test]
+    fn malformed_flags_fall_back_to_the_defaults() {
+        let options = LaunchOptions::parse(args(
+            "--seed forty-two --mode tetris --board 2x200 --start-level 3 --turbo",
+        ));
+        assert_eq!(
+            options,
+            LaunchOptions {
+                start_level: Some(3),
+                ..LaunchOptions::default()
+            }
+        );
+
+        let mut app = App::new();
+        options.apply(&mut app);
+        assert!(!app.world.contains_resource::<SeedEntry>());
+        assert!(!app.world.contains_resource::<SurvivalMode>());
+        assert!(!app.world.contains_resource::<BoardSize>());
+    }
+}
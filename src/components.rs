@@ -1,15 +1,33 @@
 use bevy::prelude::*;
 use crate::game_color::GameColor;
+use crate::game_types::PieceType;
 
-#[derive(Component, Default, Copy, Clone)]
+#[derive(Component, Default, Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Piece {
+    pub piece_type: PieceType,
     pub states: [u16; 4],
     pub color: GameColor,
     pub current_state: usize,
 }
 
-#[derive(Component, Default, Copy, Clone, PartialEq, Eq)]
+#[derive(Component, Default, Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Position {
     pub x: isize,
     pub y: isize,
 }
+
+// Render-only offset, in cells, between where the piece is drawn and its grid Position
+#[derive(Component, Default, Copy, Clone, Debug, PartialEq)]
+pub struct VisualOffset {
+    pub offset: Vec2,
+    pub last: Position,
+}
+
+impl VisualOffset {
+    pub fn new(position: Position) -> Self {
+        VisualOffset {
+            offset: Vec2::ZERO,
+            last: position,
+        }
+    }
+}
@@ -0,0 +1,727 @@
+use crate::components::{Piece, Position};
+use crate::event_log::EventLog;
+use crate::game_constants::{FONT_SIZE_STATUS, Z_BACKGROUND, Z_DEBUG};
+use crate::game_types::{BoardConfig, GameMap};
+use crate::rotation::{KickAttempt, LastKick};
+use crate::{Block, LockDelay, piece_cells};
+use bevy::prelude::*;
+use std::time::{Duration, Instant};
+
+// Developer overlays, all off by default and without effect on game logic
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DebugSettings {
+    pub show_rulers: bool,
+    pub show_event_log: bool,
+    pub show_rotation: bool,
+    /// Outline every kick candidate of the latest rotation attempt.
+    pub show_kicks: bool,
+    pub show_actions: bool,
+    pub show_heat_map: bool,
+    pub show_metrics: bool,
+    pub show_lock_resets: bool,
+    /// Gravity waits for the step key instead of the clock.
+    pub step_mode: bool,
+    /// Gravity and locking stop so the piece can be posed for a screenshot.
+    pub compose_mode: bool,
+    /// Keep the structured game event stream in the telemetry buffer.
+    pub record_telemetry: bool,
+}
+
+// Key that moves the piece down one row while step mode is on
+pub const STEP_KEY: KeyCode = KeyCode::F10;
+
+// Rows gravity moves the piece this frame in step mode: one per step key press
+pub fn stepped_rows(keyboard_input: &ButtonInput<KeyCode>) -> u32 {
+    u32::from(keyboard_input.just_pressed(STEP_KEY))
+}
+
+// Marker component for the step and compose mode indicator
+#[derive(Component)]
+pub struct GravityModeIndicator;
+
+pub fn setup_gravity_mode_indicator(mut commands: Commands) {
+    let mut text_bundle = TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: FONT_SIZE_STATUS,
+            color: Color::ORANGE,
+            ..default()
+        },
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        top: Val::Percent(22.0),
+        right: Val::Px(10.0),
+        ..default()
+    });
+    text_bundle.visibility = Visibility::Hidden;
+
+    commands.spawn((text_bundle, GravityModeIndicator));
+}
+
+pub fn update_gravity_mode_indicator(
+    settings: Res<DebugSettings>,
+    mut query_indicator: Query<(&mut Text, &mut Visibility), With<GravityModeIndicator>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for (mut text, mut visibility) in query_indicator.iter_mut() {
+        match gravity_mode_label(&settings) {
+            Some(label) => {
+                text.sections[0].value = label.to_string();
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+// Marker component for the row/column index labels
+#[derive(Component)]
+pub struct GridRuler;
+
+pub fn toggle_debug_settings(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<DebugSettings>,
+    mut log: ResMut<EventLog>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F1) {
+        settings.show_rulers = !settings.show_rulers;
+    }
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if keyboard_input.just_pressed(KeyCode::F2) {
+        if shift {
+            settings.record_telemetry = !settings.record_telemetry;
+            log.push(format!(
+                "Telemetry recording: {}",
+                settings.record_telemetry
+            ));
+        } else {
+            settings.show_event_log = !settings.show_event_log;
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::F3) {
+        if shift {
+            settings.show_kicks = !settings.show_kicks;
+        } else {
+            settings.show_rotation = !settings.show_rotation;
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::F4) {
+        settings.show_actions = !settings.show_actions;
+    }
+    if keyboard_input.just_pressed(KeyCode::F6) {
+        settings.show_heat_map = !settings.show_heat_map;
+    }
+    // Shift+F7 is the draw benchmark
+    if keyboard_input.just_pressed(KeyCode::F7) && !shift {
+        settings.show_metrics = !settings.show_metrics;
+    }
+    if keyboard_input.just_pressed(KeyCode::F8) {
+        settings.show_lock_resets = !settings.show_lock_resets;
+    }
+    if keyboard_input.just_pressed(KeyCode::F9) {
+        settings.step_mode = !settings.step_mode;
+    }
+    if keyboard_input.just_pressed(KeyCode::F11) {
+        settings.compose_mode = !settings.compose_mode;
+    }
+}
+
+// Label for the gravity mode indicator, or None when gravity runs normally
+pub fn gravity_mode_label(settings: &DebugSettings) -> Option<&'static str> {
+    if settings.compose_mode {
+        Some("COMPOSE MODE")
+    } else if settings.step_mode {
+        Some("STEP MODE")
+    } else {
+        None
+    }
+}
+
+// Labels column indices along the top row and row indices down the left
+// column, so on-screen cells can be matched to GameMap coordinates
+pub fn update_grid_rulers(
+    mut commands: Commands,
+    settings: Res<DebugSettings>,
+    board: Res<BoardConfig>,
+    query_rulers: Query<Entity, With<GridRuler>>,
+) {
+    if !settings.is_changed() && !board.is_changed() {
+        return;
+    }
+    for entity in query_rulers.iter() {
+        commands.entity(entity).despawn();
+    }
+    if !settings.show_rulers {
+        return;
+    }
+
+    for (x, y) in ruler_cells(&board) {
+        let label = if y == board.scroll_row { x } else { y };
+        let translation = board.cell_translation(x as isize, y as isize);
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    label.to_string(),
+                    TextStyle {
+                        font_size: 12.0,
+                        color: Color::rgba(1.0, 1.0, 1.0, 0.7),
+                        ..default()
+                    },
+                ),
+                transform: Transform::from_translation(translation.extend(Z_DEBUG)),
+                ..default()
+            },
+            GridRuler,
+        ));
+    }
+}
+
+// Cells that carry a ruler label: the whole top visible row plus the left
+// column, in logical coordinates
+pub fn ruler_cells(board: &BoardConfig) -> Vec<(usize, usize)> {
+    let top = board.scroll_row;
+    let columns = (0..board.width).map(move |x| (x, top));
+    let rows = (top + 1..top + board.visible_height).map(|y| (0, y));
+    columns.chain(rows).collect()
+}
+
+// Marker component for the rotation state label that follows the piece
+#[derive(Component)]
+pub struct RotationOverlay;
+
+pub fn setup_rotation_overlay(mut commands: Commands) {
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 14.0,
+                    color: Color::YELLOW,
+                    ..default()
+                },
+            ),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        RotationOverlay,
+    ));
+}
+
+// Text shown by the rotation overlay for a piece state and the last kick
+pub fn rotation_overlay_text(state: usize, kick: Option<(isize, isize)>) -> String {
+    match kick {
+        Some((dx, dy)) => format!("state {}\nkick ({}, {})", state, dx, dy),
+        None => format!("state {}\nkick -", state),
+    }
+}
+
+// Shows the active piece's rotation state and the kick offset of the last
+// successful rotation just above the piece
+pub fn update_rotation_overlay(
+    settings: Res<DebugSettings>,
+    board: Res<BoardConfig>,
+    last_kick: Res<LastKick>,
+    query_piece: Query<(&Piece, &Position)>,
+    mut query_overlay: Query<(&mut Text, &mut Transform, &mut Visibility), With<RotationOverlay>>,
+) {
+    let Ok((mut text, mut transform, mut visibility)) = query_overlay.get_single_mut() else {
+        return;
+    };
+    let piece = query_piece
+        .get_single()
+        .ok()
+        .filter(|_| settings.show_rotation);
+    let Some((piece, position)) = piece else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    *visibility = Visibility::Visible;
+    text.sections[0].value = rotation_overlay_text(piece.current_state, last_kick.0);
+    let anchor = board.cell_translation(position.x + 1, position.y - 1);
+    transform.translation = anchor.extend(Z_DEBUG);
+}
+
+// Marker component for the cells drawn by the kick overlay
+#[derive(Component)]
+pub struct KickOverlay;
+
+// How long the kick overlay stays up after a rotation attempt
+const KICK_OVERLAY_SECS: f32 = 0.75;
+
+// Tint for one kick candidate's cells: faint for the ones passed over,
+// bright for the one the rotation used
+pub fn kick_color(accepted: bool) -> Color {
+    if accepted {
+        Color::rgba(0.2, 1.0, 0.2, 0.6)
+    } else {
+        Color::rgba(1.0, 1.0, 1.0, 0.12)
+    }
+}
+
+// Briefly draws the rotated piece at each kick candidate of the latest
+// rotation attempt, highlighting the one that was accepted
+pub fn update_kick_overlay(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<DebugSettings>,
+    board: Res<BoardConfig>,
+    attempt: Res<KickAttempt>,
+    mut shown: Local<Timer>,
+    query_overlay: Query<Entity, With<KickOverlay>>,
+) {
+    if attempt.is_changed() {
+        *shown = Timer::from_seconds(KICK_OVERLAY_SECS, TimerMode::Once);
+    }
+    let expired = shown.tick(time.delta()).just_finished();
+    if !attempt.is_changed() && !settings.is_changed() && !expired {
+        return;
+    }
+    for entity in query_overlay.iter() {
+        commands.entity(entity).despawn();
+    }
+    let Some((piece, origin)) = attempt.rotated.filter(|_| settings.show_kicks) else {
+        return;
+    };
+    if shown.finished() {
+        return;
+    }
+
+    for &(dx, dy) in &attempt.candidates {
+        let kicked = Position {
+            x: origin.x + dx,
+            y: origin.y + dy,
+        };
+        let accepted = attempt.accepted == Some((dx, dy));
+        for (x, y) in piece_cells(&piece, &kicked) {
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: kick_color(accepted),
+                        custom_size: Some(Vec2::splat(board.cell_size * 0.8)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(
+                        board.cell_translation(x, y).extend(Z_DEBUG),
+                    ),
+                    ..default()
+                },
+                KickOverlay,
+            ));
+        }
+    }
+}
+
+// Frames the draw benchmark averages over
+const BENCHMARK_FRAMES: u32 = 300;
+// Rows left empty at the top of the benchmark board
+const BENCHMARK_EMPTY_ROWS: usize = 4;
+
+// A draw_blocks benchmark in progress. The board is swapped for a fixed
+// pattern, with compose mode holding the piece still, and the time spent
+// drawing is summed frame by frame.
+#[derive(Resource, Debug, Default)]
+pub struct DrawBenchmark {
+    /// The board and compose mode to put back afterwards.
+    saved: Option<(GameMap, bool)>,
+    frames: u32,
+    total: Duration,
+    started: Option<Instant>,
+}
+
+impl DrawBenchmark {
+    // Mean draw time per frame so far
+    pub fn average(&self) -> Option<Duration> {
+        (self.frames > 0).then(|| self.total / self.frames)
+    }
+}
+
+// The fixed benchmark board: every row below the top few filled but for one
+// hole, stepping a column to the right each row
+pub fn benchmark_board(width: usize, height: usize) -> GameMap {
+    let mut map = GameMap::new(width, height);
+    let rows = height.saturating_sub(BENCHMARK_EMPTY_ROWS);
+    let holes: Vec<usize> = (0..rows).map(|row| row % width.max(1)).collect();
+    map.insert_garbage_rows(&holes);
+    map
+}
+
+// Shift+F7 starts a benchmark of drawing the board
+pub fn start_draw_benchmark(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut benchmark: ResMut<DrawBenchmark>,
+    mut game_map: ResMut<GameMap>,
+    mut settings: ResMut<DebugSettings>,
+    mut log: ResMut<EventLog>,
+) {
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !shift || !keyboard_input.just_pressed(KeyCode::F7) || benchmark.saved.is_some() {
+        return;
+    }
+    let mut board = benchmark_board(game_map.width(), game_map.height());
+    board.wrap_edges = game_map.wrap_edges;
+    *benchmark = DrawBenchmark {
+        saved: Some((
+            std::mem::replace(&mut *game_map, board),
+            settings.compose_mode,
+        )),
+        ..default()
+    };
+    settings.compose_mode = true;
+    log.push(format!(
+        "Benchmarking draw_blocks over {} frames",
+        BENCHMARK_FRAMES
+    ));
+}
+
+pub fn begin_draw_timing(mut benchmark: ResMut<DrawBenchmark>) {
+    if benchmark.saved.is_some() {
+        benchmark.started = Some(Instant::now());
+    }
+}
+
+// Adds up this frame's drawing, the queued sprite spawns included, and
+// reports and restores the board once enough frames are in
+pub fn end_draw_timing(
+    mut benchmark: ResMut<DrawBenchmark>,
+    mut game_map: ResMut<GameMap>,
+    mut settings: ResMut<DebugSettings>,
+    query_blocks: Query<(), With<Block>>,
+    mut log: ResMut<EventLog>,
+) {
+    let Some(started) = benchmark.started.take() else {
+        return;
+    };
+    benchmark.total += started.elapsed();
+    benchmark.frames += 1;
+    if benchmark.frames < BENCHMARK_FRAMES {
+        return;
+    }
+    if let Some(average) = benchmark.average() {
+        log.push(format!(
+            "draw_blocks: {:.3} ms per frame over {} frames, {} sprites per frame",
+            average.as_secs_f64() * 1000.0,
+            benchmark.frames,
+            query_blocks.iter().count()
+        ));
+    }
+    if let Some((map, compose_mode)) = benchmark.saved.take() {
+        *game_map = map;
+        settings.compose_mode = compose_mode;
+    }
+}
+
+// Marker component for the bar showing lock delay resets left
+#[derive(Component)]
+pub struct LockResetBar;
+
+// Height of the lock reset bar in pixels, and its length in cells when full
+const LOCK_RESET_BAR_HEIGHT: f32 = 4.0;
+const LOCK_RESET_BAR_CELLS: f32 = 3.0;
+
+pub fn setup_lock_reset_bar(mut commands: Commands) {
+    commands.spawn((
+        SpriteBundle {
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        LockResetBar,
+    ));
+}
+
+// Fraction of the full bar drawn for the resets left
+pub fn lock_reset_fraction(remaining: u32, max_resets: u32) -> f32 {
+    if max_resets == 0 {
+        return 0.0;
+    }
+    remaining as f32 / max_resets as f32
+}
+
+// Draws a bar under the active piece that shrinks, and turns from green to
+// red, as move resets of the lock delay are used up
+pub fn update_lock_reset_bar(
+    settings: Res<DebugSettings>,
+    board: Res<BoardConfig>,
+    lock_delay: Res<LockDelay>,
+    query_piece: Query<&Position, With<Piece>>,
+    mut query_bar: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<LockResetBar>>,
+) {
+    let Ok((mut sprite, mut transform, mut visibility)) = query_bar.get_single_mut() else {
+        return;
+    };
+    let position = query_piece
+        .get_single()
+        .ok()
+        .filter(|_| settings.show_lock_resets);
+    let Some(position) = position else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    *visibility = Visibility::Visible;
+    let fraction = lock_reset_fraction(lock_delay.remaining_resets(), lock_delay.max_resets);
+    let length = board.cell_size * LOCK_RESET_BAR_CELLS * fraction;
+    sprite.custom_size = Some(Vec2::new(length, LOCK_RESET_BAR_HEIGHT));
+    sprite.color = Color::rgb(1.0 - fraction, fraction, 0.0);
+    let anchor = board.cell_translation(position.x + 1, position.y + 3);
+    let below = board.screen_dy(1) as f32 * board.cell_size / 2.0;
+    transform.translation = (anchor + Vec2::new(0.0, below)).extend(Z_DEBUG);
+}
+
+// Marker component for the column tints and height labels of the heat map
+#[derive(Component)]
+pub struct HeatMapOverlay;
+
+// Tint for a column whose stack fills `fraction` of the board: blue when
+// empty through to red when full
+pub fn heat_color(fraction: f32) -> Color {
+    let hue = 240.0 * (1.0 - fraction.clamp(0.0, 1.0));
+    Color::hsla(hue, 1.0, 0.5, 0.25)
+}
+
+// Tints each column by its stack height and labels the height along the
+// bottom visible row, rebuilt whenever the board changes
+pub fn update_heat_map(
+    mut commands: Commands,
+    settings: Res<DebugSettings>,
+    board: Res<BoardConfig>,
+    game_map: Res<GameMap>,
+    query_overlay: Query<Entity, With<HeatMapOverlay>>,
+) {
+    if !settings.is_changed() && !board.is_changed() && !game_map.is_changed() {
+        return;
+    }
+    for entity in query_overlay.iter() {
+        commands.entity(entity).despawn();
+    }
+    if !settings.show_heat_map {
+        return;
+    }
+
+    let top = board.scroll_row as isize;
+    let bottom = top + board.visible_height as isize - 1;
+    for (x, height) in game_map.column_heights().into_iter().enumerate() {
+        let upper = board.cell_translation(x as isize, top);
+        let lower = board.cell_translation(x as isize, bottom);
+        let column_height = (upper.y - lower.y).abs() + board.cell_size;
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: heat_color(height as f32 / board.height as f32),
+                    custom_size: Some(Vec2::new(board.cell_size, column_height)),
+                    ..default()
+                },
+                transform: Transform::from_translation(
+                    ((upper + lower) / 2.0).extend(Z_BACKGROUND),
+                ),
+                ..default()
+            },
+            HeatMapOverlay,
+        ));
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    height.to_string(),
+                    TextStyle {
+                        font_size: 12.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                transform: Transform::from_translation(lower.extend(Z_DEBUG)),
+                ..default()
+            },
+            HeatMapOverlay,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_types::PieceType;
+    use crate::hold::HoldSwapAnimation;
+    use crate::line_clear::{LineClearAnimation, RowCollapse};
+    use crate::rotation::rotate_cw;
+    use crate::rules::RulesConfig;
+    use crate::theme::ColorTheme;
+    use crate::visual::VisualSettings;
+    use crate::{Level, draw_blocks};
+
+    fn ruler_count(app: &mut App) -> usize {
+        app.update();
+        let mut query = app.world.query_filtered::<(), With<GridRuler>>();
+        query.iter(&app.world).count()
+    }
+
+    #[test]
+    fn rulers_are_spawned_only_while_enabled() {
+        let mut app = App::new();
+        app.init_resource::<DebugSettings>()
+            .init_resource::<BoardConfig>()
+            .add_systems(Update, update_grid_rulers);
+        assert_eq!(ruler_count(&mut app), 0);
+
+        app.world.resource_mut::<DebugSettings>().show_rulers = true;
+        let board = BoardConfig::default();
+        assert_eq!(
+            ruler_count(&mut app),
+            board.width + board.visible_height - 1
+        );
+
+        app.world.resource_mut::<DebugSettings>().show_rulers = false;
+        assert_eq!(ruler_count(&mut app), 0);
+    }
+
+    #[test]
+    fn rotation_overlay_shows_the_state_and_kick() {
+        let game_map = GameMap::default();
+        let mut piece = Piece::new(PieceType::I);
+        piece.current_state = 1;
+        let position = Position { x: -2, y: 5 };
+        let rotation = rotate_cw(&piece, &position, &game_map, &RulesConfig::default()).unwrap();
+        piece.current_state = rotation.state;
+
+        let mut app = App::new();
+        app.insert_resource(DebugSettings {
+            show_rotation: true,
+            ..DebugSettings::default()
+        })
+        .init_resource::<BoardConfig>()
+        .insert_resource(LastKick(Some(rotation.kick)))
+        .add_systems(Startup, setup_rotation_overlay)
+        .add_systems(Update, update_rotation_overlay);
+        app.world.spawn((piece, rotation.position));
+        app.update();
+
+        let mut query = app.world.query_filtered::<&Text, With<RotationOverlay>>();
+        let (dx, dy) = rotation.kick;
+        assert_eq!(
+            query.single(&app.world).sections[0].value,
+            format!("state 2\nkick ({}, {})", dx, dy)
+        );
+    }
+
+    #[test]
+    fn heat_map_labels_each_column_with_its_height() {
+        let game_map = GameMap::from_compact_string("..../.r../.r.g/br.g").unwrap();
+        assert_eq!(game_map.column_heights(), vec![1, 3, 0, 2]);
+
+        let mut app = App::new();
+        app.insert_resource(DebugSettings {
+            show_heat_map: true,
+            ..DebugSettings::default()
+        })
+        .insert_resource(BoardConfig::sized(4, 4))
+        .insert_resource(game_map)
+        .add_systems(Update, update_heat_map);
+        app.update();
+
+        let mut query = app
+            .world
+            .query_filtered::<(&Text, &Transform), With<HeatMapOverlay>>();
+        let mut labels: Vec<_> = query
+            .iter(&app.world)
+            .map(|(text, transform)| (transform.translation.x, text.sections[0].value.clone()))
+            .collect();
+        labels.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let heights: Vec<_> = labels.into_iter().map(|(_, height)| height).collect();
+        assert_eq!(heights, ["1", "3", "0", "2"]);
+    }
+
+    #[test]
+    fn lock_reset_bar_shrinks_with_each_move_reset() {
+        let mut app = App::new();
+        app.insert_resource(DebugSettings {
+            show_lock_resets: true,
+            ..DebugSettings::default()
+        })
+        .init_resource::<BoardConfig>()
+        .init_resource::<LockDelay>()
+        .add_systems(Startup, setup_lock_reset_bar)
+        .add_systems(Update, update_lock_reset_bar);
+        app.world
+            .spawn((Position { x: 3, y: 14 }, Piece::new(PieceType::T)));
+        app.world.resource_mut::<LockDelay>().descended(14);
+        let bar_length = |app: &mut App| {
+            app.update();
+            let mut query = app.world.query_filtered::<&Sprite, With<LockResetBar>>();
+            query.single(&app.world).custom_size.unwrap().x
+        };
+
+        let mut previous = bar_length(&mut app);
+        for used in 1..=3 {
+            {
+                let mut lock_delay = app.world.resource_mut::<LockDelay>();
+                // Grounded, so the delay is running when the move resets it
+                lock_delay.timer.tick(Duration::from_millis(100));
+                lock_delay.move_reset();
+                assert_eq!(lock_delay.remaining_resets(), lock_delay.max_resets - used);
+            }
+            let length = bar_length(&mut app);
+            assert!(
+                length < previous,
+                "reset {} left the bar at {}",
+                used,
+                length
+            );
+            previous = length;
+        }
+        let lock_delay = app.world.resource::<LockDelay>();
+        let full = BoardConfig::default().cell_size * LOCK_RESET_BAR_CELLS;
+        let expected =
+            full * lock_reset_fraction(lock_delay.remaining_resets(), lock_delay.max_resets);
+        assert_eq!(previous, expected);
+    }
+
+    #[test]
+    fn draw_benchmark_times_a_filled_board_and_restores_the_game() {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<EventLog>()
+            .init_resource::<DebugSettings>()
+            .init_resource::<DrawBenchmark>()
+            .init_resource::<BoardConfig>()
+            .init_resource::<GameMap>()
+            .init_resource::<VisualSettings>()
+            .init_resource::<LineClearAnimation>()
+            .init_resource::<RowCollapse>()
+            .init_resource::<ColorTheme>()
+            .init_resource::<Level>()
+            .init_resource::<HoldSwapAnimation>()
+            .add_systems(
+                Update,
+                (
+                    start_draw_benchmark,
+                    begin_draw_timing,
+                    draw_blocks,
+                    end_draw_timing,
+                )
+                    .chain(),
+            );
+        let mut keyboard_input = app.world.resource_mut::<ButtonInput<KeyCode>>();
+        keyboard_input.press(KeyCode::ShiftLeft);
+        keyboard_input.press(KeyCode::F7);
+        app.update();
+        assert!(app.world.resource::<DebugSettings>().compose_mode);
+        let board = BoardConfig::default();
+        assert_eq!(
+            *app.world.resource::<GameMap>(),
+            benchmark_board(board.width, board.height)
+        );
+
+        app.world.resource_mut::<ButtonInput<KeyCode>>().clear();
+        for _ in 1..BENCHMARK_FRAMES {
+            app.update();
+        }
+        let benchmark = app.world.resource::<DrawBenchmark>();
+        assert_eq!(benchmark.frames, BENCHMARK_FRAMES);
+        let average = benchmark.average().unwrap().as_secs_f64();
+        assert!(average.is_finite() && average > 0.0, "{}", average);
+        assert_eq!(*app.world.resource::<GameMap>(), GameMap::default());
+        assert!(!app.world.resource::<DebugSettings>().compose_mode);
+    }
+}
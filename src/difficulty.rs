@@ -0,0 +1,112 @@
+use crate::rules::RulesConfig;
+use crate::{AutoShift, Level, LockDelay};
+use bevy::prelude::*;
+use std::time::Duration;
+
+// The preset the current run was started with
+#[derive(Resource, Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+// Bundle of tunables applied when a new game starts
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DifficultyConfig {
+    pub start_level: u32,
+    pub lock_delay: Duration,
+    pub das: Duration,
+    pub arr: Duration,
+    pub spawn_push: bool,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    pub fn config(self) -> DifficultyConfig {
+        match self {
+            Difficulty::Easy => DifficultyConfig {
+                start_level: 0,
+                lock_delay: Duration::from_millis(1000),
+                das: Duration::from_millis(250),
+                arr: Duration::from_millis(60),
+                spawn_push: true,
+            },
+            Difficulty::Normal => DifficultyConfig {
+                start_level: 0,
+                lock_delay: Duration::from_millis(500),
+                das: Duration::from_millis(170),
+                arr: Duration::from_millis(50),
+                spawn_push: false,
+            },
+            Difficulty::Hard => DifficultyConfig {
+                start_level: 5,
+                lock_delay: Duration::from_millis(250),
+                das: Duration::from_millis(117),
+                arr: Duration::from_millis(17),
+                spawn_push: false,
+            },
+        }
+    }
+}
+
+impl DifficultyConfig {
+    // Writes the preset into the resources that drive a run
+    pub fn apply(
+        &self,
+        level: &mut Level,
+        lock_delay: &mut LockDelay,
+        auto_shift: &mut AutoShift,
+        rules: &mut RulesConfig,
+    ) {
+        level.value = self.start_level;
+        level.lines_cleared_in_level = 0;
+        lock_delay.set_duration(self.lock_delay);
+        auto_shift.das = self.das;
+        auto_shift.arr = self.arr;
+        rules.spawn_push = self.spawn_push;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gravity_curve::GravityCurve;
+    use crate::gravity_for_level;
+    use crate::practice::GravityMultiplier;
+
+    #[test]
+    fn hard_preset_sets_lock_delay_gravity_and_spawn_push() {
+        let preset = Difficulty::Hard.config();
+        let mut level = Level::default();
+        let mut lock_delay = LockDelay::default();
+        let mut auto_shift = AutoShift::default();
+        let mut rules = RulesConfig {
+            spawn_push: true,
+            ..RulesConfig::default()
+        };
+
+        preset.apply(&mut level, &mut lock_delay, &mut auto_shift, &mut rules);
+
+        assert_eq!(level.value, preset.start_level);
+        assert_eq!(lock_delay.timer.duration(), preset.lock_delay);
+        assert_eq!((auto_shift.das, auto_shift.arr), (preset.das, preset.arr));
+        assert!(!rules.spawn_push);
+        let curve = GravityCurve::default();
+        let multiplier = GravityMultiplier::default();
+        assert!(
+            gravity_for_level(level.value, &curve, &multiplier)
+                < gravity_for_level(Difficulty::Normal.config().start_level, &curve, &multiplier)
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use crate::debug::DebugSettings;
+use crate::events::{
+    GameOver, LevelUp, LinesCleared, PerfectClear, PieceHeld, PieceLocked, PieceMovedDown,
+    PieceSpawned, Spin,
+};
+use crate::game_constants::FONT_SIZE_LOG;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+const EVENT_LOG_CAPACITY: usize = 10;
+
+// Most recent game events, oldest first
+#[derive(Resource, Debug, Default)]
+pub struct EventLog {
+    pub lines: VecDeque<String>,
+}
+
+impl EventLog {
+    pub fn push(&mut self, line: String) {
+        if self.lines.len() == EVENT_LOG_CAPACITY {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+}
+
+// Marker component for the on-screen event log panel
+#[derive(Component)]
+pub struct EventLogPanel;
+
+#[allow(clippy::too_many_arguments)]
+pub fn record_events(
+    mut log: ResMut<EventLog>,
+    mut spawned: EventReader<PieceSpawned>,
+    mut moved_down: EventReader<PieceMovedDown>,
+    mut locked: EventReader<PieceLocked>,
+    mut held: EventReader<PieceHeld>,
+    mut spins: EventReader<Spin>,
+    mut lines_cleared: EventReader<LinesCleared>,
+    mut perfect_clear: EventReader<PerfectClear>,
+    mut level_up: EventReader<LevelUp>,
+    mut game_over: EventReader<GameOver>,
+) {
+    for _ in spawned.read() {
+        log.push("Spawned new piece".to_string());
+    }
+    for event in moved_down.read() {
+        log.push(format!("Piece moved down to y: {}", event.y));
+    }
+    for event in locked.read() {
+        log.push(format!(
+            "{:?} piece landed at ({}, {})",
+            event.piece_type, event.position.x, event.position.y
+        ));
+    }
+    for event in held.read() {
+        log.push(format!(
+            "Held {:?}, {:?} comes in",
+            event.outgoing.piece_type, event.incoming
+        ));
+    }
+    for event in spins.read() {
+        log.push(format!(
+            "{:?}-spin, {} lines",
+            event.piece_type, event.lines
+        ));
+    }
+    for event in lines_cleared.read() {
+        log.push(format!("Cleared {} lines", event.count));
+    }
+    for _ in perfect_clear.read() {
+        log.push("Perfect clear!".to_string());
+    }
+    for event in level_up.read() {
+        log.push(format!("Level up! Now level {}", event.level));
+    }
+    for _ in game_over.read() {
+        log.push("Game over".to_string());
+    }
+}
+
+pub fn setup_event_log_panel(mut commands: Commands) {
+    let mut text_bundle = TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: FONT_SIZE_LOG,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        bottom: Val::Px(40.0),
+        left: Val::Px(10.0),
+        ..default()
+    })
+    .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6));
+    text_bundle.visibility = Visibility::Hidden;
+
+    commands.spawn((text_bundle, EventLogPanel));
+}
+
+pub fn update_event_log_panel(
+    log: Res<EventLog>,
+    settings: Res<DebugSettings>,
+    mut query_panel: Query<(&mut Text, &mut Visibility), With<EventLogPanel>>,
+) {
+    if (log.is_changed() || settings.is_changed())
+        && let Some((mut text, mut visibility)) = query_panel.iter_mut().next()
+    {
+        text.sections[0].value = log.lines.iter().cloned().collect::<Vec<_>>().join("\n");
+        *visibility = if settings.show_event_log {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_cleared_event_is_logged_and_shown() {
+        let mut app = App::new();
+        app.init_resource::<EventLog>()
+            .init_resource::<DebugSettings>()
+            .add_event::<PieceSpawned>()
+            .add_event::<PieceMovedDown>()
+            .add_event::<PieceLocked>()
+            .add_event::<PieceHeld>()
+            .add_event::<Spin>()
+            .add_event::<LinesCleared>()
+            .add_event::<PerfectClear>()
+            .add_event::<LevelUp>()
+            .add_event::<GameOver>()
+            .add_systems(Startup, setup_event_log_panel)
+            .add_systems(Update, (record_events, update_event_log_panel).chain());
+
+        app.world.send_event(LinesCleared { count: 2 });
+        app.update();
+
+        let log = app.world.resource::<EventLog>();
+        assert_eq!(
+            log.lines.back().map(String::as_str),
+            Some("Cleared 2 lines")
+        );
+        let mut query = app.world.query_filtered::<&Text, With<EventLogPanel>>();
+        let text = query.single(&app.world);
+        assert!(text.sections[0].value.contains("Cleared 2 lines"));
+    }
+}
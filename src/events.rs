@@ -0,0 +1,70 @@
+use crate::components::{Piece, Position};
+use crate::game_types::PieceType;
+use bevy::prelude::*;
+
+// A new active piece entered the board
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PieceSpawned;
+
+// Gravity pulled the active piece down one row
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PieceMovedDown {
+    pub y: isize,
+}
+
+// The active piece landed and was written into the game map
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PieceLocked {
+    pub piece_type: PieceType,
+    pub position: Position,
+    pub cells: [(isize, isize); 4],
+    /// Rotated into a spot it could not move out of, under the all spin rule.
+    pub spin: bool,
+}
+
+// A piece locked by a spin, clearing `lines` rows
+#[derive(Event, Debug, Clone, Copy)]
+pub struct Spin {
+    pub piece_type: PieceType,
+    pub lines: u32,
+}
+
+// The active piece went into the hold slot and `incoming` took its place
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PieceHeld {
+    pub outgoing: Piece,
+    /// Where the outgoing piece was when it was held.
+    pub from: Position,
+    pub incoming: PieceType,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct LinesCleared {
+    pub count: u32,
+}
+
+// A clear left the board completely empty
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PerfectClear;
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct LevelUp {
+    pub level: u32,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct GameOver;
+
+// Fires PieceSpawned for every piece entity added this frame
+pub fn emit_piece_spawned(
+    query_new_pieces: Query<(), Added<Piece>>,
+    mut spawned: EventWriter<PieceSpawned>,
+) {
+    for _ in query_new_pieces.iter() {
+        spawned.send(PieceSpawned);
+    }
+}
+
+pub fn emit_game_over(mut game_over: EventWriter<GameOver>) {
+    game_over.send(GameOver);
+}
@@ -1,5 +1,3 @@
-use bevy::prelude::Color;
-
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
 pub enum GameColor {
     #[default] Red,
@@ -10,21 +8,54 @@ pub enum GameColor {
     Orange,
     Purple,
     Gray,
+    DarkGray,
     Pink,
 }
 
-impl From<GameColor> for Color {
-    fn from(game_color: GameColor) -> Self {
-        match game_color {
-            GameColor::Red => Color::rgb_u8(255, 0, 0),
-            GameColor::Green => Color::rgb_u8(0, 255, 0),
-            GameColor::Blue => Color::rgb_u8(0, 0, 255),
-            GameColor::Yellow => Color::rgb_u8(255, 255, 0),
-            GameColor::Cyan => Color::rgb_u8(0, 255, 255),
-            GameColor::Orange => Color::rgb_u8(255, 165, 0),
-            GameColor::Purple => Color::rgb_u8(128, 0, 128),
-            GameColor::Gray => Color::rgb_u8(128, 128, 128),
-            GameColor::Pink => Color::rgb_u8(255, 192, 203),
+// Colors a randomly colored piece may take; the grays are left out so pieces
+// stay distinct from the background and from garbage
+pub const PIECE_COLORS: [GameColor; 8] = [
+    GameColor::Red,
+    GameColor::Green,
+    GameColor::Blue,
+    GameColor::Yellow,
+    GameColor::Cyan,
+    GameColor::Orange,
+    GameColor::Purple,
+    GameColor::Pink,
+];
+
+impl GameColor {
+    const ALL: [GameColor; 10] = [
+        GameColor::Red,
+        GameColor::Green,
+        GameColor::Blue,
+        GameColor::Yellow,
+        GameColor::Cyan,
+        GameColor::Orange,
+        GameColor::Purple,
+        GameColor::Gray,
+        GameColor::DarkGray,
+        GameColor::Pink,
+    ];
+
+    // Single-character code used when a board is saved as text
+    pub fn code(&self) -> char {
+        match self {
+            GameColor::Red => 'r',
+            GameColor::Green => 'g',
+            GameColor::Blue => 'b',
+            GameColor::Yellow => 'y',
+            GameColor::Cyan => 'c',
+            GameColor::Orange => 'o',
+            GameColor::Purple => 'p',
+            GameColor::Gray => 'a',
+            GameColor::DarkGray => 'd',
+            GameColor::Pink => 'k',
         }
     }
+
+    pub fn from_code(code: char) -> Option<GameColor> {
+        GameColor::ALL.into_iter().find(|color| color.code() == code)
+    }
 }
@@ -1,4 +1,4 @@
-pub const TITLE: &'static str = "Tetris in Rust";
+pub const TITLE: &str = "Tetris in Rust";
 
 pub const NUM_BLOCKS_X: usize = 10;
 pub const NUM_BLOCKS_Y: usize = 18;
@@ -13,3 +13,34 @@ pub const HEIGHT: u32 = NUM_BLOCKS_Y as u32 * TEXTURE_SIZE;
 pub const NUM_LEVELS: usize = 10;
 pub const LEVEL_TIMES: [usize; NUM_LEVELS] = [3000, 850, 700, 600, 500, 400, 300, 250, 221, 190];
 
+// Fastest gravity allowed, one frame at 60 Hz, so the timer period never reaches zero
+pub const MIN_GRAVITY_MS: u64 = 16;
+
+
+// Logical rows of a tall board; the window still shows NUM_BLOCKS_Y of them
+pub const TALL_BOARD_ROWS: usize = 40;
+
+// Rows a blocked spawn may be pushed upward when spawn push is enabled
+pub const SPAWN_PUSH_ROWS: isize = 2;
+
+
+// Depth of each layer of board sprites, back to front. UI nodes render after
+// every sprite, so the HUD is never hidden by the board.
+pub const Z_BACKGROUND: f32 = -0.5;
+pub const Z_TRAJECTORY: f32 = -0.2;
+pub const Z_GHOST: f32 = -0.1;
+pub const Z_BLOCKS: f32 = 0.0;
+pub const Z_HINTS: f32 = 0.5;
+pub const Z_PARTICLES: f32 = 0.6;
+pub const Z_PIECE: f32 = 0.8;
+pub const Z_OVERLAY: f32 = 1.0;
+pub const Z_DEBUG: f32 = 2.0;
+
+
+// Font sizes at the reference window height; UiScale resizes them with the window
+pub const FONT_SIZE_BANNER: f32 = 100.0;
+pub const FONT_SIZE_HUD: f32 = 40.0;
+pub const FONT_SIZE_PANEL: f32 = 30.0;
+pub const FONT_SIZE_STATUS: f32 = 24.0;
+pub const FONT_SIZE_SMALL: f32 = 16.0;
+pub const FONT_SIZE_LOG: f32 = 14.0;
@@ -1,11 +1,12 @@
 use crate::game_color::GameColor;
-use crate::game_constants::{NUM_BLOCKS_X, NUM_BLOCKS_Y};
+use crate::game_constants::{NUM_BLOCKS_X, NUM_BLOCKS_Y, TEXTURE_SIZE};
 use bevy::prelude::*;
 
 pub type PieceMatrix = [[Presence; 4]; 4];
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
 pub enum PieceType {
+    #[default]
     L,
     J,
     S,
@@ -15,19 +16,594 @@ pub enum PieceType {
     O,
 }
 
+impl PieceType {
+    pub const ALL: [PieceType; 7] = [
+        PieceType::L,
+        PieceType::J,
+        PieceType::S,
+        PieceType::Z,
+        PieceType::T,
+        PieceType::I,
+        PieceType::O,
+    ];
+
+    // The type after this one in ALL, wrapping from O back to L
+    pub fn next(self) -> PieceType {
+        PieceType::ALL[(self as usize + 1) % PieceType::ALL.len()]
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Presence {
     No,
     Yes(GameColor),
 }
 
-#[derive(Resource)]
-pub struct GameMap(pub Vec<Vec<Presence>>);
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct GameMap {
+    pub rows: Vec<Vec<Presence>>,
+    /// Experimental: the left and right edges meet, so columns wrap around.
+    pub wrap_edges: bool,
+    /// Cells outside the playable shape of the board, as (x, y). They read
+    /// as solid, never take a block and are left out of the full row check.
+    pub blocked: Vec<(usize, usize)>,
+}
 
 impl Default for GameMap {
     fn default() -> Self {
-        GameMap(vec![vec![Presence::No; NUM_BLOCKS_X]; NUM_BLOCKS_Y])
+        GameMap::new(NUM_BLOCKS_X, NUM_BLOCKS_Y)
+    }
+}
+
+impl GameMap {
+    pub fn new(width: usize, height: usize) -> Self {
+        GameMap {
+            rows: vec![vec![Presence::No; width]; height],
+            wrap_edges: false,
+            blocked: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.rows.first().map_or(0, Vec::len)
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    // The board column x falls in: x itself when inside the walls, x wrapped
+    // around the board when the edges wrap, or None when it is off the board
+    pub fn column(&self, x: isize) -> Option<isize> {
+        let width = self.width() as isize;
+        if self.wrap_edges && width > 0 {
+            Some(x.rem_euclid(width))
+        } else {
+            (0..width).contains(&x).then_some(x)
+        }
+    }
+
+    // The cells with each x moved into its board column, for drawing pieces
+    // that straddle the seam of a wrapping board
+    pub fn wrapped_cells(&self, cells: Vec<(isize, isize)>) -> Vec<(isize, isize)> {
+        cells
+            .into_iter()
+            .map(|(x, y)| (self.column(x).unwrap_or(x), y))
+            .collect()
+    }
+
+    // Whether (x, y) lies outside the playable shape of the board
+    pub fn is_blocked(&self, x: usize, y: usize) -> bool {
+        self.blocked.contains(&(x, y))
+    }
+
+    // Takes (x, y) out of the playable shape for good, emptying it
+    pub fn block_cell(&mut self, x: usize, y: usize) {
+        if y < self.height() && x < self.width() && !self.is_blocked(x, y) {
+            self.rows[y][x] = Presence::No;
+            self.blocked.push((x, y));
+        }
+    }
+
+    // Stacks `rows`, bottom first, against the floor and empties the rows
+    // left over at the top. Rows move whole while the blocked cells stay
+    // where they are, so a block that lands on a blocked cell is dropped.
+    // Returns true if a filled row did not fit.
+    fn restack_rows(&mut self, rows: Vec<Vec<Presence>>) -> bool {
+        let (width, height) = (self.width(), self.height());
+        let overflowed = rows
+            .iter()
+            .skip(height)
+            .flatten()
+            .any(|cell| *cell != Presence::No);
+        let mut rows = rows.into_iter();
+        for y in (0..height).rev() {
+            self.rows[y] = rows.next().unwrap_or_else(|| vec![Presence::No; width]);
+        }
+        for &(x, y) in &self.blocked {
+            self.rows[y][x] = Presence::No;
+        }
+        overflowed
+    }
+
+    // Copies of the rows of `ys`, taken in the order given
+    fn rows_at(&self, ys: impl Iterator<Item = usize>) -> Vec<Vec<Presence>> {
+        ys.map(|y| self.rows[y].clone()).collect()
+    }
+
+    // The cell at (x, y), or None when the coordinates fall outside the board.
+    // A blocked cell reads as filled.
+    pub fn cell(&self, x: isize, y: isize) -> Option<Presence> {
+        let x = usize::try_from(self.column(x)?).ok()?;
+        let y = usize::try_from(y).ok()?;
+        if self.is_blocked(x, y) {
+            return Some(Presence::Yes(GameColor::Gray));
+        }
+        self.rows.get(y)?.get(x).copied()
+    }
+
+    // Writes the cell at (x, y); returns false, leaving the board untouched,
+    // when the coordinates fall outside it
+    pub fn set_cell(&mut self, x: isize, y: isize, presence: Presence) -> bool {
+        let Some(x) = self.column(x) else {
+            return false;
+        };
+        let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) else {
+            return false;
+        };
+        if self.is_blocked(x, y) {
+            return false;
+        }
+        match self.rows.get_mut(y).and_then(|row| row.get_mut(x)) {
+            Some(cell) => {
+                *cell = presence;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Stack height of every column: rows from the floor up to and including
+    // its highest filled cell, or 0 for an empty column. Blocked cells are
+    // not part of the stack.
+    pub fn column_heights(&self) -> Vec<usize> {
+        let height = self.height();
+        (0..self.width())
+            .map(|x| {
+                (0..height)
+                    .find(|&y| !self.is_blocked(x, y) && self.rows[y][x] != Presence::No)
+                    .map_or(0, |top| height - top)
+            })
+            .collect()
+    }
+
+    // Whether every playable cell of row y is filled; a row with no
+    // playable cells is never full
+    fn is_row_full(&self, y: usize) -> bool {
+        let mut playable = self.rows[y]
+            .iter()
+            .enumerate()
+            .filter(|&(x, _)| !self.is_blocked(x, y))
+            .peekable();
+        playable.peek().is_some() && playable.all(|(_, cell)| *cell != Presence::No)
+    }
+
+    // Indices of every full row, top to bottom
+    pub fn full_rows(&self) -> Vec<usize> {
+        (0..self.height())
+            .filter(|&y| self.is_row_full(y))
+            .collect()
+    }
+
+    // Whether clearing the full rows would leave the board empty: every row
+    // is either full or empty, and at least one is full
+    pub fn clears_to_empty(&self) -> bool {
+        let is_empty = |y: usize| self.rows[y].iter().all(|cell| *cell == Presence::No);
+        (0..self.height()).all(|y| self.is_row_full(y) || is_empty(y)) && self.full_row_count() > 0
+    }
+
+    pub fn full_row_count(&self) -> usize {
+        (0..self.height()).filter(|&y| self.is_row_full(y)).count()
+    }
+
+    // Rows that would be full with `cells` filled in as well, top to bottom.
+    // Cells off the board or blocked are left out.
+    pub fn rows_completed_by(&self, cells: &[(isize, isize)]) -> Vec<usize> {
+        let placed: Vec<(usize, usize)> = cells
+            .iter()
+            .filter_map(|&(x, y)| {
+                let x = usize::try_from(self.column(x)?).ok()?;
+                let y = usize::try_from(y).ok()?;
+                (y < self.height() && !self.is_blocked(x, y)).then_some((x, y))
+            })
+            .collect();
+        let mut rows: Vec<usize> = placed
+            .iter()
+            .map(|&(_, y)| y)
+            .filter(|&y| {
+                self.rows[y].iter().enumerate().all(|(x, cell)| {
+                    self.is_blocked(x, y) || *cell != Presence::No || placed.contains(&(x, y))
+                })
+            })
+            .collect();
+        rows.sort_unstable();
+        rows.dedup();
+        rows
+    }
+
+    /// Pushes the stack up and fills one bottom row of garbage per entry of
+    /// `holes`, top row first, each with a single hole at its entry. Returns
+    /// true if any filled cell was pushed off the top of the board.
+    pub fn insert_garbage_rows(&mut self, holes: &[usize]) -> bool {
+        let holes = &holes[..holes.len().min(self.height())];
+        let width = self.width();
+        let garbage = holes.iter().rev().map(|&hole_x| {
+            (0..width)
+                .map(|x| {
+                    if x == hole_x {
+                        Presence::No
+                    } else {
+                        Presence::Yes(GameColor::DarkGray)
+                    }
+                })
+                .collect()
+        });
+        let rows = garbage
+            .chain(self.rows_at((0..self.height()).rev()))
+            .collect();
+        self.restack_rows(rows)
+    }
+
+    /// Removes every full row in a single pass. This is the one canonical
+    /// compaction: surviving rows keep their top-to-bottom order and colors,
+    /// packed against the floor, however many full rows sat between them,
+    /// and the top is filled with empty rows. Rows move whole; blocked cells
+    /// stay where they are, and a block moved onto one is dropped. Returns
+    /// the number of rows cleared.
+    pub fn clear_full_rows(&mut self) -> usize {
+        let full: Vec<bool> = (0..self.height()).map(|y| self.is_row_full(y)).collect();
+        let rows = self.rows_at((0..self.height()).rev().filter(|&y| !full[y]));
+        self.restack_rows(rows);
+        full.iter().filter(|&&full| full).count()
+    }
+
+    /// Empties every cell, keeping the board's dimensions.
+    pub fn clear(&mut self) {
+        for row in &mut self.rows {
+            row.fill(Presence::No);
+        }
+    }
+
+    /// Reshapes the board to `width` x `height` if it differs, including rows
+    /// of uneven length. Rows stay against the floor and columns against the
+    /// left wall; rows or columns past the new size are dropped and new ones
+    /// start empty. Returns true if the board had to change.
+    pub fn fit_to(&mut self, width: usize, height: usize) -> bool {
+        if self.height() == height && self.rows.iter().all(|row| row.len() == width) {
+            return false;
+        }
+        let old_height = self.height();
+        let dropped = old_height.saturating_sub(height);
+        let mut rows: Vec<Vec<Presence>> = self.rows.drain(dropped..).collect();
+        rows.splice(0..0, vec![Vec::new(); height - rows.len()]);
+        for row in &mut rows {
+            row.resize(width, Presence::No);
+        }
+        self.rows = rows;
+        // Blocked cells keep their place relative to the floor
+        self.blocked = self
+            .blocked
+            .iter()
+            .filter_map(|&(x, y)| {
+                let y = (y + height).checked_sub(old_height)?;
+                (x < width).then_some((x, y))
+            })
+            .collect();
+        true
+    }
+
+    /// Removes row `y` whether or not it is full, shifting every row above it
+    /// down by one and leaving an empty row at the top.
+    pub fn remove_row(&mut self, y: usize) {
+        if y >= self.height() {
+            return;
+        }
+        let rows = self.rows_at((0..self.height()).rev().filter(|&row| row != y));
+        self.restack_rows(rows);
+    }
+
+    /// Serializes the board as one character per cell, rows top to bottom
+    /// separated by '/'. Empty cells are '.', blocked cells '#' and filled
+    /// cells their color code.
+    pub fn to_compact_string(&self) -> String {
+        self.rows
+            .iter()
+            .enumerate()
+            .map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(x, cell)| match cell {
+                        _ if self.is_blocked(x, y) => '#',
+                        Presence::No => '.',
+                        Presence::Yes(color) => color.code(),
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Parses a board written by `to_compact_string`. Returns None for an
+    /// unknown cell code or rows of differing widths.
+    pub fn from_compact_string(text: &str) -> Option<GameMap> {
+        let mut blocked = Vec::new();
+        let rows = text
+            .split('/')
+            .enumerate()
+            .map(|(y, row)| {
+                row.chars()
+                    .enumerate()
+                    .map(|(x, code)| match code {
+                        '.' => Some(Presence::No),
+                        '#' => {
+                            blocked.push((x, y));
+                            Some(Presence::No)
+                        }
+                        _ => GameColor::from_code(code).map(Presence::Yes),
+                    })
+                    .collect::<Option<Vec<_>>>()
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let width = rows.first().map_or(0, Vec::len);
+        if width == 0 || rows.iter().any(|row| row.len() != width) {
+            return None;
+        }
+        let mut map = GameMap {
+            rows,
+            wrap_edges: false,
+            blocked: Vec::new(),
+        };
+        for (x, y) in blocked {
+            map.block_cell(x, y);
+        }
+        Some(map)
+    }
+}
+
+// Logical board dimensions, the window of rows on screen and the on-screen
+// size of one cell
+#[derive(Resource, Debug, Copy, Clone, PartialEq)]
+pub struct BoardConfig {
+    pub width: usize,
+    pub height: usize,
+    /// Rows drawn at once; a tall board scrolls this window over `height` rows.
+    pub visible_height: usize,
+    /// Logical row shown at the top of the visible window.
+    pub scroll_row: usize,
+    pub cell_size: f32,
+    /// Draw the board flipped left to right; the GameMap itself stays canonical.
+    pub mirrored: bool,
+    /// Draw the board upside down, so pieces enter at the bottom and fall
+    /// upward; the GameMap itself stays canonical.
+    pub reversed_gravity: bool,
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        BoardConfig {
+            width: NUM_BLOCKS_X,
+            height: NUM_BLOCKS_Y,
+            visible_height: NUM_BLOCKS_Y,
+            scroll_row: 0,
+            cell_size: TEXTURE_SIZE as f32,
+            mirrored: false,
+            reversed_gravity: false,
+        }
     }
 }
 
-impl GameMap {}
+impl BoardConfig {
+    // Big mode doubles every cell, halving the board resolution in the same window
+    pub fn big() -> Self {
+        let normal = BoardConfig::default();
+        BoardConfig {
+            width: normal.width / 2,
+            height: normal.height / 2,
+            visible_height: normal.visible_height / 2,
+            cell_size: normal.cell_size * 2.0,
+            ..normal
+        }
+    }
+
+    // A `width` x `height` board, with cells sized so all of it fits the window
+    pub fn sized(width: usize, height: usize) -> Self {
+        let normal = BoardConfig::default();
+        let fit_width = normal.cell_size * normal.width as f32 / width as f32;
+        let fit_height = normal.cell_size * normal.visible_height as f32 / height as f32;
+        BoardConfig {
+            width,
+            height,
+            visible_height: height,
+            cell_size: fit_width.min(fit_height),
+            ..normal
+        }
+    }
+
+    // A board `rows` tall, viewed through the normal window starting at the bottom
+    pub fn tall(rows: usize) -> Self {
+        let normal = BoardConfig::default();
+        let height = rows.max(normal.visible_height);
+        BoardConfig {
+            height,
+            scroll_row: height - normal.visible_height,
+            ..normal
+        }
+    }
+
+    // Whether logical row y falls inside the visible window
+    pub fn is_row_visible(&self, y: isize) -> bool {
+        y >= self.scroll_row as isize && y < (self.scroll_row + self.visible_height) as isize
+    }
+
+    // The cells, in order, whose rows fall inside the visible window
+    pub fn visible_cells(&self, cells: Vec<(isize, isize)>) -> Vec<(isize, isize)> {
+        cells
+            .into_iter()
+            .filter(|&(_, y)| self.is_row_visible(y))
+            .collect()
+    }
+
+    // Maps a horizontal step between screen and board directions; the
+    // mapping is its own inverse
+    pub fn screen_dx(&self, dx: isize) -> isize {
+        if self.mirrored { -dx } else { dx }
+    }
+
+    // World y direction of a step of `dy` rows along the board: board y grows
+    // downward while world y grows upward, unless gravity is reversed
+    pub fn screen_dy(&self, dy: isize) -> isize {
+        if self.reversed_gravity { dy } else { -dy }
+    }
+
+    // Keys for soft drop and rotation, swapped with reversed gravity so soft
+    // drop still points the way pieces fall on screen
+    pub fn soft_drop_key(&self) -> KeyCode {
+        if self.reversed_gravity {
+            KeyCode::ArrowUp
+        } else {
+            KeyCode::ArrowDown
+        }
+    }
+
+    pub fn rotate_key(&self) -> KeyCode {
+        if self.reversed_gravity {
+            KeyCode::ArrowDown
+        } else {
+            KeyCode::ArrowUp
+        }
+    }
+
+    // Center of the cell at logical (x, y) in world space, with the top of the
+    // visible window at the top of the drawn board, or at the bottom with
+    // reversed gravity
+    pub fn cell_translation(&self, x: isize, y: isize) -> Vec2 {
+        let y = y - self.scroll_row as isize;
+        let y = if self.reversed_gravity {
+            self.visible_height as isize - 1 - y
+        } else {
+            y
+        };
+        let x = if self.mirrored {
+            self.width as isize - 1 - x
+        } else {
+            x
+        };
+        let board_width = self.width as f32 * self.cell_size;
+        let board_height = self.visible_height as f32 * self.cell_size;
+        Vec2::new(
+            (x as f32 * self.cell_size) - (board_width / 2.0) + (self.cell_size / 2.0),
+            (board_height / 2.0) - (y as f32 * self.cell_size) - (self.cell_size / 2.0),
+        )
+    }
+}
+
+// Classic "big" mode where every block is drawn at double size
+#[derive(Resource, Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct BigMode(pub bool);
+
+// Plays on a board taller than the window, scrolling to follow the stack
+#[derive(Resource, Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct TallBoard(pub bool);
+
+// Board dimensions given at launch, used instead of the menu's board size
+#[derive(Resource, Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct BoardSize(pub Option<(usize, usize)>);
+
+// Experimental: pieces leaving one side of the board come back in the other,
+// applied to the GameMap when a game starts
+#[derive(Resource, Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct WrapEdges(pub bool);
+
+// Experimental: pieces spawn at the bottom and fall upward, with cleared rows
+// closing the gap from below
+#[derive(Resource, Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct ReverseGravity(pub bool);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clear_full_rows_compacts_around_non_adjacent_full_rows() {
+        let mut map =
+            GameMap::from_compact_string("..../r.../dddd/.g../dddd/..b./dddd/...y/dddd/o...")
+                .unwrap();
+
+        assert_eq!(map.clear_full_rows(), 4);
+        assert_eq!(
+            map.to_compact_string(),
+            "..../..../..../..../..../r.../.g../..b./...y/o..."
+        );
+    }
+
+    #[test]
+    fn clear_full_rows_keeps_surviving_rows_in_order_with_their_colors() {
+        // Rows 2 and 4 of five are full; rows 1, 3 and 5 keep their colors
+        let mut map = GameMap::from_compact_string("r.r./yyyy/.g.g/bbbb/oo..").unwrap();
+
+        assert_eq!(map.clear_full_rows(), 2);
+        assert_eq!(map.to_compact_string(), "..../..../r.r./.g.g/oo..");
+        assert_eq!(map.cell(0, 2), Some(Presence::Yes(GameColor::Red)));
+        assert_eq!(map.cell(1, 3), Some(Presence::Yes(GameColor::Green)));
+        assert_eq!(map.cell(1, 4), Some(Presence::Yes(GameColor::Orange)));
+    }
+
+    #[test]
+    fn out_of_range_cells_read_none_and_refuse_writes() {
+        let mut map = GameMap::new(4, 3);
+        for (x, y) in [(-1, 0), (4, 0), (0, -1), (0, 3), (isize::MAX, isize::MIN)] {
+            assert_eq!(map.cell(x, y), None, "({}, {})", x, y);
+            assert!(!map.set_cell(x, y, Presence::Yes(GameColor::Red)));
+        }
+        assert_eq!(map.cell(3, 2), Some(Presence::No));
+        assert_eq!(map.to_compact_string(), "..../..../....");
+    }
+
+    #[test]
+    fn row_with_a_blocked_cell_is_full_once_its_playable_cells_are() {
+        let mut map = GameMap::from_compact_string("r.../..../....").unwrap();
+        map.block_cell(1, 2);
+        for x in [0, 2] {
+            map.set_cell(x, 2, Presence::Yes(GameColor::Gray));
+        }
+        assert!(map.full_rows().is_empty());
+        map.set_cell(3, 2, Presence::Yes(GameColor::Gray));
+        assert!(!map.set_cell(1, 2, Presence::Yes(GameColor::Gray)));
+
+        assert_eq!(map.full_rows(), [2]);
+        assert_eq!(map.clear_full_rows(), 1);
+        assert_eq!(map.to_compact_string(), "..../r.../.#..");
+        assert!(map.full_rows().is_empty());
+    }
+
+    #[test]
+    fn rows_fall_whole_past_a_blocked_cell() {
+        // The row above the clear lands in one piece around the blocked cell,
+        // which stays put
+        let mut map = GameMap::from_compact_string("r.g./.#../aaaa").unwrap();
+
+        assert_eq!(map.clear_full_rows(), 1);
+        assert_eq!(map.to_compact_string(), "..../r#g./....");
+        assert!(map.is_blocked(1, 1));
+    }
+
+    #[test]
+    fn block_moved_onto_a_blocked_cell_is_dropped() {
+        // The rest of the row moves down with it, unsplit
+        let mut map = GameMap::from_compact_string("rg../.#../aaaa").unwrap();
+
+        assert_eq!(map.clear_full_rows(), 1);
+        assert_eq!(map.to_compact_string(), "..../r#../....");
+    }
+}
@@ -0,0 +1,275 @@
+use crate::GameState;
+use crate::game_color::GameColor;
+use crate::game_constants::Z_OVERLAY;
+use crate::game_types::{BoardConfig, GameMap};
+use crate::rng::GameRng;
+use crate::theme::ColorTheme;
+use bevy::prelude::*;
+
+const METER_WIDTH: f32 = 6.0;
+// How often the garbage challenge sends another row
+const CHALLENGE_INTERVAL_SECS: f32 = 8.0;
+
+// Garbage rows waiting to rise from the bottom of the board
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PendingGarbage {
+    pub rows: u32,
+}
+
+impl PendingGarbage {
+    // Cleared lines first cancel pending rows; returns the lines left over
+    pub fn cancel(&mut self, lines: u32) -> u32 {
+        let cancelled = lines.min(self.rows);
+        self.rows -= cancelled;
+        lines - cancelled
+    }
+}
+
+// Challenge mode that keeps queueing garbage rows on a timer
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GarbageChallenge(pub bool);
+
+#[derive(Resource)]
+pub struct GarbageChallengeTimer(pub Timer);
+
+impl Default for GarbageChallengeTimer {
+    fn default() -> Self {
+        GarbageChallengeTimer(Timer::from_seconds(
+            CHALLENGE_INTERVAL_SECS,
+            TimerMode::Repeating,
+        ))
+    }
+}
+
+// Where the hole goes in each row of a batch of rising garbage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GarbageStyle {
+    /// Every row shares one hole column.
+    #[default]
+    Clean,
+    /// Each row's hole moves to a different column than the row below.
+    Messy,
+    /// Holes alternate between two columns, row by row.
+    Cheese,
+}
+
+impl GarbageStyle {
+    pub const ALL: [GarbageStyle; 3] = [
+        GarbageStyle::Clean,
+        GarbageStyle::Messy,
+        GarbageStyle::Cheese,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GarbageStyle::Clean => "clean",
+            GarbageStyle::Messy => "messy",
+            GarbageStyle::Cheese => "cheese",
+        }
+    }
+
+    pub fn cycled(self) -> Self {
+        match self {
+            GarbageStyle::Clean => GarbageStyle::Messy,
+            GarbageStyle::Messy => GarbageStyle::Cheese,
+            GarbageStyle::Cheese => GarbageStyle::Clean,
+        }
+    }
+
+    // Hole column for each of `count` rows, drawn from the seeded RNG
+    pub fn hole_columns(&self, count: usize, width: usize, rng: &mut GameRng) -> Vec<usize> {
+        let first = rng.column(width);
+        if width < 2 {
+            return vec![first; count];
+        }
+        match self {
+            GarbageStyle::Clean => vec![first; count],
+            GarbageStyle::Messy => {
+                let mut holes = vec![first];
+                while holes.len() < count {
+                    holes.push(other_column(holes[holes.len() - 1], width, rng));
+                }
+                holes.truncate(count);
+                holes
+            }
+            GarbageStyle::Cheese => {
+                let second = other_column(first, width, rng);
+                (0..count)
+                    .map(|row| if row % 2 == 0 { first } else { second })
+                    .collect()
+            }
+        }
+    }
+}
+
+// A random column other than `column`
+fn other_column(column: usize, width: usize, rng: &mut GameRng) -> usize {
+    let pick = rng.column(width - 1);
+    if pick >= column { pick + 1 } else { pick }
+}
+
+// Marker component for the incoming garbage meter
+#[derive(Component)]
+pub struct GarbageMeter;
+
+// Called after a piece locks: if the lock completed no rows, every pending
+// garbage row rises into the board, each with a single hole placed by `style`
+pub fn rise_pending_garbage(
+    game_map: &mut GameMap,
+    pending: &mut PendingGarbage,
+    style: GarbageStyle,
+    rng: &mut GameRng,
+    game_state: &mut NextState<GameState>,
+) {
+    if pending.rows == 0 || game_map.full_row_count() > 0 {
+        return;
+    }
+
+    let holes = style.hole_columns(pending.rows as usize, game_map.width(), rng);
+    let overflowed = game_map.insert_garbage_rows(&holes);
+    pending.rows = 0;
+
+    if overflowed {
+        game_state.set(GameState::GameOver);
+    }
+}
+
+pub fn queue_challenge_garbage(
+    time: Res<Time>,
+    challenge: Res<GarbageChallenge>,
+    mut timer: ResMut<GarbageChallengeTimer>,
+    mut pending: ResMut<PendingGarbage>,
+) {
+    if !challenge.0 {
+        return;
+    }
+    timer.0.tick(time.delta());
+    if timer.0.just_finished() {
+        pending.rows += 1;
+    }
+}
+
+pub fn setup_garbage_meter(mut commands: Commands) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                anchor: bevy::sprite::Anchor::BottomCenter,
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        GarbageMeter,
+    ));
+}
+
+// Grows a bar up the right edge of the board, brighter the more is pending
+pub fn update_garbage_meter(
+    pending: Res<PendingGarbage>,
+    board: Res<BoardConfig>,
+    theme: Res<ColorTheme>,
+    mut query_meter: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<GarbageMeter>>,
+) {
+    let Ok((mut sprite, mut transform, mut visibility)) = query_meter.get_single_mut() else {
+        return;
+    };
+
+    let rows = (pending.rows as usize).min(board.visible_height);
+    let board_width = board.width as f32 * board.cell_size;
+    let board_height = board.visible_height as f32 * board.cell_size;
+    let intensity = rows as f32 / board.visible_height as f32;
+
+    sprite.custom_size = Some(Vec2::new(METER_WIDTH, rows as f32 * board.cell_size));
+    sprite.color = theme.color(GameColor::Red).with_a(0.4 + 0.6 * intensity);
+    transform.translation = Vec3::new(
+        board_width / 2.0 - METER_WIDTH / 2.0,
+        -board_height / 2.0,
+        Z_OVERLAY,
+    );
+    *visibility = if rows > 0 {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_types::Presence;
+    use bevy::ecs::system::RunSystemOnce;
+
+    fn meter_height(app: &mut App) -> f32 {
+        app.world.run_system_once(update_garbage_meter);
+        let mut query = app.world.query_filtered::<&Sprite, With<GarbageMeter>>();
+        query.single(&app.world).custom_size.unwrap().y
+    }
+
+    #[test]
+    fn meter_shows_pending_rows_and_shrinks_on_cancel() {
+        let mut app = App::new();
+        app.init_resource::<BoardConfig>()
+            .init_resource::<ColorTheme>()
+            .insert_resource(PendingGarbage { rows: 5 });
+        app.world.run_system_once(setup_garbage_meter);
+        let cell_size = BoardConfig::default().cell_size;
+
+        assert_eq!(meter_height(&mut app), 5.0 * cell_size);
+
+        let leftover = app.world.resource_mut::<PendingGarbage>().cancel(1);
+        assert_eq!(leftover, 0);
+        assert_eq!(meter_height(&mut app), 4.0 * cell_size);
+    }
+
+    #[test]
+    fn clearing_lines_cancels_pending_garbage_without_a_rise() {
+        let mut game_map = GameMap::from_compact_string("..../r.../dddd/dddd").unwrap();
+        let before = game_map.clone();
+        let mut pending = PendingGarbage { rows: 4 };
+        let mut game_state = NextState::<GameState>::default();
+
+        rise_pending_garbage(
+            &mut game_map,
+            &mut pending,
+            GarbageStyle::Clean,
+            &mut GameRng::new(1),
+            &mut game_state,
+        );
+        assert_eq!(game_map, before);
+
+        let leftover = pending.cancel(game_map.clear_full_rows() as u32);
+        assert_eq!(leftover, 0);
+        assert_eq!(pending.rows, 2);
+    }
+
+    #[test]
+    fn messy_garbage_moves_the_single_hole_between_rows() {
+        let mut game_map = GameMap::default();
+        let mut pending = PendingGarbage { rows: 8 };
+        let mut game_state = NextState::<GameState>::default();
+
+        rise_pending_garbage(
+            &mut game_map,
+            &mut pending,
+            GarbageStyle::Messy,
+            &mut GameRng::new(7),
+            &mut game_state,
+        );
+        assert_eq!(pending.rows, 0);
+        let holes: Vec<usize> = game_map.rows[game_map.height() - 8..]
+            .iter()
+            .map(|row| {
+                let empty: Vec<usize> =
+                    (0..row.len()).filter(|&x| row[x] == Presence::No).collect();
+                assert_eq!(empty.len(), 1, "{:?}", row);
+                empty[0]
+            })
+            .collect();
+        assert!(
+            holes.windows(2).all(|pair| pair[0] != pair[1]),
+            "{:?}",
+            holes
+        );
+        assert_eq!(game_state.0, None);
+    }
+}
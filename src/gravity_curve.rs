@@ -0,0 +1,166 @@
+use crate::event_log::EventLog;
+use crate::game_constants::LEVEL_TIMES;
+use bevy::prelude::*;
+use std::fs;
+
+const GRAVITY_CURVE_FILE: &str = "gravity_curve.txt";
+
+// Milliseconds per row at each level, from level 0 up; levels past the end
+// keep the last entry
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct GravityCurve(Vec<u64>);
+
+impl Default for GravityCurve {
+    fn default() -> Self {
+        GravityCurve(LEVEL_TIMES.iter().map(|&ms| ms as u64).collect())
+    }
+}
+
+impl GravityCurve {
+    pub fn millis_for_level(&self, level: u32) -> u64 {
+        let index = (level as usize).min(self.0.len() - 1);
+        self.0[index]
+    }
+
+    // Number of levels the curve defines
+    pub fn levels(&self) -> u32 {
+        self.0.len() as u32
+    }
+
+    // Parses one millisecond value per line, level 0 first. Blank lines and
+    // lines starting with '#' are skipped. A curve must have at least one
+    // level, every value above zero, and never get slower as levels rise.
+    pub fn parse(text: &str) -> Result<GravityCurve, String> {
+        let mut millis = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let ms: u64 = line
+                .parse()
+                .map_err(|_| format!("'{}' is not a whole number of milliseconds", line))?;
+            if ms == 0 {
+                return Err(format!("level {} has zero gravity time", millis.len()));
+            }
+            if millis.last().is_some_and(|&previous| ms > previous) {
+                return Err(format!(
+                    "level {} is slower than the one before",
+                    millis.len()
+                ));
+            }
+            millis.push(ms);
+        }
+        if millis.is_empty() {
+            return Err("no levels".to_string());
+        }
+        Ok(GravityCurve(millis))
+    }
+}
+
+// Starting level picked from the curve in the main menu; None keeps the
+// difficulty's own starting level
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LevelSelect(pub Option<u32>);
+
+impl LevelSelect {
+    // Steps one level up the curve, starting from level 0
+    pub fn raise(&mut self, curve: &GravityCurve) {
+        self.0 = Some(
+            self.0
+                .map_or(0, |level| (level + 1).min(curve.levels() - 1)),
+        );
+    }
+
+    // Steps one level down; below level 0 goes back to the difficulty's level
+    pub fn lower(&mut self) {
+        self.0 = self.0.and_then(|level| level.checked_sub(1));
+    }
+
+    // Selected level with its seconds per row, as the menu shows it
+    pub fn label(&self, curve: &GravityCurve) -> String {
+        match self.0 {
+            Some(level) => format!(
+                "{} of {} ({:.2} s/row)",
+                level,
+                curve.levels(),
+                curve.millis_for_level(level) as f32 / 1000.0
+            ),
+            None => String::from("difficulty default"),
+        }
+    }
+}
+
+// Replaces the built-in curve with the one in the curve file, when there is
+// one and it is valid
+pub fn load_gravity_curve(mut curve: ResMut<GravityCurve>, mut log: ResMut<EventLog>) {
+    if let Some(custom) = read_gravity_curve(GRAVITY_CURVE_FILE, &mut log) {
+        *curve = custom;
+    }
+}
+
+// The curve in the file at `path`, or None when it is missing or invalid
+fn read_gravity_curve(path: &str, log: &mut EventLog) -> Option<GravityCurve> {
+    let text = fs::read_to_string(path).ok()?;
+    match GravityCurve::parse(&text) {
+        Ok(custom) => {
+            log.push(format!(
+                "Loaded gravity curve with {} levels",
+                custom.0.len()
+            ));
+            Some(custom)
+        }
+        Err(err) => {
+            log.push(format!("Ignoring {}: {}", path, err));
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gravity_for_level;
+    use crate::practice::GravityMultiplier;
+
+    // Loads `text` as a curve file, keeping the built-in curve as the game
+    // does when the file is invalid
+    fn load_curve_text(name: &str, text: &str) -> GravityCurve {
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, text).unwrap();
+        let curve = read_gravity_curve(path.to_str().unwrap(), &mut EventLog::default())
+            .unwrap_or_default();
+        fs::remove_file(&path).unwrap();
+        curve
+    }
+
+    #[test]
+    fn custom_curve_file_sets_the_gravity_of_each_level() {
+        let curve = load_curve_text("tetris_curve_custom.txt", "# fast start\n900\n600\n\n300\n");
+        let multiplier = GravityMultiplier::default();
+        let millis = |level| {
+            (gravity_for_level(level, &curve, &multiplier).as_secs_f32() * 1000.0).round() as u64
+        };
+        assert_eq!(curve.levels(), 3);
+        assert_eq!(millis(0), 900);
+        assert_eq!(millis(2), 300);
+        // Levels past the end keep the last entry
+        assert_eq!(millis(9), 300);
+    }
+
+    #[test]
+    fn invalid_curve_files_fall_back_to_the_built_in_curve() {
+        for (name, text) in [
+            ("tetris_curve_slower.txt", "500\n700\n"),
+            ("tetris_curve_zero.txt", "500\n0\n"),
+            ("tetris_curve_text.txt", "fast\n"),
+            ("tetris_curve_empty.txt", "# nothing\n"),
+        ] {
+            assert_eq!(
+                load_curve_text(name, text),
+                GravityCurve::default(),
+                "{}",
+                text
+            );
+        }
+    }
+}
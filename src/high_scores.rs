@@ -0,0 +1,387 @@
+use crate::Score;
+use crate::event_log::EventLog;
+use crate::game_color::GameColor;
+use crate::game_constants::{FONT_SIZE_PANEL, FONT_SIZE_SMALL};
+use crate::game_types::{GameMap, Presence};
+use crate::survival::{SurvivalClock, SurvivalMode, format_survival_time};
+use crate::theme::ColorTheme;
+use bevy::prelude::*;
+use std::cmp::Reverse;
+use std::fs;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const HIGH_SCORES_FILE: &str = "high_scores.txt";
+// Survival runs rank by milliseconds survived, kept in their own list
+const SURVIVAL_TIMES_FILE: &str = "survival_times.txt";
+const HIGH_SCORE_CAPACITY: usize = 5;
+
+// Size in pixels of one board cell in a high-score thumbnail
+const THUMBNAIL_CELL: f32 = 2.0;
+
+// One finished run: its score, when it ended and the final board
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighScoreEntry {
+    pub score: u32,
+    pub recorded_at: u64,
+    pub board: String,
+}
+
+impl HighScoreEntry {
+    // One tab-separated line per entry: score, unix seconds, compact board
+    fn to_line(&self) -> String {
+        format!("{}\t{}\t{}", self.score, self.recorded_at, self.board)
+    }
+
+    fn from_line(line: &str) -> Option<HighScoreEntry> {
+        let mut fields = line.split('\t');
+        let score = fields.next()?.parse().ok()?;
+        let recorded_at = fields.next()?.parse().ok()?;
+        let board = fields.next()?.to_string();
+        GameMap::from_compact_string(&board)?;
+        Some(HighScoreEntry {
+            score,
+            recorded_at,
+            board,
+        })
+    }
+}
+
+// Best runs, highest score first, saved to disk between sessions
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Default)]
+pub struct HighScores {
+    pub entries: Vec<HighScoreEntry>,
+}
+
+impl HighScores {
+    // Adds a run if it makes the list, keeping it sorted and capped
+    pub fn record(&mut self, entry: HighScoreEntry) {
+        self.entries.push(entry);
+        self.entries.sort_by_key(|entry| Reverse(entry.score));
+        self.entries.truncate(HIGH_SCORE_CAPACITY);
+    }
+
+    // Parses saved entries, skipping any line that is malformed
+    pub fn parse(text: &str) -> HighScores {
+        let mut high_scores = HighScores::default();
+        for entry in text.lines().filter_map(HighScoreEntry::from_line) {
+            high_scores.record(entry);
+        }
+        high_scores
+    }
+
+    // Whether a run scoring `value` beats the best saved run
+    pub fn beats_best(&self, value: u32) -> bool {
+        value > self.entries.first().map_or(0, |entry| entry.score)
+    }
+
+    pub fn serialize(&self) -> String {
+        self.entries
+            .iter()
+            .map(|entry| entry.to_line() + "\n")
+            .collect()
+    }
+}
+
+// Set when the run that just ended beat the best saved score
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NewHighScore(pub bool);
+
+// Marker component for the banner celebrating a new best score
+#[derive(Component)]
+pub struct NewHighScoreBanner;
+
+// Notes of the chord played for a new best score, in hertz (C, E and G)
+const FANFARE_NOTES: [f32; 3] = [523.25, 659.25, 783.99];
+const FANFARE_DURATION: Duration = Duration::from_millis(600);
+
+// Marker component for the high-score list shown after a game
+#[derive(Component)]
+pub struct HighScorePanel;
+
+fn high_scores_file(survival: bool) -> &'static str {
+    if survival {
+        SURVIVAL_TIMES_FILE
+    } else {
+        HIGH_SCORES_FILE
+    }
+}
+
+fn read_high_scores(path: &str) -> HighScores {
+    match fs::read_to_string(path) {
+        Ok(text) => HighScores::parse(&text),
+        Err(_) => HighScores::default(),
+    }
+}
+
+pub fn load_high_scores(mut commands: Commands) {
+    commands.insert_resource(read_high_scores(HIGH_SCORES_FILE));
+}
+
+// Records the finished run with a snapshot of its final board, in the list
+// for the mode it was played in
+pub fn record_high_score(
+    score: Res<Score>,
+    survival: Res<SurvivalMode>,
+    clock: Res<SurvivalClock>,
+    game_map: Res<GameMap>,
+    mut high_scores: ResMut<HighScores>,
+    mut new_high_score: ResMut<NewHighScore>,
+    mut log: ResMut<EventLog>,
+) {
+    let path = high_scores_file(survival.0);
+    *high_scores = read_high_scores(path);
+    let value = if survival.0 {
+        clock.elapsed.as_millis() as u32
+    } else {
+        score.value
+    };
+
+    // Compare against the saved best before this run joins the list
+    new_high_score.0 = high_scores.beats_best(value);
+
+    let recorded_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    high_scores.record(HighScoreEntry {
+        score: value,
+        recorded_at,
+        board: game_map.to_compact_string(),
+    });
+
+    if let Err(err) = fs::write(path, high_scores.serialize()) {
+        log.push(format!("Could not save high scores: {}", err));
+    }
+}
+
+// Shows the new high score banner and plays a fanfare, only when the run
+// beat the saved best
+pub fn celebrate_new_high_score(
+    mut commands: Commands,
+    new_high_score: Res<NewHighScore>,
+    mut pitches: ResMut<Assets<Pitch>>,
+) {
+    if !new_high_score.0 {
+        return;
+    }
+    commands.spawn((
+        TextBundle::from_section(
+            "NEW HIGH SCORE!",
+            TextStyle {
+                font_size: FONT_SIZE_PANEL,
+                color: Color::GOLD,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(30.0),
+            left: Val::Percent(20.0),
+            ..default()
+        }),
+        NewHighScoreBanner,
+    ));
+    for frequency in FANFARE_NOTES {
+        commands.spawn(PitchBundle {
+            source: pitches.add(Pitch::new(frequency, FANFARE_DURATION)),
+            settings: PlaybackSettings::DESPAWN,
+        });
+    }
+}
+
+// Pulses the banner between gold and white
+pub fn animate_new_high_score_banner(
+    time: Res<Time>,
+    mut query_banner: Query<&mut Text, With<NewHighScoreBanner>>,
+) {
+    let blend = (time.elapsed_seconds() * 6.0).sin() * 0.5 + 0.5;
+    let gold = Color::GOLD;
+    let color = Color::rgb(
+        gold.r() + (1.0 - gold.r()) * blend,
+        gold.g() + (1.0 - gold.g()) * blend,
+        gold.b() + (1.0 - gold.b()) * blend,
+    );
+    for mut text in query_banner.iter_mut() {
+        text.sections[0].style.color = color;
+    }
+}
+
+// Lists each high score next to a thumbnail of its final board
+pub fn spawn_high_score_panel(
+    mut commands: Commands,
+    high_scores: Res<HighScores>,
+    survival: Res<SurvivalMode>,
+    theme: Res<ColorTheme>,
+) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Percent(60.0),
+                    left: Val::Px(10.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(6.0),
+                    ..default()
+                },
+                ..default()
+            },
+            HighScorePanel,
+        ))
+        .with_children(|panel| {
+            for (rank, entry) in high_scores.entries.iter().enumerate() {
+                panel
+                    .spawn(NodeBundle {
+                        style: Style {
+                            column_gap: Val::Px(8.0),
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        if let Some(board) = GameMap::from_compact_string(&entry.board) {
+                            spawn_thumbnail(row, &board, &theme);
+                        }
+                        row.spawn(TextBundle::from_section(
+                            format!(
+                                "{}. {}  {}",
+                                rank + 1,
+                                entry_value(entry, survival.0),
+                                format_date(entry.recorded_at)
+                            ),
+                            TextStyle {
+                                font_size: FONT_SIZE_SMALL,
+                                color: Color::WHITE,
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+// An entry's score, or its survival time for the survival list
+fn entry_value(entry: &HighScoreEntry, survival: bool) -> String {
+    if survival {
+        format_survival_time(Duration::from_millis(entry.score as u64))
+    } else {
+        entry.score.to_string()
+    }
+}
+
+// Draws the filled cells of a board as tiny squares on a dark backdrop
+fn spawn_thumbnail(parent: &mut ChildBuilder, board: &GameMap, theme: &ColorTheme) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(board.width() as f32 * THUMBNAIL_CELL),
+                height: Val::Px(board.height() as f32 * THUMBNAIL_CELL),
+                ..default()
+            },
+            background_color: Color::BLACK.into(),
+            ..default()
+        })
+        .with_children(|thumbnail| {
+            for (y, x, color) in filled_cells(board) {
+                thumbnail.spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(x as f32 * THUMBNAIL_CELL),
+                        top: Val::Px(y as f32 * THUMBNAIL_CELL),
+                        width: Val::Px(THUMBNAIL_CELL),
+                        height: Val::Px(THUMBNAIL_CELL),
+                        ..default()
+                    },
+                    background_color: theme.color(color).into(),
+                    ..default()
+                });
+            }
+        });
+}
+
+// (row, column, color) of every filled cell, top to bottom
+fn filled_cells(board: &GameMap) -> Vec<(usize, usize, GameColor)> {
+    let mut cells = Vec::new();
+    for (y, row) in board.rows.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            if let Presence::Yes(color) = *cell {
+                cells.push((y, x, color));
+            }
+        }
+    }
+    cells
+}
+
+// Formats unix seconds as a UTC calendar date, YYYY-MM-DD
+fn format_date(unix_secs: u64) -> String {
+    // Days-to-civil conversion from Howard Hinnant's date algorithms
+    let days = (unix_secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn board_snapshot_round_trips_through_the_saved_list() {
+        let board = GameMap::from_compact_string("..../r.../.gb.").unwrap();
+        let mut high_scores = HighScores::default();
+        high_scores.record(HighScoreEntry {
+            score: 1200,
+            recorded_at: 1_700_000_000,
+            board: board.to_compact_string(),
+        });
+
+        let reloaded = HighScores::parse(&high_scores.serialize());
+        assert_eq!(reloaded, high_scores);
+        let snapshot = GameMap::from_compact_string(&reloaded.entries[0].board).unwrap();
+        assert_eq!(
+            filled_cells(&snapshot),
+            vec![
+                (1, 0, GameColor::Red),
+                (2, 1, GameColor::Green),
+                (2, 2, GameColor::Blue),
+            ]
+        );
+        assert_eq!(format_date(reloaded.entries[0].recorded_at), "2023-11-14");
+    }
+
+    fn banners(new_high_score: bool) -> usize {
+        let mut app = App::new();
+        app.init_resource::<Assets<Pitch>>()
+            .insert_resource(NewHighScore(new_high_score))
+            .add_systems(Update, celebrate_new_high_score);
+        app.update();
+        let mut query = app.world.query_filtered::<(), With<NewHighScoreBanner>>();
+        query.iter(&app.world).count()
+    }
+
+    #[test]
+    fn beating_the_saved_best_celebrates() {
+        let mut high_scores = HighScores::default();
+        high_scores.record(HighScoreEntry {
+            score: 100,
+            recorded_at: 0,
+            board: "....".to_string(),
+        });
+
+        assert!(!high_scores.beats_best(100));
+        assert!(high_scores.beats_best(500));
+        assert_eq!(banners(high_scores.beats_best(500)), 1);
+        assert_eq!(banners(high_scores.beats_best(50)), 0);
+    }
+}
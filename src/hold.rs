@@ -0,0 +1,291 @@
+use crate::components::{Piece, Position};
+use crate::events::PieceHeld;
+use crate::game_constants::{FONT_SIZE_PANEL, Z_PIECE};
+use crate::game_types::{BoardConfig, GameMap, PieceType};
+use crate::hud_layout::UiLayout;
+use crate::queue::PieceQueue;
+use crate::rng::GameRng;
+use crate::rules::RulesConfig;
+use crate::theme::ColorTheme;
+use crate::visual;
+use crate::{GameState, block_sprite, piece_cells, spawn_position};
+use bevy::prelude::*;
+use std::time::Duration;
+
+// Piece set aside by the player, and the holds used since the last lock
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HoldSlot {
+    pub piece_type: Option<PieceType>,
+    pub holds_used: u32,
+}
+
+impl HoldSlot {
+    // Whether another hold fits in the per-piece limit; None is unlimited
+    pub fn can_hold(&self, holds_per_piece: Option<u32>) -> bool {
+        holds_per_piece.is_none_or(|limit| self.holds_used < limit)
+    }
+
+    // Gives the next piece its full hold allowance
+    pub fn refresh(&mut self) {
+        self.holds_used = 0;
+    }
+}
+
+// Marker component for the hold display
+#[derive(Component)]
+pub struct HoldDisplay;
+
+// Swaps the active piece with the held one. The incoming piece always starts
+// fresh at the spawn position in its spawn state, never at the stale
+// location or orientation of the piece it replaces. Returns false when the
+// hold limit turned the swap down.
+#[allow(clippy::too_many_arguments)]
+pub fn hold_piece(
+    piece: &mut Piece,
+    position: &mut Position,
+    hold: &mut HoldSlot,
+    game_map: &GameMap,
+    board: &BoardConfig,
+    rules: &RulesConfig,
+    queue: &mut PieceQueue,
+    rng: &mut GameRng,
+    game_state: &mut NextState<GameState>,
+) -> bool {
+    if !hold.can_hold(rules.holds_per_piece) {
+        return false;
+    }
+
+    let incoming_type = match hold.piece_type.replace(piece.piece_type) {
+        Some(piece_type) => piece_type,
+        None => queue.next(rng, &rules.piece_weights),
+    };
+    let incoming = Piece::for_rules(incoming_type, rules, rng);
+    hold.holds_used += 1;
+
+    let top_row = visual::scroll_row_for(board, game_map) as isize;
+    match spawn_position(&incoming, game_map, rules, top_row) {
+        Some(spawn) => {
+            *piece = incoming;
+            *position = spawn;
+        }
+        None => {
+            game_state.set(GameState::GameOver);
+        }
+    }
+    true
+}
+
+// How long the held and incoming pieces take to trade places on screen
+const HOLD_SWAP_DURATION: Duration = Duration::from_millis(200);
+
+// On-screen hold swap in progress: the outgoing piece shrinks away where it
+// was while the incoming one grows in. Purely cosmetic; the swap itself has
+// already happened.
+#[derive(Resource, Debug, Default)]
+pub struct HoldSwapAnimation {
+    timer: Option<Timer>,
+}
+
+impl HoldSwapAnimation {
+    // Scale to draw the active piece's cells at, 1 when no swap is animating
+    pub fn incoming_scale(&self) -> f32 {
+        self.timer.as_ref().map_or(1.0, Timer::fraction)
+    }
+}
+
+// A cell of the piece that just went into the hold slot
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutgoingHoldCell {
+    pub piece_type: PieceType,
+}
+
+pub fn start_hold_swap_animation(
+    mut commands: Commands,
+    board: Res<BoardConfig>,
+    game_map: Res<GameMap>,
+    theme: Res<ColorTheme>,
+    mut held: EventReader<PieceHeld>,
+    mut animation: ResMut<HoldSwapAnimation>,
+    query_cells: Query<Entity, With<OutgoingHoldCell>>,
+) {
+    let Some(event) = held.read().last() else {
+        return;
+    };
+    for entity in query_cells.iter() {
+        commands.entity(entity).despawn();
+    }
+    let cells = game_map.wrapped_cells(piece_cells(&event.outgoing, &event.from));
+    for (x, y) in board.visible_cells(cells) {
+        let mut sprite = block_sprite(&board, x, y, theme.color(event.outgoing.color));
+        sprite.transform.translation.z = Z_PIECE;
+        commands.spawn((
+            sprite,
+            OutgoingHoldCell {
+                piece_type: event.outgoing.piece_type,
+            },
+        ));
+    }
+    animation.timer = Some(Timer::new(HOLD_SWAP_DURATION, TimerMode::Once));
+}
+
+pub fn animate_hold_swap(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut animation: ResMut<HoldSwapAnimation>,
+    mut query_cells: Query<(Entity, &mut Transform), With<OutgoingHoldCell>>,
+) {
+    let Some(timer) = animation.timer.as_mut() else {
+        return;
+    };
+    if timer.tick(time.delta()).finished() {
+        animation.timer = None;
+        for (entity, _) in query_cells.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    let scale = 1.0 - timer.fraction();
+    for (_, mut transform) in query_cells.iter_mut() {
+        transform.scale = Vec3::splat(scale);
+    }
+}
+
+pub fn setup_hold_display(mut commands: Commands, layout: Res<UiLayout>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "Hold: -",
+            TextStyle {
+                font_size: FONT_SIZE_PANEL,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(layout.hold.style()),
+        HoldDisplay,
+    ));
+}
+
+pub fn update_hold_display(
+    hold: Res<HoldSlot>,
+    mut query_text: Query<&mut Text, With<HoldDisplay>>,
+) {
+    if hold.is_changed()
+        && let Some(mut text) = query_text.iter_mut().next()
+    {
+        text.sections[0].value = match hold.piece_type {
+            Some(piece_type) => format!("Hold: {:?}", piece_type),
+            None => "Hold: -".to_string(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_color::GameColor;
+    use crate::game_types::Presence;
+
+    #[test]
+    fn held_swap_starts_at_spawn_in_spawn_state() {
+        let game_map = GameMap::default();
+        let board = BoardConfig::default();
+        let rules = RulesConfig::default();
+        let mut rng = GameRng::new(1);
+        let mut queue = PieceQueue::default();
+        queue.force_next(PieceType::I);
+        let mut hold = HoldSlot::default();
+        let mut game_state = NextState::<GameState>::default();
+        let mut piece = Piece::new(PieceType::T);
+        piece.current_state = 2;
+        let mut position = Position { x: 0, y: 8 };
+
+        let swapped = hold_piece(
+            &mut piece,
+            &mut position,
+            &mut hold,
+            &game_map,
+            &board,
+            &rules,
+            &mut queue,
+            &mut rng,
+            &mut game_state,
+        );
+
+        assert!(swapped);
+        assert_eq!(hold.piece_type, Some(PieceType::T));
+        assert_eq!(piece.piece_type, PieceType::I);
+        assert_eq!(piece.current_state, 0);
+        let spawn = spawn_position(&piece, &game_map, &rules, 0).unwrap();
+        assert_eq!(position, spawn);
+        assert!(game_state.0.is_none());
+    }
+
+    #[test]
+    fn held_swap_with_no_room_ends_the_game() {
+        let mut game_map = GameMap::default();
+        for y in 0..game_map.height() as isize {
+            for x in 0..game_map.width() as isize {
+                game_map.set_cell(x, y, Presence::Yes(GameColor::Gray));
+            }
+        }
+        let mut game_state = NextState::<GameState>::default();
+        let mut piece = Piece::new(PieceType::T);
+        let mut position = Position { x: 3, y: 0 };
+
+        hold_piece(
+            &mut piece,
+            &mut position,
+            &mut HoldSlot::default(),
+            &game_map,
+            &BoardConfig::default(),
+            &RulesConfig::default(),
+            &mut PieceQueue::default(),
+            &mut GameRng::new(1),
+            &mut game_state,
+        );
+
+        assert_eq!(game_state.0, Some(GameState::GameOver));
+    }
+
+    // Whether each of `holds` holds in a row goes through, with a lock
+    // between them when `lock_between` is set
+    fn holds_in_a_row(holds_per_piece: Option<u32>, holds: usize, lock_between: bool) -> Vec<bool> {
+        let game_map = GameMap::default();
+        let rules = RulesConfig {
+            holds_per_piece,
+            ..RulesConfig::default()
+        };
+        let mut hold = HoldSlot::default();
+        let mut queue = PieceQueue::default();
+        let mut rng = GameRng::new(1);
+        let mut piece = Piece::new(PieceType::T);
+        let mut position = Position { x: 3, y: 0 };
+        (0..holds)
+            .map(|_| {
+                let swapped = hold_piece(
+                    &mut piece,
+                    &mut position,
+                    &mut hold,
+                    &game_map,
+                    &BoardConfig::default(),
+                    &rules,
+                    &mut queue,
+                    &mut rng,
+                    &mut NextState::default(),
+                );
+                if lock_between {
+                    hold.refresh();
+                }
+                swapped
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unlimited_holds_cycle_while_the_default_waits_for_a_lock() {
+        assert_eq!(holds_in_a_row(None, 2, false), [true, true]);
+        let default_limit = RulesConfig::default().holds_per_piece;
+        assert_eq!(holds_in_a_row(default_limit, 2, false), [true, false]);
+        assert_eq!(holds_in_a_row(default_limit, 2, true), [true, true]);
+    }
+}
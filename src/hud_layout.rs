@@ -0,0 +1,139 @@
+use crate::ScoreDisplay;
+use crate::hold::HoldDisplay;
+use bevy::prelude::*;
+
+// Gap in pixels between a HUD element and the window edges
+const EDGE_MARGIN: f32 = 10.0;
+
+// Window corner a HUD element is pinned to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiAnchor {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl UiAnchor {
+    pub fn name(&self) -> &'static str {
+        match self {
+            UiAnchor::TopLeft => "top left",
+            UiAnchor::TopRight => "top right",
+            UiAnchor::BottomLeft => "bottom left",
+            UiAnchor::BottomRight => "bottom right",
+        }
+    }
+
+    // Absolute placement against the anchor's two window edges
+    pub fn style(&self) -> Style {
+        let margin = Val::Px(EDGE_MARGIN);
+        let (top, bottom) = match self {
+            UiAnchor::TopLeft | UiAnchor::TopRight => (margin, Val::Auto),
+            UiAnchor::BottomLeft | UiAnchor::BottomRight => (Val::Auto, margin),
+        };
+        let (left, right) = match self {
+            UiAnchor::TopLeft | UiAnchor::BottomLeft => (margin, Val::Auto),
+            UiAnchor::TopRight | UiAnchor::BottomRight => (Val::Auto, margin),
+        };
+        Style {
+            position_type: PositionType::Absolute,
+            top,
+            bottom,
+            left,
+            right,
+            ..default()
+        }
+    }
+}
+
+// Where each HUD element sits. The menu steps through LAYOUTS, which keep the
+// elements in different corners.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UiLayout {
+    /// Score (or survival time) and level text.
+    pub score: UiAnchor,
+    /// Held piece text.
+    pub hold: UiAnchor,
+}
+
+impl Default for UiLayout {
+    fn default() -> Self {
+        UiLayout::LAYOUTS[0]
+    }
+}
+
+impl UiLayout {
+    const LAYOUTS: [UiLayout; 4] = [
+        UiLayout {
+            score: UiAnchor::TopLeft,
+            hold: UiAnchor::TopRight,
+        },
+        UiLayout {
+            score: UiAnchor::TopRight,
+            hold: UiAnchor::TopLeft,
+        },
+        UiLayout {
+            score: UiAnchor::BottomLeft,
+            hold: UiAnchor::BottomRight,
+        },
+        UiLayout {
+            score: UiAnchor::BottomRight,
+            hold: UiAnchor::BottomLeft,
+        },
+    ];
+
+    // The next layout in LAYOUTS; a custom layout goes back to the first
+    pub fn cycled(&self) -> UiLayout {
+        let index = UiLayout::LAYOUTS
+            .iter()
+            .position(|layout| layout == self)
+            .map_or(0, |i| (i + 1) % UiLayout::LAYOUTS.len());
+        UiLayout::LAYOUTS[index]
+    }
+
+    pub fn name(&self) -> String {
+        format!("score {}, hold {}", self.score.name(), self.hold.name())
+    }
+}
+
+// Moves the HUD text when the layout changes after setup
+pub fn apply_ui_layout(
+    layout: Res<UiLayout>,
+    mut query_score: Query<&mut Style, (With<ScoreDisplay>, Without<HoldDisplay>)>,
+    mut query_hold: Query<&mut Style, (With<HoldDisplay>, Without<ScoreDisplay>)>,
+) {
+    if !layout.is_changed() {
+        return;
+    }
+    for mut style in query_score.iter_mut() {
+        *style = layout.score.style();
+    }
+    for mut style in query_hold.iter_mut() {
+        *style = layout.hold.style();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn top_right_score_anchor_places_the_score_text_from_the_right_and_top() {
+        let mut world = World::new();
+        world.insert_resource(UiLayout {
+            score: UiAnchor::TopRight,
+            ..UiLayout::default()
+        });
+        world.run_system_once(crate::setup_ui);
+
+        let style = world
+            .query_filtered::<&Style, With<ScoreDisplay>>()
+            .single(&world);
+        assert_eq!(style.position_type, PositionType::Absolute);
+        assert_eq!(style.top, Val::Px(EDGE_MARGIN));
+        assert_eq!(style.right, Val::Px(EDGE_MARGIN));
+        assert_eq!(style.left, Val::Auto);
+        assert_eq!(style.bottom, Val::Auto);
+    }
+}
@@ -0,0 +1,199 @@
+use crate::game_constants::Z_PARTICLES;
+use crate::game_types::BoardConfig;
+use crate::rules::RulesConfig;
+use bevy::prelude::*;
+use std::time::Duration;
+
+// How long full rows stay on screen animating before they are removed
+const LINE_CLEAR_DURATION: Duration = Duration::from_millis(300);
+// Blinks per second of the Flash style
+const FLASH_RATE: f32 = 20.0;
+// Gold blinks of a perfect clear highlight over the whole animation
+const HIGHLIGHT_BLINKS: f32 = 6.0;
+// Cells an exploding block travels by the end of the animation
+const EXPLODE_DISTANCE: f32 = 3.0;
+
+// Animation played on full rows before they are cleared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineClearStyle {
+    /// Rows blink white.
+    #[default]
+    Flash,
+    /// Cells shrink away to nothing.
+    Collapse,
+    /// Cells fly outward from the middle of the board and fade.
+    Explode,
+}
+
+impl LineClearStyle {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineClearStyle::Flash => "flash",
+            LineClearStyle::Collapse => "collapse",
+            LineClearStyle::Explode => "explode",
+        }
+    }
+
+    pub fn cycled(self) -> Self {
+        match self {
+            LineClearStyle::Flash => LineClearStyle::Collapse,
+            LineClearStyle::Collapse => LineClearStyle::Explode,
+            LineClearStyle::Explode => LineClearStyle::Flash,
+        }
+    }
+}
+
+// Full rows waiting out their animation. With line_clear_pause on, gameplay
+// pauses until the timer finishes and clear_lines removes them.
+#[derive(Resource, Debug, Clone)]
+pub struct LineClearAnimation {
+    pub rows: Vec<usize>,
+    pub timer: Timer,
+    /// Cells of the piece that set up a perfect clear, flashed gold while
+    /// the rows animate; empty for any other clear.
+    pub highlight: Vec<(isize, isize)>,
+}
+
+impl Default for LineClearAnimation {
+    fn default() -> Self {
+        LineClearAnimation {
+            rows: Vec::new(),
+            timer: Timer::new(LINE_CLEAR_DURATION, TimerMode::Once),
+            highlight: Vec::new(),
+        }
+    }
+}
+
+impl LineClearAnimation {
+    pub fn is_active(&self) -> bool {
+        !self.rows.is_empty()
+    }
+
+    pub fn start(&mut self, rows: Vec<usize>) {
+        self.rows = rows;
+        self.timer.reset();
+    }
+
+    pub fn finish(&mut self) {
+        self.rows.clear();
+        self.highlight.clear();
+    }
+
+    // Fraction of the animation played so far, 0.0 to 1.0
+    pub fn progress(&self) -> f32 {
+        self.timer.fraction()
+    }
+}
+
+// Collapse durations the main menu cycles through; zero drops rows instantly
+const ROW_COLLAPSE_CHOICES: [Duration; 4] = [
+    Duration::ZERO,
+    Duration::from_millis(100),
+    Duration::from_millis(200),
+    Duration::from_millis(400),
+];
+
+// Steps a collapse duration to the next menu choice, wrapping around
+pub fn cycled_collapse_duration(duration: Duration) -> Duration {
+    let next = ROW_COLLAPSE_CHOICES
+        .iter()
+        .position(|&choice| choice == duration)
+        .map_or(0, |index| (index + 1) % ROW_COLLAPSE_CHOICES.len());
+    ROW_COLLAPSE_CHOICES[next]
+}
+
+// Rows left standing above a clear, drawn falling into the places the clear
+// already moved them to in the game map
+#[derive(Resource, Debug, Clone, Default)]
+pub struct RowCollapse {
+    /// Rows each map row fell in the last clear, indexed by its new row.
+    drops: Vec<usize>,
+    timer: Timer,
+}
+
+impl RowCollapse {
+    // Starts the fall for a clear of `full_rows` on a board `height` rows tall
+    pub fn start(&mut self, full_rows: &[usize], height: usize, duration: Duration) {
+        self.drops = vec![0; height];
+        for y in 0..height {
+            let drop = full_rows.iter().filter(|&&full| full > y).count();
+            if !full_rows.contains(&y) && y + drop < height {
+                self.drops[y + drop] = drop;
+            }
+        }
+        self.timer = Timer::new(duration, TimerMode::Once);
+    }
+
+    pub fn tick(&mut self, delta: Duration) {
+        self.timer.tick(delta);
+    }
+
+    pub fn finish(&mut self) {
+        self.drops.clear();
+    }
+
+    // Rows above its place in the map that row y is still drawn
+    pub fn remaining(&self, y: usize) -> f32 {
+        let drop = self.drops.get(y).copied().unwrap_or(0);
+        if drop == 0 || self.timer.finished() {
+            return 0.0;
+        }
+        drop as f32 * (1.0 - self.timer.fraction())
+    }
+}
+
+pub fn advance_row_collapse(time: Res<Time>, mut collapse: ResMut<RowCollapse>) {
+    if !collapse.drops.is_empty() {
+        collapse.tick(time.delta());
+    }
+}
+
+// Recolors a highlighted cell, blinking between gold and its own color
+pub fn highlight_cell(sprite: &mut SpriteBundle, progress: f32) {
+    let blinks = (progress * HIGHLIGHT_BLINKS) as u32;
+    if blinks.is_multiple_of(2) {
+        sprite.sprite.color = Color::GOLD;
+    }
+}
+
+// Run condition that holds gameplay while rows are animating, unless the
+// rules let play continue over the animation
+pub fn gameplay_running(animation: Res<LineClearAnimation>, rules: Res<RulesConfig>) -> bool {
+    !rules.line_clear_pause || !animation.is_active()
+}
+
+// Restyles the sprite of cell x in an animating row for the given style
+pub fn animate_cell(
+    sprite: &mut SpriteBundle,
+    board: &BoardConfig,
+    x: usize,
+    style: LineClearStyle,
+    progress: f32,
+) {
+    match style {
+        LineClearStyle::Flash => {
+            let blinks = (progress * LINE_CLEAR_DURATION.as_secs_f32() * FLASH_RATE) as u32;
+            if blinks.is_multiple_of(2) {
+                sprite.sprite.color = Color::WHITE;
+            }
+        }
+        LineClearStyle::Collapse => {
+            sprite.transform.scale = Vec3::splat(1.0 - progress);
+        }
+        LineClearStyle::Explode => {
+            // Cells left of center fly left, the rest fly right, all drifting
+            // away from the floor
+            let from_center = x as f32 + 0.5 - board.width as f32 / 2.0;
+            let direction = Vec2::new(
+                board.screen_dx(1) as f32 * from_center.signum(),
+                -board.screen_dy(1) as f32 * 0.5,
+            );
+            let travel = direction * EXPLODE_DISTANCE * board.cell_size * progress;
+            sprite.transform.translation += travel.extend(0.0);
+            sprite.transform.translation.z = Z_PARTICLES;
+            sprite.transform.scale = Vec3::splat(1.0 - 0.5 * progress);
+            let alpha = sprite.sprite.color.a() * (1.0 - progress);
+            sprite.sprite.color.set_a(alpha);
+        }
+    }
+}
@@ -1,38 +1,287 @@
-use crate::components::{Piece, Position};
+use crate::accessibility::{ColumnTargeting, MirrorBoard};
+use crate::action_trace::ActionTrace;
+use crate::blitz::{PieceLimit, PiecesPlaced};
+use crate::components::{Piece, Position, VisualOffset};
+use crate::debug::{DebugSettings, DrawBenchmark};
+use crate::difficulty::Difficulty;
+use crate::event_log::EventLog;
+use crate::events::{
+    GameOver, LevelUp, LinesCleared, PerfectClear, PieceHeld, PieceLocked, PieceMovedDown,
+    PieceSpawned, Spin,
+};
 use crate::game_color::GameColor;
 use crate::game_constants::{
-    HEIGHT, LEVEL_TIMES, NUM_BLOCKS_X, NUM_BLOCKS_Y, NUM_LEVELS, TEXTURE_SIZE, TITLE, WIDTH,
+    FONT_SIZE_BANNER, FONT_SIZE_HUD, HEIGHT, MIN_GRAVITY_MS, SPAWN_PUSH_ROWS, TITLE, WIDTH,
+    Z_BLOCKS, Z_PIECE,
+};
+use crate::game_types::{
+    BigMode, BoardConfig, BoardSize, GameMap, PieceMatrix, Presence, ReverseGravity, TallBoard,
+    WrapEdges,
 };
-use crate::game_types::{GameMap, PieceMatrix, PieceType, Presence};
+use crate::garbage::{GarbageChallenge, GarbageChallengeTimer, PendingGarbage};
+use crate::gravity_curve::{GravityCurve, LevelSelect};
+use crate::high_scores::NewHighScore;
+use crate::hold::{HoldSlot, HoldSwapAnimation};
+use crate::hud_layout::UiLayout;
+use crate::line_clear::{LineClearAnimation, RowCollapse};
+use crate::menu::MenuPage;
+use crate::mouse::{MouseBindings, MouseControls};
+use crate::practice::{GravityMultiplier, PlacementHints, PracticeGarbage, PracticeMode};
+use crate::puzzle::ActivePuzzle;
+use crate::queue::PieceQueue;
+use crate::restart::QuickRestart;
+use crate::rhythm::{Metronome, RhythmMode};
+use crate::rng::GameRng;
+use crate::rotation::{KickAttempt, LastKick, LastRotation};
+use crate::rules::RulesConfig;
+use crate::run_stats::{RunStats, RunStatsExport};
+use crate::savegame::ResumableGame;
+use crate::seed::SeedEntry;
+use crate::simulation::GameAction;
+use crate::streak::{ClearStreak, StreakHud};
+use crate::survival::{SurvivalClock, SurvivalMode};
+use crate::telemetry::{GameEvent, TelemetryBuffer};
+use crate::theme::ColorTheme;
+use crate::touch::TouchControls;
+use crate::transition::FadeTransition;
+use crate::visual::VisualSettings;
 use bevy::input::ButtonInput;
 use bevy::input::keyboard::KeyCode;
 use bevy::prelude::*;
-use rand::{Rng, rng};
 use std::time::Duration;
 
+mod accessibility;
+mod action_trace;
+mod analysis;
+mod blitz;
+mod cli;
 mod components;
+mod debug;
+mod difficulty;
+mod event_log;
+mod events;
 mod game_color;
 mod game_constants;
 mod game_types;
+mod garbage;
+mod gravity_curve;
+mod high_scores;
+mod hold;
+mod hud_layout;
+mod line_clear;
+mod menu;
+mod mode_summary;
+mod mouse;
+mod pause;
+mod pieces;
+mod practice;
+mod puzzle;
+mod queue;
+mod restart;
+mod rhythm;
+mod rng;
+mod rotation;
+mod rules;
+mod run_stats;
+mod savegame;
+mod screenshot;
+mod seed;
+mod simulation;
+mod spin;
+mod streak;
+mod survival;
+mod telemetry;
+mod theme;
+mod touch;
+mod transition;
+mod ui_scale;
+mod visual;
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 enum GameState {
     #[default]
+    MainMenu,
     Playing,
+    Paused,
     GameOver,
 }
 
-#[derive(Resource, Default)]
+#[derive(Resource, Default, Debug, Clone, PartialEq, Eq)]
 pub struct Score {
     pub value: u32,
 }
 
-#[derive(Resource, Default)]
+#[derive(Resource, Default, Debug, Clone, PartialEq, Eq)]
 pub struct Level {
     pub value: u32,
     pub lines_cleared_in_level: u32,
 }
 
+// Time a grounded piece may keep moving before it locks. Moves and rotations
+// on the ground restart the delay at most max_resets times, until the piece
+// reaches a row lower than any it has been on.
+#[derive(Resource)]
+pub struct LockDelay {
+    pub timer: Timer,
+    pub max_resets: u32,
+    resets_used: u32,
+    lowest_row: Option<isize>,
+    /// Set for a new piece until it first moves down, see `expire`.
+    spawn_protected: bool,
+}
+
+impl Default for LockDelay {
+    fn default() -> Self {
+        LockDelay {
+            timer: Timer::new(Duration::from_millis(500), TimerMode::Once),
+            max_resets: 15,
+            resets_used: 0,
+            lowest_row: None,
+            spawn_protected: true,
+        }
+    }
+}
+
+impl LockDelay {
+    pub fn set_duration(&mut self, duration: Duration) {
+        self.timer = Timer::new(duration, TimerMode::Once);
+    }
+
+    // Starts over for a new piece, with the full reset allowance
+    pub fn reset(&mut self) {
+        self.timer.reset();
+        self.resets_used = 0;
+        self.lowest_row = None;
+        self.spawn_protected = true;
+    }
+
+    // Restarts a running delay after a move or rotation, while resets remain.
+    // A delay that has not started, because the piece is airborne, costs none.
+    pub fn move_reset(&mut self) {
+        if self.timer.elapsed().is_zero() || self.resets_used >= self.max_resets {
+            return;
+        }
+        self.timer.reset();
+        self.resets_used += 1;
+    }
+
+    // Records the piece moving down to row y; a new lowest row restores the
+    // reset allowance
+    pub fn descended(&mut self, y: isize) {
+        self.spawn_protected = false;
+        if self.lowest_row.is_none_or(|lowest| y > lowest) {
+            self.lowest_row = Some(y);
+            self.resets_used = 0;
+        }
+    }
+
+    pub fn remaining_resets(&self) -> u32 {
+        self.max_resets.saturating_sub(self.resets_used)
+    }
+
+    // Runs the delay out so the piece locks on the next lock check. A piece
+    // that has not fallen since it spawned keeps its whole delay, so one held
+    // soft drop cannot lock a piece that spawned onto the stack.
+    pub fn expire(&mut self) {
+        if self.spawn_protected {
+            return;
+        }
+        let duration = self.timer.duration();
+        self.timer.set_elapsed(duration);
+    }
+}
+
+// Entry delay (ARE): set when a piece locks and counted down to the next spawn
+#[derive(Resource, Default)]
+pub struct EntryDelay {
+    timer: Option<Timer>,
+}
+
+impl EntryDelay {
+    pub fn start(&mut self, duration: Duration) {
+        self.timer = Some(Timer::new(duration, TimerMode::Once));
+    }
+
+    // Drops a pending spawn, for when something else has placed the next piece
+    pub fn cancel(&mut self) {
+        self.timer = None;
+    }
+
+    // Time left before the next spawn, or None when no spawn is pending
+    pub fn remaining(&self) -> Option<Duration> {
+        self.timer.as_ref().map(Timer::remaining)
+    }
+
+    // Advances the delay; true once, on the tick where it runs out
+    fn tick(&mut self, delta: Duration) -> bool {
+        let Some(timer) = self.timer.as_mut() else {
+            return false;
+        };
+        if !timer.tick(delta).finished() {
+            return false;
+        }
+        self.timer = None;
+        true
+    }
+}
+
+// Drives gravity: the piece falls one row each time the timer wraps
+#[derive(Resource)]
+pub struct GravityTimer {
+    pub timer: Timer,
+    /// Repeat clock for a held soft drop, soft_drop_factor times faster than
+    /// `timer`. Kept apart from gravity and from the left/right AutoShift.
+    pub soft_drop: Timer,
+}
+
+impl Default for GravityTimer {
+    fn default() -> Self {
+        GravityTimer {
+            timer: Timer::from_seconds(2.0, TimerMode::Repeating),
+            soft_drop: Timer::from_seconds(0.1, TimerMode::Repeating),
+        }
+    }
+}
+
+impl GravityTimer {
+    // Rows a held soft drop falls this frame. Gravity waits while it is held
+    // and picks up where it left off on release.
+    fn soft_drop_rows(&mut self, delta: Duration, factor: u32, just_pressed: bool) -> u32 {
+        let interval = (self.timer.duration() / factor.max(1)).max(Duration::from_millis(1));
+        if just_pressed || self.soft_drop.duration() != interval {
+            self.soft_drop = Timer::new(interval, TimerMode::Repeating);
+        }
+        self.soft_drop.tick(delta);
+        self.soft_drop.times_finished_this_tick()
+    }
+}
+
+// Delayed auto shift (DAS) and auto repeat rate (ARR) for held left/right
+#[derive(Resource)]
+pub struct AutoShift {
+    pub das: Duration,
+    pub arr: Duration,
+    direction: isize,
+    held_for: Duration,
+    repeats: u32,
+}
+
+impl Default for AutoShift {
+    fn default() -> Self {
+        AutoShift {
+            das: Duration::from_millis(170),
+            arr: Duration::from_millis(50),
+            direction: 0,
+            held_for: Duration::ZERO,
+            repeats: 0,
+        }
+    }
+}
+
+// Marker for the sprites draw_blocks rebuilds every frame
+#[derive(Component)]
+struct Block;
+
 // New marker component for score display
 #[derive(Component)]
 struct ScoreDisplay;
@@ -42,8 +291,14 @@ struct ScoreDisplay;
 struct LevelDisplay;
 
 fn main() {
-    App::new()
-        .insert_resource(ClearColor(GameColor::Gray.into()))
+    let options = cli::LaunchOptions::parse(std::env::args().skip(1));
+    if let Some(pieces) = options.simulate {
+        simulation::run_headless(options.seed.unwrap_or_else(rand::random), pieces);
+        return;
+    }
+    let mut app = App::new();
+    options.apply(&mut app);
+    app.insert_resource(ClearColor(ColorTheme::default().background()))
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: TITLE.into(),
@@ -53,35 +308,266 @@ fn main() {
             ..default()
         }))
         .init_resource::<GameMap>()
+        .init_resource::<BoardConfig>()
+        .init_resource::<BigMode>()
+        .init_resource::<TallBoard>()
+        .init_resource::<BoardSize>()
+        .init_resource::<ReverseGravity>()
+        .init_resource::<WrapEdges>()
         .init_resource::<Score>() // Add Score resource
         .init_resource::<Level>() // Add Level resource
-        .insert_resource(Time::<Fixed>::from_seconds(2.0))
+        .init_resource::<LockDelay>()
+        .init_resource::<EntryDelay>()
+        .init_resource::<AutoShift>()
+        .init_resource::<Difficulty>()
+        .init_resource::<RulesConfig>()
+        .init_resource::<GameRng>()
+        .init_resource::<PieceQueue>()
+        .init_resource::<HoldSlot>()
+        .init_resource::<GravityTimer>()
+        .init_resource::<GravityCurve>()
+        .init_resource::<LevelSelect>()
+        .init_resource::<PracticeMode>()
+        .init_resource::<GravityMultiplier>()
+        .init_resource::<PlacementHints>()
+        .init_resource::<PendingGarbage>()
+        .init_resource::<GarbageChallenge>()
+        .init_resource::<GarbageChallengeTimer>()
+        .init_resource::<DebugSettings>()
+        .init_resource::<VisualSettings>()
+        .init_resource::<EventLog>()
+        .init_resource::<ColumnTargeting>()
+        .init_resource::<MirrorBoard>()
+        .init_resource::<ActivePuzzle>()
+        .init_resource::<LastKick>()
+        .init_resource::<KickAttempt>()
+        .init_resource::<LastRotation>()
+        .init_resource::<LineClearAnimation>()
+        .init_resource::<RowCollapse>()
+        .init_resource::<SeedEntry>()
+        .init_resource::<NewHighScore>()
+        .init_resource::<ActionTrace>()
+        .init_resource::<SurvivalMode>()
+        .init_resource::<SurvivalClock>()
+        .init_resource::<FadeTransition>()
+        .init_resource::<RhythmMode>()
+        .init_resource::<Metronome>()
+        .init_resource::<QuickRestart>()
+        .init_resource::<PieceLimit>()
+        .init_resource::<PiecesPlaced>()
+        .init_resource::<ColorTheme>()
+        .init_resource::<TouchControls>()
+        .init_resource::<MouseControls>()
+        .init_resource::<MouseBindings>()
+        .init_resource::<UiLayout>()
+        .init_resource::<MenuPage>()
+        .init_resource::<PracticeGarbage>()
+        .init_resource::<RunStats>()
+        .init_resource::<HoldSwapAnimation>()
+        .init_resource::<DrawBenchmark>()
+        .init_resource::<RunStatsExport>()
+        .init_resource::<StreakHud>()
+        .init_resource::<ClearStreak>()
+        .init_resource::<TelemetryBuffer>()
+        .init_resource::<ResumableGame>()
+        .add_event::<PieceSpawned>()
+        .add_event::<PieceMovedDown>()
+        .add_event::<PieceLocked>()
+        .add_event::<Spin>()
+        .add_event::<PieceHeld>()
+        .add_event::<LinesCleared>()
+        .add_event::<PerfectClear>()
+        .add_event::<LevelUp>()
+        .add_event::<GameOver>()
+        .add_event::<GameEvent>()
         .init_state::<GameState>()
         .add_systems(
             Startup,
             (
+                (theme::load_color_theme, savegame::load_saved_game).before(menu::setup_main_menu),
+                high_scores::load_high_scores,
                 setup_camera,
-                spawn_initial_piece,
+                menu::setup_main_menu,
                 setup_ui,
                 setup_game_over_ui,
-                update_gravity_speed,
+                hold::setup_hold_display,
+                practice::setup_gravity_multiplier_display,
+                garbage::setup_garbage_meter,
+                event_log::setup_event_log_panel,
+                action_trace::setup_action_trace_panel,
+                analysis::setup_board_metrics_panel,
+                seed::setup_seed_display,
+                (
+                    mode_summary::setup_mode_summary,
+                    rhythm::setup_rhythm_display,
+                    restart::setup_quick_restart_prompt,
+                    blitz::setup_piece_limit_display,
+                    streak::setup_streak_display,
+                ),
+                (
+                    transition::setup_fade_overlay,
+                    touch::setup_touch_buttons,
+                    mouse::load_mouse_bindings,
+                    pause::setup_pause_banner,
+                    visual::setup_drop_distance_label,
+                ),
+                (
+                    debug::setup_rotation_overlay,
+                    debug::setup_lock_reset_bar,
+                    debug::setup_gravity_mode_indicator,
+                ),
+                (gravity_curve::load_gravity_curve, update_gravity_speed).chain(),
             ),
         ) // Add setup_game_over_ui here
         .add_systems(
             Update,
             (
-                handle_input,
-                draw_blocks,
+                (savegame::continue_saved_game, menu::main_menu_input)
+                    .chain()
+                    .run_if(in_state(GameState::MainMenu)),
+                (
+                    despawn_extra_pieces,
+                    rhythm::tick_metronome,
+                    handle_input,
+                    accessibility::target_column,
+                    auto_shift_piece,
+                    hard_drop_piece,
+                    move_piece_down,
+                    lock_grounded_piece,
+                    spin::award_spin_bonus,
+                    rhythm::award_rhythm_bonus,
+                    spawn_after_entry_delay,
+                    puzzle::restart_puzzle_on_top_out,
+                    blitz::count_placed_pieces,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(line_clear::gameplay_running),
+                (
+                    practice::adjust_gravity_multiplier,
+                    practice::force_next_piece,
+                    practice::cycle_active_piece,
+                    practice::clear_bottom_row,
+                    practice::clear_whole_board,
+                    (
+                        practice::toggle_placement_hints,
+                        practice::update_placement_hints,
+                    )
+                        .chain(),
+                )
+                    .run_if(in_state(GameState::Playing)),
+                (
+                    garbage::queue_challenge_garbage,
+                    survival::tick_survival_clock.run_if(line_clear::gameplay_running),
+                    run_stats::track_run_stats,
+                    (
+                        restart::quick_restart.before(rhythm::tick_metronome),
+                        restart::update_quick_restart_prompt,
+                    )
+                        .chain(),
+                )
+                    .run_if(in_state(GameState::Playing)),
+                (
+                    visual::interpolate_piece_motion,
+                    visual::update_board_scroll,
+                    line_clear::advance_row_collapse,
+                    (hold::start_hold_swap_animation, hold::animate_hold_swap).chain(),
+                )
+                    .before(draw_blocks),
+                (
+                    (
+                        debug::start_draw_benchmark,
+                        debug::begin_draw_timing,
+                        draw_blocks,
+                        debug::end_draw_timing,
+                    )
+                        .chain(),
+                    visual::update_drop_distance_label,
+                ),
                 clear_lines,
                 update_score_display,
                 update_gravity_speed,
                 update_level_display,
-                display_game_over_message.run_if(in_state(GameState::GameOver)),
+                hold::update_hold_display,
+                practice::update_gravity_multiplier_display,
+                garbage::update_garbage_meter,
+                (
+                    seed::copy_seed,
+                    seed::update_seed_display,
+                    mode_summary::update_mode_summary,
+                    rhythm::update_rhythm_display,
+                    blitz::update_piece_limit_display,
+                    (streak::track_clear_streak, streak::update_streak_display).chain(),
+                ),
+                (
+                    (transition::start_fade, transition::animate_fade).chain(),
+                    pause::toggle_pause,
+                ),
+                (
+                    ui_scale::update_ui_scale,
+                    theme::apply_color_theme,
+                    hud_layout::apply_ui_layout,
+                ),
+                (
+                    touch::press_touch_buttons
+                        .before(rhythm::tick_metronome)
+                        .before(pause::toggle_pause),
+                    mouse::press_mouse_bindings
+                        .before(rhythm::tick_metronome)
+                        .before(pause::toggle_pause),
+                    touch::update_touch_button_visibility,
+                ),
+                (
+                    debug::toggle_debug_settings,
+                    debug::update_grid_rulers,
+                    debug::update_rotation_overlay,
+                    debug::update_kick_overlay,
+                    debug::update_heat_map,
+                    debug::update_lock_reset_bar,
+                    debug::update_gravity_mode_indicator,
+                    analysis::update_board_metrics_panel,
+                    action_trace::update_action_trace_panel
+                        .after(puzzle::restart_puzzle_on_top_out),
+                )
+                    .chain(),
+                (
+                    events::emit_piece_spawned,
+                    event_log::record_events,
+                    event_log::update_event_log_panel,
+                    telemetry::emit_game_events
+                        .after(puzzle::restart_puzzle_on_top_out)
+                        .before(action_trace::update_action_trace_panel),
+                    telemetry::record_telemetry,
+                )
+                    .chain(),
+                (
+                    display_game_over_message,
+                    screenshot::export_board_screenshot,
+                    high_scores::animate_new_high_score_banner,
+                )
+                    .run_if(in_state(GameState::GameOver)),
             ),
         ) // Add update_level_display here
+        .add_systems(OnEnter(GameState::Playing), validate_board_dimensions)
+        .add_systems(
+            OnEnter(GameState::Paused),
+            (pause::pause_game, savegame::save_game_on_pause),
+        )
+        .add_systems(OnExit(GameState::Paused), pause::resume_game)
+        .add_systems(Last, savegame::save_game_on_exit)
         .add_systems(
-            FixedUpdate,
-            move_piece_down.run_if(in_state(GameState::Playing)),
+            OnEnter(GameState::GameOver),
+            (
+                events::emit_game_over,
+                savegame::discard_saved_game,
+                run_stats::export_run_stats,
+                (
+                    high_scores::record_high_score,
+                    high_scores::spawn_high_score_panel,
+                    high_scores::celebrate_new_high_score,
+                )
+                    .chain(),
+            ),
         )
         .run();
 }
@@ -90,92 +576,199 @@ fn setup_camera(mut commands: Commands) {
     commands.spawn(Camera2dBundle::default());
 }
 
-fn spawn_piece(commands: &mut Commands, game_map: &GameMap, game_state: &mut NextState<GameState>) {
-    let new_piece = Piece::random();
-    let initial_position = Position {
-        x: NUM_BLOCKS_X as isize / 2 - 1,
-        y: 0,
-    };
-
-    if can_move(&new_piece, &initial_position, initial_position.y, &game_map) {
-        commands.spawn((new_piece, initial_position));
-        println!("Spawned new piece");
+fn spawn_piece(
+    commands: &mut Commands,
+    game_map: &GameMap,
+    board: &BoardConfig,
+    rules: &RulesConfig,
+    queue: &mut PieceQueue,
+    rng: &mut GameRng,
+    game_state: &mut NextState<GameState>,
+) {
+    let piece_type = queue.next(rng, &rules.piece_weights);
+    let new_piece = Piece::for_rules(piece_type, rules, rng);
+    let top_row = visual::scroll_row_for(board, game_map) as isize;
+    if let Some(initial_position) = spawn_position(&new_piece, game_map, rules, top_row) {
+        commands.spawn((
+            new_piece,
+            initial_position,
+            VisualOffset::new(initial_position),
+        ));
     } else {
-        println!("Game Over! Cannot spawn new piece.");
         game_state.set(GameState::GameOver);
     }
 }
 
-fn spawn_initial_piece(
-    mut commands: Commands,
-    game_map: Res<GameMap>,
-    mut game_state: ResMut<NextState<GameState>>,
-) {
-    spawn_piece(&mut commands, &game_map, &mut game_state);
+// Keeps a single active piece. A stale piece left over from a restart would
+// make every get_single piece query fail and stall the game, so all but the
+// newest piece are despawned.
+fn despawn_extra_pieces(mut commands: Commands, query_piece: Query<(Entity, Ref<Piece>)>) {
+    if query_piece.iter().len() < 2 {
+        return;
+    }
+    let newest = query_piece
+        .iter()
+        .max_by_key(|(entity, piece)| (piece.is_added(), *entity))
+        .map(|(entity, _)| entity);
+    for (entity, _) in query_piece.iter() {
+        if Some(entity) != newest {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// Where a new piece enters the board, or None if the stack has topped out.
+// Pieces enter at `top_row`, the top of the visible window; on a tall board
+// that window only reaches the logical top once the stack has grown there.
+fn spawn_position(
+    piece: &Piece,
+    game_map: &GameMap,
+    rules: &RulesConfig,
+    top_row: isize,
+) -> Option<Position> {
+    let mut position = Position {
+        x: game_map.width() as isize / 2 - 1,
+        y: top_row,
+    };
+
+    // Spawn push lets a blocked piece start a few rows higher before topping out
+    let max_push = if rules.spawn_push { SPAWN_PUSH_ROWS } else { 0 };
+    for _ in 0..=max_push {
+        if can_move(piece, &position, position.y, game_map) {
+            return Some(position);
+        }
+        position.y -= 1;
+    }
+    None
 }
 
 // System to draw blocks
+#[allow(clippy::too_many_arguments)]
 fn draw_blocks(
     mut commands: Commands,
     game_map: Res<GameMap>,
-    query_piece: Query<(&Piece, &Position)>,
-    query_existing_blocks: Query<Entity, With<Sprite>>,
+    board: Res<BoardConfig>,
+    visual_settings: Res<VisualSettings>,
+    line_clear: Res<LineClearAnimation>,
+    theme: Res<ColorTheme>,
+    collapse: Res<RowCollapse>,
+    level: Res<Level>,
+    hold_swap: Res<HoldSwapAnimation>,
+    query_piece: Query<(&Piece, &Position, &VisualOffset)>,
+    query_existing_blocks: Query<Entity, With<Block>>,
 ) {
     // Despawn all existing block sprites to redraw
     for entity in query_existing_blocks.iter() {
         commands.entity(entity).despawn();
     }
 
+    let completing_rows = match query_piece.get_single() {
+        Ok((piece, position, _)) if visual_settings.preview_completed_rows => {
+            visual::rows_completed_on_drop(piece, position, &game_map)
+        }
+        _ => Vec::new(),
+    };
+
     // Draw GameMap blocks
-    for y in 0..NUM_BLOCKS_Y {
-        for x in 0..NUM_BLOCKS_X {
-            if let Presence::Yes(color) = game_map.0[y][x] {
-                commands.spawn(SpriteBundle {
-                    sprite: Sprite {
-                        color: color.into(),
-                        custom_size: Some(Vec2::new(TEXTURE_SIZE as f32, TEXTURE_SIZE as f32)),
-                        ..default()
-                    },
-                    transform: Transform::from_xyz(
-                        (x as f32 * TEXTURE_SIZE as f32) - (WIDTH as f32 / 2.0)
-                            + (TEXTURE_SIZE as f32 / 2.0),
-                        (HEIGHT as f32 / 2.0)
-                            - (y as f32 * TEXTURE_SIZE as f32)
-                            - (TEXTURE_SIZE as f32 / 2.0),
-                        0.0,
-                    ),
-                    ..default()
-                });
+    for (y, row) in game_map.rows.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            if let Presence::Yes(color) = *cell
+                && board.is_row_visible(y as isize)
+            {
+                let mut sprite = block_sprite(&board, x as isize, y as isize, theme.color(color));
+                if completing_rows.contains(&y) {
+                    visual::light_completing_cell(&mut sprite);
+                }
+                if line_clear.rows.contains(&y) {
+                    line_clear::animate_cell(
+                        &mut sprite,
+                        &board,
+                        x,
+                        visual_settings.line_clear_style,
+                        line_clear.progress(),
+                    );
+                }
+                if line_clear.highlight.contains(&(x as isize, y as isize)) {
+                    line_clear::highlight_cell(&mut sprite, line_clear.progress());
+                }
+                let row_up = board.cell_translation(0, y as isize - 1)
+                    - board.cell_translation(0, y as isize);
+                sprite.transform.translation += (row_up * collapse.remaining(y)).extend(0.0);
+                commands.spawn((sprite, Block));
             }
         }
     }
 
+    // Draw the cells outside the playable shape of a masked board
+    for &(x, y) in &game_map.blocked {
+        if board.is_row_visible(y as isize) {
+            let color = theme.color(GameColor::Gray);
+            commands.spawn((block_sprite(&board, x as isize, y as isize, color), Block));
+        }
+    }
+
     // Draw current piece blocks
-    if let Ok((piece, position)) = query_piece.get_single() {
-        let piece_matrix = get_block_matrix(piece.states[piece.current_state], piece.color);
-        for my in 0..4 {
-            for mx in 0..4 {
-                if let Presence::Yes(color) = piece_matrix[my][mx] {
-                    commands.spawn(SpriteBundle {
-                        sprite: Sprite {
-                            color: color.into(),
-                            custom_size: Some(Vec2::new(TEXTURE_SIZE as f32, TEXTURE_SIZE as f32)),
-                            ..default()
-                        },
-                        transform: Transform::from_xyz(
-                            ((position.x + mx as isize) as f32 * TEXTURE_SIZE as f32)
-                                - (WIDTH as f32 / 2.0)
-                                + (TEXTURE_SIZE as f32 / 2.0),
-                            (HEIGHT as f32 / 2.0)
-                                - ((position.y + my as isize) as f32 * TEXTURE_SIZE as f32)
-                                - (TEXTURE_SIZE as f32 / 2.0),
-                            0.0,
-                        ),
-                        ..default()
-                    });
-                }
+    if let Ok((piece, position, visual)) = query_piece.get_single() {
+        // Ghost shows where a hard drop would land
+        let landing_y = position.y + drop_distance(piece, position, &game_map);
+        let landing = Position {
+            x: position.x,
+            y: landing_y,
+        };
+        if visual_settings.shows_ghost(level.value) {
+            let ghost_cells =
+                board.visible_cells(game_map.wrapped_cells(piece_cells(piece, &landing)));
+            let ghost = visual::ghost_sprites(
+                &board,
+                &ghost_cells,
+                theme.color(piece.color),
+                visual_settings.ghost_style,
+            );
+            for sprite in ghost {
+                commands.spawn((sprite, Block));
             }
         }
+        if visual_settings.show_trajectory {
+            let trajectory = visual::trajectory_sprites(
+                &board,
+                &game_map.wrapped_cells(piece_cells(piece, position)),
+                landing_y - position.y,
+                theme.color(piece.color),
+            );
+            for sprite in trajectory {
+                commands.spawn((sprite, Block));
+            }
+        }
+
+        let glide = Vec3::new(
+            board.screen_dx(1) as f32 * visual.offset.x,
+            board.screen_dy(1) as f32 * visual.offset.y,
+            0.0,
+        ) * board.cell_size;
+        // A piece still in the hidden rows above the window shows only the
+        // cells that have entered it, and a piece straddling the seam of a
+        // wrapping board shows on both sides
+        let cells = game_map.wrapped_cells(piece_cells(piece, position));
+        for (x, y) in board.visible_cells(cells) {
+            let mut sprite = block_sprite(&board, x, y, theme.color(piece.color));
+            sprite.transform.translation += glide;
+            sprite.transform.translation.z = Z_PIECE;
+            sprite.transform.scale = Vec3::splat(hold_swap.incoming_scale());
+            commands.spawn((sprite, Block));
+        }
+    }
+}
+
+// Sprite for one logical board cell, sized and placed by the board config
+fn block_sprite(board: &BoardConfig, x: isize, y: isize, color: Color) -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite {
+            color,
+            custom_size: Some(Vec2::new(board.cell_size, board.cell_size)),
+            ..default()
+        },
+        transform: Transform::from_translation(board.cell_translation(x, y).extend(Z_BLOCKS)),
+        ..default()
     }
 }
 
@@ -190,62 +783,256 @@ fn get_block_matrix(num: u16, color: GameColor) -> PieceMatrix {
     res
 }
 
+// Holding soft drop swaps gravity for its own repeat clock, soft_drop_factor
+// times faster than gravity, so its speed follows the level curve and neither
+// the frame rate nor the left/right repeat timing. With the release grace rule
+// gravity also waits out the frame soft drop is let go.
+#[allow(clippy::too_many_arguments)]
 fn move_piece_down(
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    rules: Res<RulesConfig>,
+    mut gravity: ResMut<GravityTimer>,
+    mut lock_delay: ResMut<LockDelay>,
+    mut query_piece: Query<(&Piece, &mut Position)>,
+    game_map: Res<GameMap>,
+    board: Res<BoardConfig>,
+    mut moved_down: EventWriter<PieceMovedDown>,
+    mut action_trace: ResMut<ActionTrace>,
+    debug_settings: Res<DebugSettings>,
+    metronome: Res<Metronome>,
+    (mut score, survival): (ResMut<Score>, Res<SurvivalMode>),
+) {
+    let soft_dropping = keyboard_input.pressed(board.soft_drop_key());
+    // Only rows the soft drop clock itself moves score; debug stepping and
+    // the rhythm beat do not
+    let mut soft_drop_scores = false;
+    // Step mode freezes the gravity clock; each step key press falls one row.
+    // Compose mode does the same with the soft drop key. Rhythm play replaces
+    // the clock with the beat, soft drop included.
+    let rows = if debug_settings.compose_mode {
+        u32::from(keyboard_input.just_pressed(board.soft_drop_key()))
+    } else if debug_settings.step_mode {
+        debug::stepped_rows(&keyboard_input)
+    } else if let Some(beats) = metronome.beats_this_frame() {
+        beats
+    } else if soft_dropping {
+        soft_drop_scores = !survival.0;
+        gravity.soft_drop_rows(
+            time.delta(),
+            rules.soft_drop_factor,
+            keyboard_input.just_pressed(board.soft_drop_key()),
+        )
+    } else if rules.soft_drop_release_grace && keyboard_input.just_released(board.soft_drop_key()) {
+        0
+    } else {
+        gravity.timer.tick(time.delta());
+        gravity.timer.times_finished_this_tick()
+    };
+
+    if let Ok((piece, mut position)) = query_piece.get_single_mut() {
+        // Several rows can fall in one frame at high gravity; step them one at
+        // a time so the piece stops on the first ledge instead of passing it
+        for _ in 0..rows {
+            let new_y = position.y + 1;
+            if !can_move(piece, &position, new_y, &game_map) {
+                break;
+            }
+            position.y = new_y;
+            lock_delay.descended(new_y);
+            moved_down.send(PieceMovedDown { y: new_y });
+            if soft_dropping {
+                action_trace.record(GameAction::SoftDrop);
+            }
+            if soft_drop_scores {
+                score.value += rules.soft_drop_points;
+            }
+        }
+        if soft_dropping
+            && rules.soft_drop_locks
+            && !can_move(piece, &position, position.y + 1, &game_map)
+        {
+            lock_delay.expire();
+        }
+    }
+}
+
+// Locks the piece once it has rested on the stack for the full lock delay
+#[allow(clippy::too_many_arguments)]
+fn lock_grounded_piece(
     mut commands: Commands,
-    mut query_piece: Query<(Entity, &mut Piece, &mut Position)>,
-    mut game_map: ResMut<GameMap>, // Make game_map mutable
+    time: Res<Time>,
+    mut lock_delay: ResMut<LockDelay>,
+    query_piece: Query<(Entity, &Piece, &Position)>,
+    mut game_map: ResMut<GameMap>,
+    rules: Res<RulesConfig>,
+    mut entry_delay: ResMut<EntryDelay>,
+    mut rng: ResMut<GameRng>,
+    mut hold: ResMut<HoldSlot>,
+    mut pending_garbage: ResMut<PendingGarbage>,
+    mut locked: EventWriter<PieceLocked>,
     mut game_state: ResMut<NextState<GameState>>,
+    debug_settings: Res<DebugSettings>,
+    mut last_rotation: ResMut<LastRotation>,
 ) {
-    if let Ok((entity, piece, mut position)) = query_piece.get_single_mut() {
-        let new_y = position.y + 1;
-        if can_move(&piece, &position, new_y, &game_map) {
-            position.y = new_y;
-            println!("Piece moved down to y: {}", position.y);
-        } else {
+    if debug_settings.compose_mode {
+        return;
+    }
+    if let Ok((entity, piece, position)) = query_piece.get_single() {
+        if can_move(piece, position, position.y + 1, &game_map) {
+            lock_delay.timer.reset();
+            return;
+        }
+
+        lock_delay.timer.tick(time.delta());
+        if lock_delay.timer.finished() {
+            lock_delay.reset();
             // Collision detected, finalize piece placement
-            let piece_matrix = get_block_matrix(piece.states[piece.current_state], piece.color);
-            for my in 0..4 {
-                for mx in 0..4 {
-                    if let Presence::Yes(color) = piece_matrix[my][mx] {
-                        let map_x = position.x + mx as isize;
-                        let map_y = position.y + my as isize;
-                        if map_x >= 0
-                            && map_x < NUM_BLOCKS_X as isize
-                            && map_y >= 0
-                            && map_y < NUM_BLOCKS_Y as isize
-                        {
-                            game_map.0[map_y as usize][map_x as usize] = Presence::Yes(color);
-                        }
-                    }
-                }
+            finalize_piece(
+                &mut commands,
+                entity,
+                piece,
+                position,
+                &mut game_map,
+                &rules,
+                &mut entry_delay,
+                &mut rng,
+                &mut hold,
+                &mut pending_garbage,
+                &mut locked,
+                &mut game_state,
+                last_rotation.0.take() == Some(*position),
+            );
+        }
+    }
+}
+
+// Writes a landed piece into the map, despawns it, raises any pending
+// garbage, announces the lock and starts the entry delay before the next
+// piece. Every drop path (gravity, soft drop, hard drop) ends here.
+// `rotated_into_place` says a rotation was the piece's last move, which
+// makes the lock a spin candidate.
+#[allow(clippy::too_many_arguments)]
+fn finalize_piece(
+    commands: &mut Commands,
+    entity: Entity,
+    piece: &Piece,
+    position: &Position,
+    game_map: &mut GameMap,
+    rules: &RulesConfig,
+    entry_delay: &mut EntryDelay,
+    rng: &mut GameRng,
+    hold: &mut HoldSlot,
+    pending_garbage: &mut PendingGarbage,
+    locked: &mut EventWriter<PieceLocked>,
+    game_state: &mut NextState<GameState>,
+    rotated_into_place: bool,
+) {
+    let spin = rotated_into_place && rules.all_spin && spin::is_immobile(piece, position, game_map);
+    place_piece(piece, position, game_map);
+    hold.refresh();
+    garbage::rise_pending_garbage(
+        game_map,
+        pending_garbage,
+        rules.garbage_style,
+        rng,
+        game_state,
+    );
+    commands.entity(entity).despawn(); // Despawn the piece entity
+    let cells = game_map.wrapped_cells(piece_cells(piece, position));
+    locked.send(PieceLocked {
+        piece_type: piece.piece_type,
+        position: *position,
+        cells: [cells[0], cells[1], cells[2], cells[3]],
+        spin,
+    });
+    entry_delay.start(rules.entry_delay);
+}
+
+// Spawns the next piece once the entry delay after a lock runs out. With
+// line_clear_pause on, the delay waits for full rows to animate and clear so
+// the new piece never falls into rows that are about to shift.
+#[allow(clippy::too_many_arguments)]
+fn spawn_after_entry_delay(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut entry_delay: ResMut<EntryDelay>,
+    game_map: Res<GameMap>,
+    board: Res<BoardConfig>,
+    rules: Res<RulesConfig>,
+    mut queue: ResMut<PieceQueue>,
+    mut rng: ResMut<GameRng>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if rules.line_clear_pause && game_map.full_row_count() > 0 {
+        return;
+    }
+    if entry_delay.tick(time.delta()) {
+        spawn_piece(
+            &mut commands,
+            &game_map,
+            &board,
+            &rules,
+            &mut queue,
+            &mut rng,
+            &mut game_state,
+        );
+    }
+}
+
+// Writes the piece's cells into the game map, ignoring cells outside the board
+fn place_piece(piece: &Piece, position: &Position, game_map: &mut GameMap) {
+    let piece_matrix = get_block_matrix(piece.states[piece.current_state], piece.color);
+    for (my, row) in piece_matrix.iter().enumerate() {
+        for (mx, cell) in row.iter().enumerate() {
+            if let Presence::Yes(color) = *cell {
+                let map_x = position.x + mx as isize;
+                let map_y = position.y + my as isize;
+                game_map.set_cell(map_x, map_y, Presence::Yes(color));
             }
-            commands.entity(entity).despawn(); // Despawn the piece entity
-            spawn_piece(&mut commands, &game_map, &mut game_state);
-            println!("Piece landed at y: {}", position.y);
-            println!("Piece finalized and added to game map.");
         }
     }
 }
 
 // Helper function to check if a piece can move to a new position
 fn can_move(piece: &Piece, current_pos: &Position, new_y: isize, game_map: &GameMap) -> bool {
+    can_move_avoiding(piece, current_pos, new_y, game_map, &[])
+}
+
+// can_move that also treats `occupied` board cells as solid, such as the
+// cells of another active piece sharing the same GameMap
+fn can_move_avoiding(
+    piece: &Piece,
+    current_pos: &Position,
+    new_y: isize,
+    game_map: &GameMap,
+    occupied: &[(isize, isize)],
+) -> bool {
     let piece_matrix = get_block_matrix(piece.states[piece.current_state], piece.color);
-    for my in 0..4 {
-        for mx in 0..4 {
-            if let Presence::Yes(_) = piece_matrix[my][mx] {
-                let block_x = current_pos.x + mx as isize;
+    for (my, row) in piece_matrix.iter().enumerate() {
+        for (mx, cell) in row.iter().enumerate() {
+            if let Presence::Yes(_) = *cell {
                 let block_y = new_y + my as isize;
 
-                // Check collision with bottom boundary
-                if block_y >= NUM_BLOCKS_Y as isize {
+                // Check collision with side walls and bottom boundary; on a
+                // wrapping board there are no side walls
+                let Some(block_x) = game_map.column(current_pos.x + mx as isize) else {
+                    return false;
+                };
+                if block_y >= game_map.height() as isize {
                     return false;
                 }
 
-                // Check collision with existing blocks on the game map
-                if block_x >= 0 && block_x < NUM_BLOCKS_X as isize && block_y >= 0 {
-                    if let Presence::Yes(_) = game_map.0[block_y as usize][block_x as usize] {
-                        return false;
-                    }
+                // Check collision with existing blocks on the game map; rows
+                // above the visible board are always open
+                if block_y >= 0
+                    && let Some(Presence::Yes(_)) = game_map.cell(block_x, block_y)
+                {
+                    return false;
+                }
+
+                if occupied.contains(&(block_x, block_y)) {
+                    return false;
                 }
             }
         }
@@ -253,94 +1040,27 @@ fn can_move(piece: &Piece, current_pos: &Position, new_y: isize, game_map: &Game
     true
 }
 
-// From<PieceType> for Piece implementation
-impl From<PieceType> for Piece {
-    fn from(piece_type: PieceType) -> Piece {
-        use self::PieceType::*;
-
-        let def = Piece::default();
-
-        match piece_type {
-            L => Piece {
-                states: [17504, 1856, 1570, 736],
-                color: GameColor::Orange,
-                ..def
-            },
-            J => Piece {
-                states: [8800, 1136, 1604, 3616],
-                color: GameColor::Blue,
-                ..def
-            },
-            S => Piece {
-                states: [17952, 1728, 17952, 1728],
-                color: GameColor::Green,
-                ..def
-            },
-            Z => Piece {
-                states: [9792, 3168, 9792, 3168],
-                color: GameColor::Red,
-                ..def
-            },
-            T => Piece {
-                states: [17984, 3648, 19520, 19968],
-                color: GameColor::Purple,
-                ..def
-            },
-            I => Piece {
-                states: [17476, 3840, 17476, 3840],
-                color: GameColor::Cyan,
-                ..def
-            },
-            O => Piece {
-                states: [1632, 1632, 1632, 1632],
-                color: GameColor::Yellow,
-                ..def
-            },
-        }
-    }
-}
-
-impl Piece {
-    pub fn random() -> Self {
-        let mut rng = rng();
-        let piece_type = match rng.random_range(0..7) {
-            0 => PieceType::L,
-            1 => PieceType::J,
-            2 => PieceType::S,
-            3 => PieceType::Z,
-            4 => PieceType::T,
-            5 => PieceType::I,
-            _ => PieceType::O,
-        };
-        Piece::from(piece_type)
-    }
+// Board cells covered by a piece at a position, as (x, y)
+fn piece_cells(piece: &Piece, position: &Position) -> Vec<(isize, isize)> {
+    pieces::state_cells(piece, piece.current_state)
+        .into_iter()
+        .map(|(mx, my)| (position.x + mx as isize, position.y + my as isize))
+        .collect()
 }
 
+// Whether the piece fits in its current state, by the same rules as can_move,
+// so a piece pushed above the board can still turn there
 fn can_rotate(piece: &Piece, current_pos: &Position, game_map: &GameMap) -> bool {
-    let piece_matrix = get_block_matrix(piece.states[piece.current_state], piece.color);
-    for my in 0..4 {
-        for mx in 0..4 {
-            if let Presence::Yes(_) = piece_matrix[my][mx] {
-                let block_x = current_pos.x + mx as isize;
-                let block_y = current_pos.y + my as isize;
-
-                // Check collision with boundaries
-                if block_x < 0
-                    || block_x >= NUM_BLOCKS_X as isize
-                    || block_y < 0
-                    || block_y >= NUM_BLOCKS_Y as isize
-                {
-                    return false;
-                }
+    can_move(piece, current_pos, current_pos.y, game_map)
+}
 
-                // Check collision with existing blocks on the game map
-                if let Presence::Yes(_) = game_map.0[block_y as usize][block_x as usize] {
-                    return false;
-                }
-            }
-        }
+// Rows a hard drop would move the piece before it lands
+fn drop_distance(piece: &Piece, position: &Position, game_map: &GameMap) -> isize {
+    let mut distance = 0;
+    while can_move(piece, position, position.y + distance + 1, game_map) {
+        distance += 1;
     }
-    true
+    distance
 }
 
 fn can_move_horizontally(
@@ -349,171 +1069,325 @@ fn can_move_horizontally(
     new_x: isize,
     game_map: &GameMap,
 ) -> bool {
-    let piece_matrix = get_block_matrix(piece.states[piece.current_state], piece.color);
-    for my in 0..4 {
-        for mx in 0..4 {
-            if let Presence::Yes(_) = piece_matrix[my][mx] {
-                let block_x = new_x + mx as isize;
-                let block_y = current_pos.y + my as isize;
-
-                // Check collision with side boundaries
-                if block_x < 0 || block_x >= NUM_BLOCKS_X as isize {
-                    return false;
-                }
+    let shifted = Position {
+        x: new_x,
+        y: current_pos.y,
+    };
+    can_move(piece, &shifted, current_pos.y, game_map)
+}
 
-                // Check collision with existing blocks on the game map
-                if block_y >= 0
-                    && block_y < NUM_BLOCKS_Y as isize
-                    && block_x >= 0
-                    && block_x < NUM_BLOCKS_X as isize
-                {
-                    if let Presence::Yes(_) = game_map.0[block_y as usize][block_x as usize] {
-                        return false;
-                    }
-                }
+// Hold and rotation. Within a frame rotation applies before the horizontal
+// moves in auto_shift_piece and the drops in hard_drop_piece and
+// move_piece_down, so a rotate pressed with a move near a wall turns first.
+#[allow(clippy::too_many_arguments)]
+fn handle_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut query: Query<(&mut Position, &mut Piece)>,
+    game_map: Res<GameMap>,
+    board: Res<BoardConfig>,
+    mut lock_delay: ResMut<LockDelay>,
+    rules: Res<RulesConfig>,
+    mut queue: ResMut<PieceQueue>,
+    mut rng: ResMut<GameRng>,
+    mut hold: ResMut<HoldSlot>,
+    (mut last_kick, mut last_rotation, mut kick_attempt): (
+        ResMut<LastKick>,
+        ResMut<LastRotation>,
+        ResMut<KickAttempt>,
+    ),
+    mut action_trace: ResMut<ActionTrace>,
+    mut held: EventWriter<PieceHeld>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if let Ok((mut position, mut piece)) = query.get_single_mut() {
+        if keyboard_input.just_pressed(KeyCode::KeyC) {
+            let (outgoing, from) = (*piece, *position);
+            let swapped = hold::hold_piece(
+                &mut piece,
+                &mut position,
+                &mut hold,
+                &game_map,
+                &board,
+                &rules,
+                &mut queue,
+                &mut rng,
+                &mut game_state,
+            );
+            if swapped {
+                held.send(PieceHeld {
+                    outgoing,
+                    from,
+                    incoming: piece.piece_type,
+                });
+            }
+            lock_delay.reset();
+            last_rotation.0 = None;
+            return;
+        }
+
+        if keyboard_input.just_pressed(board.rotate_key()) {
+            // If no kick fits, the piece keeps its current state
+            let result = rotation::rotate_cw(&piece, &position, &game_map, &rules);
+            if rules.allow_rotation {
+                kick_attempt.record(&piece, &position, &game_map, &rules, result);
+            }
+            if let Some(rotation) = result {
+                piece.current_state = rotation.state;
+                *position = rotation.position;
+                last_kick.0 = Some(rotation.kick);
+                last_rotation.0 = Some(rotation.position);
+                action_trace.record(GameAction::RotateCw);
+                lock_delay.move_reset();
             }
         }
     }
-    true
 }
 
-fn handle_input(
+// Drops the piece straight to the floor and locks it. Runs after this
+// frame's rotation and horizontal moves, so it lands where they left it.
+#[allow(clippy::too_many_arguments)]
+fn hard_drop_piece(
     mut commands: Commands,
     keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut query: Query<(Entity, &mut Position, &mut Piece)>,
+    mut query: Query<(Entity, &mut Position, &Piece)>,
     mut game_map: ResMut<GameMap>,
     mut score: ResMut<Score>,
+    mut lock_delay: ResMut<LockDelay>,
+    rules: Res<RulesConfig>,
+    mut entry_delay: ResMut<EntryDelay>,
+    mut rng: ResMut<GameRng>,
+    mut hold: ResMut<HoldSlot>,
+    mut pending_garbage: ResMut<PendingGarbage>,
+    (mut action_trace, survival, debug_settings, mut last_rotation): (
+        ResMut<ActionTrace>,
+        Res<SurvivalMode>,
+        Res<DebugSettings>,
+        ResMut<LastRotation>,
+    ),
+    mut locked: EventWriter<PieceLocked>,
     mut game_state: ResMut<NextState<GameState>>,
 ) {
-    if let Ok((entity, mut position, mut piece)) = query.get_single_mut() {
-        if keyboard_input.just_pressed(bevy::input::keyboard::KeyCode::ArrowLeft) {
-            let new_x = position.x - 1;
-            if can_move_horizontally(&piece, &position, new_x, &game_map) {
-                position.x = new_x;
-            }
-        }
-        if keyboard_input.just_pressed(bevy::input::keyboard::KeyCode::ArrowRight) {
-            let new_x = position.x + 1;
-            if can_move_horizontally(&piece, &position, new_x, &game_map) {
-                position.x = new_x;
-            }
-        }
-        if keyboard_input.just_pressed(bevy::input::keyboard::KeyCode::ArrowDown) {
-            let new_y = position.y + 1;
-            if can_move(&piece, &position, new_y, &game_map) {
-                position.y = new_y;
-            }
+    // Compose mode never locks, so hard drop is off while it is on
+    if !keyboard_input.just_pressed(KeyCode::Space) || debug_settings.compose_mode {
+        return;
+    }
+    let Ok((entity, mut position, piece)) = query.get_single_mut() else {
+        return;
+    };
+    action_trace.record(GameAction::HardDrop);
+    let final_y = position.y + drop_distance(piece, &position, &game_map);
+    if final_y > position.y {
+        if !survival.0 {
+            score.value += (final_y - position.y) as u32;
         }
+        position.y = final_y;
+    }
 
-        if keyboard_input.just_pressed(bevy::input::keyboard::KeyCode::Space) {
-            println!("Space key pressed");
-            let mut final_y = position.y;
-            while can_move(&piece, &position, final_y + 1, &game_map) {
-                final_y += 1;
-            }
+    // Lock the piece
+    finalize_piece(
+        &mut commands,
+        entity,
+        piece,
+        &position,
+        &mut game_map,
+        &rules,
+        &mut entry_delay,
+        &mut rng,
+        &mut hold,
+        &mut pending_garbage,
+        &mut locked,
+        &mut game_state,
+        last_rotation.0.take() == Some(*position),
+    );
+    lock_delay.reset();
+}
 
-            if final_y > position.y {
-                score.value += (final_y - position.y) as u32;
-                position.y = final_y;
-            }
+// Owns all left/right movement: one step when a direction is pressed, then
+// after the DAS charge a further step every ARR while it stays held. Left and
+// right follow the screen, which may be mirrored.
+#[allow(clippy::too_many_arguments)]
+fn auto_shift_piece(
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut auto_shift: ResMut<AutoShift>,
+    mut query: Query<(&mut Position, &Piece)>,
+    game_map: Res<GameMap>,
+    board: Res<BoardConfig>,
+    mut lock_delay: ResMut<LockDelay>,
+    mut action_trace: ResMut<ActionTrace>,
+) {
+    let left = keyboard_input.pressed(KeyCode::ArrowLeft);
+    let right = keyboard_input.pressed(KeyCode::ArrowRight);
+    let direction = match (left, right) {
+        (true, false) => -1,
+        (false, true) => 1,
+        _ => 0,
+    };
+    let action = if direction < 0 {
+        GameAction::MoveLeft
+    } else {
+        GameAction::MoveRight
+    };
 
-            // Lock the piece
-            let piece_matrix = get_block_matrix(piece.states[piece.current_state], piece.color);
-            for my in 0..4 {
-                for mx in 0..4 {
-                    if let Presence::Yes(color) = piece_matrix[my][mx] {
-                        let map_x = position.x + mx as isize;
-                        let map_y = position.y + my as isize;
-                        if map_x >= 0
-                            && map_x < NUM_BLOCKS_X as isize
-                            && map_y >= 0
-                            && map_y < NUM_BLOCKS_Y as isize
-                        {
-                            game_map.0[map_y as usize][map_x as usize] = Presence::Yes(color);
-                        }
-                    }
-                }
-            }
-            commands.entity(entity).despawn();
-            spawn_piece(&mut commands, &game_map, &mut game_state);
+    let Ok((mut position, piece)) = query.get_single_mut() else {
+        return;
+    };
+
+    // A new direction restarts the charge and makes the single initial step
+    if direction != auto_shift.direction {
+        auto_shift.direction = direction;
+        auto_shift.held_for = Duration::ZERO;
+        auto_shift.repeats = 0;
+        let new_x = position.x + board.screen_dx(direction);
+        if direction != 0 && can_move_horizontally(piece, &position, new_x, &game_map) {
+            position.x = new_x;
+            action_trace.record(action);
+            lock_delay.move_reset();
         }
+        return;
+    }
+    if direction == 0 {
+        return;
+    }
 
-        if keyboard_input.just_pressed(bevy::input::keyboard::KeyCode::ArrowUp) {
-            let old_state = piece.current_state;
-            let next_state = (piece.current_state + 1) % 4;
-            let next_state_clone = next_state.clone();
-            let mut rotated_piece = piece.clone();
-            rotated_piece.current_state = next_state_clone;
+    auto_shift.held_for += time.delta();
+    if auto_shift.held_for < auto_shift.das {
+        return;
+    }
 
-            if can_rotate(&rotated_piece, &position, &game_map) {
-                piece.current_state = next_state;
-            } else {
-                // If rotation causes collision, revert to old state
-                piece.current_state = old_state;
-            }
+    // Repeats fall due one ARR after another once DAS has charged; zero ARR
+    // slides the piece all the way to the wall
+    let due = if auto_shift.arr.is_zero() {
+        game_map.width() as u32
+    } else {
+        ((auto_shift.held_for - auto_shift.das).as_nanos() / auto_shift.arr.as_nanos()) as u32
+    };
+
+    while auto_shift.repeats < due {
+        auto_shift.repeats += 1;
+        let new_x = position.x + board.screen_dx(direction);
+        if !can_move_horizontally(piece, &position, new_x, &game_map) {
+            auto_shift.repeats = due;
+            break;
         }
+        position.x = new_x;
+        action_trace.record(action);
+        lock_delay.move_reset();
     }
 }
 
 // New system to clear full lines
-fn clear_lines(mut game_map: ResMut<GameMap>, mut score: ResMut<Score>, mut level: ResMut<Level>) {
-    // Add level as a parameter
-    let mut lines_cleared = 0;
-    let mut rows_to_clear = Vec::new();
-
-    // Find full lines
-    for y in 0..NUM_BLOCKS_Y {
-        let mut is_full = true;
-        for x in 0..NUM_BLOCKS_X {
-            if let Presence::No = game_map.0[y][x] {
-                is_full = false;
-                break;
+// Full rows first play the line-clear animation; once it ends they are
+// removed and scored
+#[allow(clippy::too_many_arguments)]
+fn clear_lines(
+    time: Res<Time>,
+    mut animation: ResMut<LineClearAnimation>,
+    mut game_map: ResMut<GameMap>,
+    rules: Res<RulesConfig>,
+    mut score: ResMut<Score>,
+    mut level: ResMut<Level>,
+    mut pending_garbage: ResMut<PendingGarbage>,
+    survival: Res<SurvivalMode>,
+    mut locked: EventReader<PieceLocked>,
+    mut cleared: EventWriter<LinesCleared>,
+    mut perfect_clear: EventWriter<PerfectClear>,
+    mut level_up: EventWriter<LevelUp>,
+    (visual_settings, mut collapse): (Res<VisualSettings>, ResMut<RowCollapse>),
+    mut log: ResMut<EventLog>,
+    mut last_locked: Local<Vec<(isize, isize)>>,
+) {
+    if let Some(event) = locked.read().last() {
+        *last_locked = event.cells.to_vec();
+    }
+    if !animation.is_active() {
+        let full_rows = game_map.full_rows();
+        if !full_rows.is_empty() {
+            // A clear that will empty the board shows off the piece behind it
+            if game_map.clears_to_empty() {
+                animation.highlight = last_locked.clone();
             }
+            animation.start(full_rows);
         }
-        if is_full {
-            rows_to_clear.push(y);
-        }
+        return;
     }
+    animation.timer.tick(time.delta());
+    if !animation.timer.finished() {
+        return;
+    }
+    let was_perfect_clear = game_map.clears_to_empty();
+    animation.finish();
 
-    // Clear lines and shift down
-    for &row_to_clear in rows_to_clear.iter().rev() {
-        // Iterate in reverse to avoid index issues
-        lines_cleared += 1;
-        // Remove the full row
-        game_map.0.remove(row_to_clear);
-        // Add a new empty row at the top
-        game_map.0.insert(0, vec![Presence::No; NUM_BLOCKS_X]);
+    // The map drops the rows above at once; they are drawn falling after it
+    if !visual_settings.collapse_duration.is_zero() {
+        collapse.start(
+            &game_map.full_rows(),
+            game_map.height(),
+            visual_settings.collapse_duration,
+        );
+    }
+    // Clear full lines and compact the remaining rows in a single pass
+    let lines_cleared = game_map.clear_full_rows();
+    if was_perfect_clear {
+        perfect_clear.send(PerfectClear);
     }
 
     if lines_cleared > 0 {
-        score.value += lines_cleared as u32 * 100; // Example scoring: 100 points per line
-        level.lines_cleared_in_level += lines_cleared as u32;
-        if level.lines_cleared_in_level >= 10 {
-            // Advance level every 10 lines
-            level.value += 1;
-            level.lines_cleared_in_level = 0;
-        }
-        println!(
-            "Cleared {} lines! Current score: {}",
-            lines_cleared, score.value
-        );
+        // Survival runs are measured by time alone, so clears only keep the
+        // board open
+        let previous_level = level.value;
+        if !survival.0 {
+            award_lines(
+                lines_cleared as u32,
+                rules.lines_per_level,
+                &mut score,
+                &mut level,
+            );
+        }
+        cleared.send(LinesCleared {
+            count: lines_cleared as u32,
+        });
+        if level.value > previous_level {
+            level_up.send(LevelUp { level: level.value });
+        }
+
+        if pending_garbage.rows > 0 {
+            let leftover = pending_garbage.cancel(lines_cleared as u32);
+            log.push(format!(
+                "Cancelled {} garbage rows, {} still pending",
+                lines_cleared as u32 - leftover,
+                pending_garbage.rows
+            ));
+        }
+    }
+}
+
+// Adds line-clear points and advances the level, carrying extra lines over
+// so one big clear can cross one or more level boundaries
+fn award_lines(lines_cleared: u32, lines_per_level: u32, score: &mut Score, level: &mut Level) {
+    score.value += lines_cleared * 100; // Example scoring: 100 points per line
+    level.lines_cleared_in_level += lines_cleared;
+    let lines_per_level = lines_per_level.max(1);
+    while level.lines_cleared_in_level >= lines_per_level {
+        level.value += 1;
+        level.lines_cleared_in_level -= lines_per_level;
     }
 }
 
 // New system to set up UI
-fn setup_ui(mut commands: Commands) {
+fn setup_ui(mut commands: Commands, layout: Res<UiLayout>) {
     commands.spawn((
         TextBundle::from_sections([
             TextSection::new(
                 "Score: ",
                 TextStyle {
-                    font_size: 40.0,
+                    font_size: FONT_SIZE_HUD,
                     color: Color::WHITE,
                     ..default()
                 },
             ),
             TextSection::from_style(TextStyle {
-                font_size: 40.0,
+                font_size: FONT_SIZE_HUD,
                 color: Color::WHITE,
                 ..default()
             }),
@@ -521,32 +1395,39 @@ fn setup_ui(mut commands: Commands) {
                 "
 Level: ",
                 TextStyle {
-                    font_size: 40.0,
+                    font_size: FONT_SIZE_HUD,
                     color: Color::WHITE,
                     ..default()
                 },
             ),
             TextSection::from_style(TextStyle {
-                font_size: 40.0,
+                font_size: FONT_SIZE_HUD,
                 color: Color::WHITE,
                 ..default()
             }),
         ])
-        .with_style(Style {
-            position_type: PositionType::Absolute,
-            top: Val::Px(10.0),
-            left: Val::Px(10.0),
-            ..default()
-        }),
+        .with_style(layout.score.style()),
         ScoreDisplay,
         LevelDisplay,
     ));
 }
 
 // New system to update score display
-fn update_score_display(score: Res<Score>, mut query_text: Query<&mut Text, With<ScoreDisplay>>) {
-    if score.is_changed() {
-        if let Some(mut text) = query_text.iter_mut().next() {
+// Survival runs show their time in place of the score
+fn update_score_display(
+    score: Res<Score>,
+    survival: Res<SurvivalMode>,
+    clock: Res<SurvivalClock>,
+    mut query_text: Query<&mut Text, With<ScoreDisplay>>,
+) {
+    if (score.is_changed() || survival.is_changed() || clock.is_changed())
+        && let Some(mut text) = query_text.iter_mut().next()
+    {
+        if survival.0 {
+            text.sections[0].value = "Time: ".to_string();
+            text.sections[1].value = survival::format_survival_time(clock.elapsed);
+        } else {
+            text.sections[0].value = "Score: ".to_string();
             text.sections[1].value = score.value.to_string();
         }
     }
@@ -561,7 +1442,7 @@ fn setup_game_over_ui(mut commands: Commands) {
     let mut text_bundle = TextBundle::from_section(
         "GAME OVER",
         TextStyle {
-            font_size: 100.0,
+            font_size: FONT_SIZE_BANNER,
             color: Color::RED,
             ..default()
         },
@@ -583,31 +1464,1435 @@ fn display_game_over_message(
     game_state: Res<State<GameState>>,
     mut query_game_over_message: Query<&mut Visibility, With<GameOverMessage>>,
 ) {
-    if game_state.get() == &GameState::GameOver {
-        if let Some(mut visibility) = query_game_over_message.iter_mut().next() {
-            *visibility = Visibility::Visible;
-        }
+    if game_state.get() == &GameState::GameOver
+        && let Some(mut visibility) = query_game_over_message.iter_mut().next()
+    {
+        *visibility = Visibility::Visible;
     }
 }
 
 // New system to update gravity speed based on level
-fn update_gravity_speed(level: Res<Level>, mut fixed_time: ResMut<Time<Fixed>>) {
-    if level.is_changed() {
-        let level_index = level.value as usize;
-        if level_index < NUM_LEVELS {
-            let new_speed_ms = LEVEL_TIMES[level_index];
-            let new_speed_secs = new_speed_ms as f32 / 1000.0;
-            fixed_time.set_wrap_period(Duration::from_secs_f32(new_speed_secs));
-            println!("Gravity speed updated to: {}s", new_speed_secs);
-        }
+fn update_gravity_speed(
+    level: Res<Level>,
+    curve: Res<GravityCurve>,
+    multiplier: Res<GravityMultiplier>,
+    mut gravity: ResMut<GravityTimer>,
+) {
+    if level.is_changed() || curve.is_changed() || multiplier.is_changed() {
+        let interval = gravity_for_level(level.value, &curve, &multiplier);
+        gravity.timer.set_duration(interval);
+    }
+}
+
+// Entering play, makes sure the map has the dimensions the board config
+// describes, so drawing and collision never index past its rows or columns
+fn validate_board_dimensions(
+    board: Res<BoardConfig>,
+    mut game_map: ResMut<GameMap>,
+    mut log: ResMut<EventLog>,
+) {
+    let (width, height) = (game_map.width(), game_map.height());
+    if game_map.fit_to(board.width, board.height) {
+        log.push(format!(
+            "Board was {}x{} but the config is {}x{}; resized it to match",
+            width, height, board.width, board.height
+        ));
     }
 }
 
+// Time per row at a level of the curve after the multiplier. Levels past the
+// curve keep its fastest speed, and the result never drops below MIN_GRAVITY_MS.
+fn gravity_for_level(level: u32, curve: &GravityCurve, multiplier: &GravityMultiplier) -> Duration {
+    let base_secs = curve.millis_for_level(level) as f32 / 1000.0;
+    let minimum = Duration::from_millis(MIN_GRAVITY_MS);
+    Duration::try_from_secs_f32(multiplier.scale(base_secs))
+        .map_or(minimum, |interval| interval.max(minimum))
+}
+
 // New system to update level display
-fn update_level_display(level: Res<Level>, mut query_text: Query<&mut Text, With<LevelDisplay>>) {
-    if level.is_changed() {
-        if let Some(mut text) = query_text.iter_mut().next() {
-            text.sections[3].value = level.value.to_string(); // Accessing index 3 for Level value
+fn update_level_display(
+    level: Res<Level>,
+    rules: Res<RulesConfig>,
+    mut query_text: Query<&mut Text, With<LevelDisplay>>,
+) {
+    if (level.is_changed() || rules.is_changed())
+        && let Some(mut text) = query_text.iter_mut().next()
+    {
+        // Accessing index 3 for Level value, followed by progress to the next level
+        text.sections[3].value = format!(
+            "{} ({}/{})",
+            level.value, level.lines_cleared_in_level, rules.lines_per_level
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_constants::{TEXTURE_SIZE, Z_GHOST, Z_PARTICLES};
+    use crate::game_types::PieceType;
+    use crate::hold::OutgoingHoldCell;
+    use crate::line_clear::LineClearStyle;
+    use crate::visual::GhostStyle;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn piece_above_the_board_can_rotate_where_it_can_move() {
+        let game_map = GameMap::default();
+        let mut piece = Piece::new(PieceType::T);
+        piece.current_state = 1;
+        let position = Position { x: 3, y: -1 };
+
+        assert!(can_move(&piece, &position, position.y, &game_map));
+        assert!(can_rotate(&piece, &position, &game_map));
+    }
+
+    #[test]
+    fn big_mode_cell_covers_four_normal_cells() {
+        let big = BoardConfig::big();
+        let sprite = block_sprite(&big, 0, 0, Color::WHITE);
+        let size = TEXTURE_SIZE as f32 * 2.0;
+
+        assert_eq!(sprite.sprite.custom_size, Some(Vec2::splat(size)));
+        // The top-left big cell fills the window's top-left 2x2 normal cells
+        let corner = Vec2::new(-(WIDTH as f32) / 2.0, HEIGHT as f32 / 2.0);
+        assert_eq!(
+            sprite.transform.translation.truncate(),
+            corner + Vec2::new(size / 2.0, -size / 2.0)
+        );
+    }
+
+    #[test]
+    fn left_wall_rejects_a_move_past_column_zero() {
+        let game_map = GameMap::default();
+        let piece = Piece::new(PieceType::O);
+        // The O piece's cells sit in matrix columns 1 and 2
+        let flush_left = Position { x: -1, y: 5 };
+
+        assert!(can_move(&piece, &flush_left, flush_left.y, &game_map));
+        assert!(!can_move_horizontally(&piece, &flush_left, -2, &game_map));
+    }
+
+    #[test]
+    fn right_wall_rejects_a_move_past_the_last_column() {
+        let game_map = GameMap::default();
+        let piece = Piece::new(PieceType::O);
+        let flush_right = Position {
+            x: game_map.width() as isize - 3,
+            y: 5,
+        };
+
+        assert!(can_move(&piece, &flush_right, flush_right.y, &game_map));
+        assert!(!can_move_horizontally(
+            &piece,
+            &flush_right,
+            flush_right.x + 1,
+            &game_map
+        ));
+    }
+
+    #[test]
+    fn tetris_crossing_a_level_boundary_carries_lines_over() {
+        let mut score = Score::default();
+        let mut level = Level {
+            value: 1,
+            lines_cleared_in_level: 3,
+        };
+
+        award_lines(4, 5, &mut score, &mut level);
+        assert_eq!(level.value, 2);
+        assert_eq!(level.lines_cleared_in_level, 2);
+    }
+
+    #[test]
+    fn hard_drop_sends_one_piece_locked_at_the_landing_spot() {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<GameMap>()
+            .init_resource::<Score>()
+            .init_resource::<LockDelay>()
+            .init_resource::<RulesConfig>()
+            .init_resource::<EntryDelay>()
+            .init_resource::<GameRng>()
+            .init_resource::<HoldSlot>()
+            .init_resource::<PendingGarbage>()
+            .init_resource::<ActionTrace>()
+            .init_resource::<SurvivalMode>()
+            .init_resource::<DebugSettings>()
+            .init_resource::<LastRotation>()
+            .init_resource::<NextState<GameState>>()
+            .add_event::<PieceLocked>()
+            .add_systems(Update, hard_drop_piece);
+        let piece = Piece::new(PieceType::O);
+        let start = Position { x: 3, y: 0 };
+        let landing_y = drop_distance(&piece, &start, &GameMap::default());
+        app.world.spawn((start, piece));
+
+        app.world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::Space);
+        app.update();
+
+        let events = app.world.resource::<Events<PieceLocked>>();
+        let locked: Vec<_> = events.get_reader().read(events).copied().collect();
+        assert_eq!(locked.len(), 1);
+        assert_eq!(locked[0].piece_type, PieceType::O);
+        assert_eq!(locked[0].position, Position { x: 3, y: landing_y });
+    }
+
+    #[test]
+    fn mirrored_left_moves_right_and_flips_the_render() {
+        let mirrored = BoardConfig {
+            mirrored: true,
+            ..BoardConfig::default()
+        };
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<AutoShift>()
+            .init_resource::<GameMap>()
+            .init_resource::<LockDelay>()
+            .init_resource::<ActionTrace>()
+            .insert_resource(mirrored)
+            .add_systems(Update, auto_shift_piece);
+        let piece = app
+            .world
+            .spawn((Position { x: 3, y: 5 }, Piece::new(PieceType::T)))
+            .id();
+
+        app.world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::ArrowLeft);
+        app.update();
+
+        assert_eq!(app.world.get::<Position>(piece).unwrap().x, 4);
+        let last = mirrored.width as isize - 1;
+        assert_eq!(
+            mirrored.cell_translation(0, 5),
+            BoardConfig::default().cell_translation(last, 5)
+        );
+    }
+
+    #[test]
+    fn held_soft_drop_falls_factor_times_the_gravity_rate() {
+        let mut gravity = GravityTimer {
+            timer: Timer::from_seconds(1.0, TimerMode::Repeating),
+            ..GravityTimer::default()
+        };
+        let factor = 20;
+        let frame = Duration::from_millis(10);
+        // Half a second at one gravity row a second, twenty times faster
+        let rows: u32 = (0..50)
+            .map(|frame_index| gravity.soft_drop_rows(frame, factor, frame_index == 0))
+            .sum();
+        assert_eq!(rows, 10);
+    }
+
+    // Translations of the sprites at depth `z` that draw_blocks draws for
+    // `game_map` and the active piece, if any
+    fn drawn_cells(
+        board: BoardConfig,
+        game_map: GameMap,
+        piece: Option<(Piece, Position)>,
+        z: f32,
+    ) -> Vec<Vec2> {
+        let mut app = App::new();
+        app.insert_resource(board)
+            .insert_resource(game_map)
+            .init_resource::<VisualSettings>()
+            .init_resource::<LineClearAnimation>()
+            .init_resource::<ColorTheme>()
+            .init_resource::<RowCollapse>()
+            .init_resource::<Level>()
+            .init_resource::<HoldSwapAnimation>()
+            .add_systems(Update, draw_blocks);
+        if let Some((piece, position)) = piece {
+            app.world
+                .spawn((piece, position, VisualOffset::new(position)));
+        }
+        app.update();
+        let mut query = app.world.query_filtered::<&Transform, With<Block>>();
+        query
+            .iter(&app.world)
+            .filter(|transform| transform.translation.z == z)
+            .map(|transform| transform.translation.truncate())
+            .collect()
+    }
+
+    #[test]
+    fn tall_board_draws_only_the_scrolled_window() {
+        let board = BoardConfig::tall(40);
+        let mut game_map = GameMap::new(board.width, board.height);
+        game_map.set_cell(2, 5, Presence::Yes(GameColor::Red));
+        game_map.set_cell(3, 39, Presence::Yes(GameColor::Blue));
+
+        // Resting on the bottom of the board, only the bottom row's cell shows
+        assert_eq!(board.scroll_row, 40 - board.visible_height);
+        assert_eq!(
+            drawn_cells(board, game_map.clone(), None, Z_BLOCKS),
+            vec![board.cell_translation(3, 39)]
+        );
+
+        // Scrolled to the top, only the high cell shows, in the same window
+        let scrolled = BoardConfig {
+            scroll_row: 0,
+            ..board
+        };
+        let drawn = drawn_cells(scrolled, game_map, None, Z_BLOCKS);
+        assert_eq!(drawn, vec![scrolled.cell_translation(2, 5)]);
+        assert_eq!(drawn[0], BoardConfig::default().cell_translation(2, 5));
+    }
+
+    #[test]
+    fn active_piece_cannot_move_into_the_other_active_piece() {
+        let game_map = GameMap::default();
+        let piece = Piece::new(PieceType::O);
+        let upper = Position { x: 3, y: 10 };
+        let lower = Position { x: 3, y: 12 };
+        let lower_cells = piece_cells(&piece, &lower);
+        let upper_cells = piece_cells(&piece, &upper);
+
+        assert!(can_move(&piece, &upper, upper.y + 1, &game_map));
+        assert!(!can_move_avoiding(
+            &piece,
+            &upper,
+            upper.y + 1,
+            &game_map,
+            &lower_cells
+        ));
+        assert!(!can_move_avoiding(
+            &piece,
+            &lower,
+            lower.y - 1,
+            &game_map,
+            &upper_cells
+        ));
+        assert!(can_move_avoiding(
+            &piece,
+            &upper,
+            upper.y,
+            &game_map,
+            &lower_cells
+        ));
+    }
+
+    #[test]
+    fn held_left_moves_once_then_once_per_arr_after_das() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<AutoShift>()
+            .init_resource::<GameMap>()
+            .init_resource::<BoardConfig>()
+            .init_resource::<LockDelay>()
+            .init_resource::<ActionTrace>()
+            .add_systems(Update, auto_shift_piece);
+        let piece = app
+            .world
+            .spawn((Position { x: 5, y: 5 }, Piece::new(PieceType::T)))
+            .id();
+        let (das, arr) = {
+            let auto_shift = app.world.resource::<AutoShift>();
+            (auto_shift.das, auto_shift.arr)
+        };
+
+        app.world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::ArrowLeft);
+        app.update();
+        assert_eq!(app.world.get::<Position>(piece).unwrap().x, 4);
+
+        // Held for exactly DAS plus one ARR, a frame at a time
+        let frame = Duration::from_millis(10);
+        let frames = (das + arr).as_millis() / frame.as_millis();
+        for _ in 0..frames {
+            app.world.resource_mut::<ButtonInput<KeyCode>>().clear();
+            app.world.resource_mut::<Time>().advance_by(frame);
+            app.update();
+        }
+        assert_eq!(app.world.get::<Position>(piece).unwrap().x, 3);
+    }
+
+    // An app running only clear_lines on `board`
+    fn clear_lines_app(board: &str, visual_settings: VisualSettings) -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<EventLog>()
+            .init_resource::<LineClearAnimation>()
+            .insert_resource(GameMap::from_compact_string(board).unwrap())
+            .init_resource::<RulesConfig>()
+            .init_resource::<Score>()
+            .init_resource::<Level>()
+            .init_resource::<PendingGarbage>()
+            .init_resource::<SurvivalMode>()
+            .insert_resource(visual_settings)
+            .init_resource::<RowCollapse>()
+            .add_event::<PieceLocked>()
+            .add_event::<LinesCleared>()
+            .add_event::<PerfectClear>()
+            .add_event::<LevelUp>()
+            .add_systems(Update, clear_lines);
+        app
+    }
+
+    // Runs clear_lines `dt` after the last frame and returns the board
+    fn clear_frame(app: &mut App, dt: Duration) -> String {
+        app.world.resource_mut::<Time>().advance_by(dt);
+        app.update();
+        app.world.resource::<GameMap>().to_compact_string()
+    }
+
+    #[test]
+    fn rows_clear_after_the_animation_whatever_its_style() {
+        for style in [
+            LineClearStyle::Flash,
+            LineClearStyle::Collapse,
+            LineClearStyle::Explode,
+        ] {
+            let board = "..../r.../gggg";
+            let visual_settings = VisualSettings {
+                line_clear_style: style,
+                ..VisualSettings::default()
+            };
+            let mut app = clear_lines_app(board, visual_settings);
+
+            let duration = app.world.resource::<LineClearAnimation>().timer.duration();
+            assert_eq!(clear_frame(&mut app, Duration::ZERO), board);
+            assert!(app.world.resource::<LineClearAnimation>().is_active());
+            assert_eq!(clear_frame(&mut app, duration / 2), board);
+            assert_eq!(clear_frame(&mut app, duration / 2), "..../..../r...");
+        }
+    }
+
+    #[test]
+    fn perfect_clear_highlights_the_last_locked_piece() {
+        let mut app = clear_lines_app("..../cccc", VisualSettings::default());
+        let cells = [(0, 1), (1, 1), (2, 1), (3, 1)];
+        app.world.send_event(PieceLocked {
+            piece_type: PieceType::I,
+            position: Position { x: 0, y: 0 },
+            cells,
+            spin: false,
+        });
+        let duration = app.world.resource::<LineClearAnimation>().timer.duration();
+
+        clear_frame(&mut app, Duration::ZERO);
+        assert_eq!(app.world.resource::<LineClearAnimation>().highlight, cells);
+        clear_frame(&mut app, duration / 2);
+        assert_eq!(app.world.resource::<LineClearAnimation>().highlight, cells);
+
+        assert_eq!(clear_frame(&mut app, duration / 2), "..../....");
+        assert!(
+            app.world
+                .resource::<LineClearAnimation>()
+                .highlight
+                .is_empty()
+        );
+        assert_eq!(app.world.resource::<Events<PerfectClear>>().len(), 1);
+    }
+
+    #[test]
+    fn other_clears_highlight_nothing() {
+        let mut app = clear_lines_app("..../r.../gggg", VisualSettings::default());
+        app.world.send_event(PieceLocked {
+            piece_type: PieceType::I,
+            position: Position { x: 0, y: 0 },
+            cells: [(0, 2), (1, 2), (2, 2), (3, 2)],
+            spin: false,
+        });
+
+        clear_frame(&mut app, Duration::ZERO);
+        assert!(app.world.resource::<LineClearAnimation>().is_active());
+        assert!(
+            app.world
+                .resource::<LineClearAnimation>()
+                .highlight
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn next_piece_waits_out_the_entry_delay() {
+        let are = Duration::from_millis(200);
+        let rules = RulesConfig {
+            entry_delay: are,
+            ..RulesConfig::default()
+        };
+        let mut entry_delay = EntryDelay::default();
+        entry_delay.start(rules.entry_delay);
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .insert_resource(entry_delay)
+            .init_resource::<GameMap>()
+            .init_resource::<BoardConfig>()
+            .insert_resource(rules)
+            .init_resource::<PieceQueue>()
+            .init_resource::<GameRng>()
+            .init_resource::<NextState<GameState>>()
+            .add_systems(Update, spawn_after_entry_delay);
+        let pieces = |app: &mut App, dt: Duration| {
+            app.world.resource_mut::<Time>().advance_by(dt);
+            app.update();
+            app.world.query::<&Piece>().iter(&app.world).count()
+        };
+
+        assert_eq!(pieces(&mut app, are / 2), 0);
+        assert_eq!(pieces(&mut app, are / 4), 0);
+        assert_eq!(pieces(&mut app, are / 4), 1);
+        assert_eq!(pieces(&mut app, are), 1);
+    }
+
+    #[test]
+    fn piece_in_the_hidden_rows_draws_only_its_visible_cells() {
+        let board = BoardConfig::tall(40);
+        let game_map = GameMap::new(board.width, board.height);
+        let mut piece = Piece::new(PieceType::I);
+        piece.current_state = 1;
+        // A vertical I in column 2 of its box, its lower two cells in view
+        let top = board.scroll_row as isize;
+        let position = Position { x: 3, y: top - 2 };
+
+        let drawn = drawn_cells(board, game_map, Some((piece, position)), Z_PIECE);
+        assert_eq!(
+            drawn,
+            vec![
+                board.cell_translation(5, top),
+                board.cell_translation(5, top + 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn gravity_waits_for_the_clear_animation() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<EventLog>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<LineClearAnimation>()
+            .insert_resource(GameMap::from_compact_string("..../..../..../..../..../gggg").unwrap())
+            .init_resource::<BoardConfig>()
+            .init_resource::<RulesConfig>()
+            .insert_resource(GravityTimer {
+                timer: Timer::from_seconds(0.01, TimerMode::Repeating),
+                ..GravityTimer::default()
+            })
+            .init_resource::<LockDelay>()
+            .init_resource::<Score>()
+            .init_resource::<Level>()
+            .init_resource::<PendingGarbage>()
+            .init_resource::<SurvivalMode>()
+            .init_resource::<VisualSettings>()
+            .init_resource::<RowCollapse>()
+            .init_resource::<ActionTrace>()
+            .init_resource::<DebugSettings>()
+            .init_resource::<Metronome>()
+            .add_event::<PieceLocked>()
+            .add_event::<LinesCleared>()
+            .add_event::<PerfectClear>()
+            .add_event::<LevelUp>()
+            .add_event::<PieceMovedDown>()
+            .add_systems(
+                Update,
+                (
+                    clear_lines,
+                    move_piece_down.run_if(line_clear::gameplay_running),
+                )
+                    .chain(),
+            );
+        let start = Position { x: 0, y: -2 };
+        let piece = app.world.spawn((start, Piece::new(PieceType::O))).id();
+        let frame = Duration::from_millis(20);
+
+        app.update();
+        while app.world.resource::<LineClearAnimation>().is_active() {
+            assert_eq!(*app.world.get::<Position>(piece).unwrap(), start);
+            app.world.resource_mut::<Time>().advance_by(frame);
+            app.update();
+        }
+        assert_eq!(app.world.resource::<GameMap>().full_row_count(), 0);
+
+        app.world.resource_mut::<Time>().advance_by(frame);
+        app.update();
+        assert!(app.world.get::<Position>(piece).unwrap().y > start.y);
+    }
+
+    #[test]
+    fn gravity_never_speeds_past_the_minimum_or_slows_with_level() {
+        let curve = GravityCurve::default();
+        let mut fastest = GravityMultiplier::default();
+        for _ in 0..20 {
+            fastest.faster();
+        }
+        let minimum = Duration::from_millis(MIN_GRAVITY_MS);
+        for multiplier in [GravityMultiplier::default(), fastest] {
+            let mut previous = Duration::MAX;
+            for level in 0..=50 {
+                let interval = gravity_for_level(level, &curve, &multiplier);
+                assert!(
+                    interval >= minimum,
+                    "level {} falls every {:?}",
+                    level,
+                    interval
+                );
+                assert!(
+                    interval <= previous,
+                    "level {} is slower than the last",
+                    level
+                );
+                previous = interval;
+            }
+        }
+    }
+
+    #[test]
+    fn spawned_t_starts_in_its_configured_spawn_state() {
+        let mut rules = RulesConfig::default();
+        rules.spawn_states[PieceType::T as usize] = 2;
+        let mut queue = PieceQueue::default();
+        queue.force_next(PieceType::T);
+        let mut entry_delay = EntryDelay::default();
+        entry_delay.start(Duration::ZERO);
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .insert_resource(entry_delay)
+            .init_resource::<GameMap>()
+            .init_resource::<BoardConfig>()
+            .insert_resource(rules)
+            .insert_resource(queue)
+            .init_resource::<GameRng>()
+            .init_resource::<NextState<GameState>>()
+            .add_systems(Update, spawn_after_entry_delay);
+        app.update();
+
+        let mut query = app.world.query::<&Piece>();
+        let piece = query.single(&app.world);
+        assert_eq!(piece.piece_type, PieceType::T);
+        assert_eq!(piece.current_state, 2);
+    }
+
+    #[test]
+    fn cleared_board_draws_only_the_active_piece() {
+        let mut full = GameMap::default();
+        for y in 4..full.height() as isize {
+            for x in 1..full.width() as isize {
+                full.set_cell(x, y, Presence::Yes(GameColor::Gray));
+            }
+        }
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .insert_resource(PracticeMode(true))
+            .init_resource::<EventLog>()
+            .insert_resource(full)
+            .init_resource::<BoardConfig>()
+            .init_resource::<VisualSettings>()
+            .init_resource::<LineClearAnimation>()
+            .init_resource::<ColorTheme>()
+            .init_resource::<RowCollapse>()
+            .init_resource::<Level>()
+            .init_resource::<HoldSwapAnimation>()
+            .add_systems(Update, (practice::clear_whole_board, draw_blocks).chain());
+        let position = Position { x: 3, y: 0 };
+        app.world.spawn((
+            Piece::new(PieceType::O),
+            position,
+            VisualOffset::new(position),
+        ));
+        app.world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::Delete);
+        app.update();
+
+        let game_map = app.world.resource::<GameMap>();
+        assert!(
+            game_map
+                .rows
+                .iter()
+                .flatten()
+                .all(|cell| *cell == Presence::No)
+        );
+        let mut query = app.world.query_filtered::<&Transform, With<Block>>();
+        let depths: Vec<f32> = query
+            .iter(&app.world)
+            .map(|transform| transform.translation.z)
+            .collect();
+        assert!(
+            !depths.contains(&Z_BLOCKS),
+            "board cells drawn: {:?}",
+            depths
+        );
+        assert_eq!(depths.iter().filter(|&&z| z == Z_PIECE).count(), 4);
+    }
+
+    // An app running only move_piece_down, with `gravity` as the gravity clock
+    fn move_piece_down_app(
+        game_map: GameMap,
+        gravity: Timer,
+        debug_settings: DebugSettings,
+    ) -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<RulesConfig>()
+            .insert_resource(GravityTimer {
+                timer: gravity,
+                ..GravityTimer::default()
+            })
+            .init_resource::<LockDelay>()
+            .insert_resource(game_map)
+            .init_resource::<BoardConfig>()
+            .init_resource::<ActionTrace>()
+            .insert_resource(debug_settings)
+            .init_resource::<Metronome>()
+            .init_resource::<Score>()
+            .init_resource::<SurvivalMode>()
+            .add_event::<PieceMovedDown>()
+            .add_systems(Update, move_piece_down);
+        app
+    }
+
+    #[test]
+    fn fast_gravity_stops_on_a_ledge_above_a_gap() {
+        let mut game_map = GameMap::default();
+        // A two-cell ledge under the O piece with open rows beneath it
+        game_map.set_cell(4, 10, Presence::Yes(GameColor::Gray));
+        game_map.set_cell(5, 10, Presence::Yes(GameColor::Gray));
+        let gravity = Timer::from_seconds(0.001, TimerMode::Repeating);
+        let mut app = move_piece_down_app(game_map, gravity, DebugSettings::default());
+        let piece = app
+            .world
+            .spawn((Position { x: 3, y: 0 }, Piece::new(PieceType::O)))
+            .id();
+
+        // A hundred rows of gravity in a single frame
+        app.world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_millis(100));
+        app.update();
+        assert_eq!(app.world.get::<Position>(piece).unwrap().y, 8);
+    }
+
+    #[test]
+    fn step_mode_moves_one_row_per_step_key_press() {
+        let debug_settings = DebugSettings {
+            step_mode: true,
+            ..DebugSettings::default()
+        };
+        // Gravity fast enough to fall many rows a frame if it were running
+        let gravity = Timer::from_seconds(0.001, TimerMode::Repeating);
+        let mut app = move_piece_down_app(GameMap::default(), gravity, debug_settings);
+        let piece = app
+            .world
+            .spawn((Position { x: 3, y: 0 }, Piece::new(PieceType::O)))
+            .id();
+
+        for _ in 0..2 {
+            let mut keyboard_input = app.world.resource_mut::<ButtonInput<KeyCode>>();
+            keyboard_input.release(debug::STEP_KEY);
+            keyboard_input.clear();
+            keyboard_input.press(debug::STEP_KEY);
+            app.world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_millis(50));
+            app.update();
+        }
+        app.world.resource_mut::<ButtonInput<KeyCode>>().clear();
+        app.world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_millis(50));
+        app.update();
+        assert_eq!(app.world.get::<Position>(piece).unwrap().y, 2);
+    }
+
+    #[test]
+    fn ghost_block_particle_and_piece_draw_back_to_front() {
+        let board = BoardConfig::default();
+        let block = block_sprite(&board, 2, 10, Color::RED);
+        let ghost =
+            &visual::ghost_sprites(&board, &[(2, 10)], Color::RED, GhostStyle::default())[0];
+        let mut particle = block_sprite(&board, 2, 10, Color::RED);
+        line_clear::animate_cell(&mut particle, &board, 2, LineClearStyle::Explode, 0.5);
+
+        let depths = [
+            ghost.transform.translation.z,
+            block.transform.translation.z,
+            particle.transform.translation.z,
+            Z_PIECE,
+        ];
+        assert_eq!(depths, [Z_GHOST, Z_BLOCKS, Z_PARTICLES, Z_PIECE]);
+        assert!(depths.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn reversed_gravity_falls_and_clears_upward_on_screen() {
+        let board = BoardConfig {
+            reversed_gravity: true,
+            ..BoardConfig::default()
+        };
+        let gravity = Timer::from_seconds(0.05, TimerMode::Repeating);
+        let mut app = move_piece_down_app(GameMap::default(), gravity, DebugSettings::default());
+        app.insert_resource(board);
+        let piece = Piece::new(PieceType::O);
+        let entity = app.world.spawn((Position { x: 3, y: 0 }, piece)).id();
+        let drawn = |position: Position| {
+            drawn_cells(board, GameMap::default(), Some((piece, position)), Z_PIECE)
+                .iter()
+                .map(|cell| cell.y)
+                .fold(f32::MIN, f32::max)
+        };
+
+        let spawned = drawn(Position { x: 3, y: 0 });
+        app.world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_millis(60));
+        app.update();
+        let position = *app.world.get::<Position>(entity).unwrap();
+        assert_eq!(position.y, 1);
+        assert!(drawn(position) > spawned);
+
+        // A full row clears and the row beside it closes the gap upward
+        let mut game_map = GameMap::default();
+        let bottom = board.height as isize - 1;
+        for x in 0..board.width as isize {
+            game_map.set_cell(x, bottom, Presence::Yes(GameColor::Gray));
         }
+        game_map.set_cell(0, bottom - 1, Presence::Yes(GameColor::Red));
+        let before = drawn_cells(board, game_map.clone(), None, Z_BLOCKS);
+        assert_eq!(game_map.clear_full_rows(), 1);
+        let after = drawn_cells(board, game_map, None, Z_BLOCKS);
+        assert_eq!(after.len(), 1);
+        assert!(before.contains(&board.cell_translation(0, bottom - 1)));
+        assert_eq!(after[0], board.cell_translation(0, bottom));
+        assert!(after[0].y > board.cell_translation(0, bottom - 1).y);
+    }
+
+    #[test]
+    fn compose_mode_holds_the_piece_through_gravity_and_lock_delay() {
+        let debug_settings = DebugSettings {
+            compose_mode: true,
+            ..DebugSettings::default()
+        };
+        let gravity = Timer::from_seconds(0.001, TimerMode::Repeating);
+        let mut app = move_piece_down_app(GameMap::default(), gravity, debug_settings);
+        app.init_resource::<EntryDelay>()
+            .init_resource::<GameRng>()
+            .init_resource::<HoldSlot>()
+            .init_resource::<PendingGarbage>()
+            .init_resource::<NextState<GameState>>()
+            .init_resource::<LastRotation>()
+            .add_event::<PieceLocked>()
+            .add_systems(Update, lock_grounded_piece.after(move_piece_down));
+        // One piece in the air, then one resting on the floor
+        let floor = GameMap::default().height() as isize - 2;
+        for y in [0, floor] {
+            let piece = app
+                .world
+                .spawn((Position { x: 3, y }, Piece::new(PieceType::O)))
+                .id();
+            for _ in 0..10 {
+                app.world
+                    .resource_mut::<Time>()
+                    .advance_by(Duration::from_millis(100));
+                app.update();
+            }
+            assert_eq!(app.world.get::<Position>(piece).unwrap().y, y);
+            assert!(app.world.resource::<Events<PieceLocked>>().is_empty());
+            assert!(
+                app.world
+                    .resource::<GameMap>()
+                    .rows
+                    .iter()
+                    .flatten()
+                    .all(|cell| *cell == Presence::No)
+            );
+            app.world.despawn(piece);
+        }
+    }
+
+    #[test]
+    fn wrapping_piece_moves_off_the_right_edge_into_the_left_columns() {
+        let mut game_map = GameMap {
+            wrap_edges: true,
+            ..GameMap::default()
+        };
+        // A block in column 1 under where the T's right arm would wrap next
+        game_map.set_cell(1, 6, Presence::Yes(GameColor::Gray));
+        let board = BoardConfig::default();
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<AutoShift>()
+            .insert_resource(game_map.clone())
+            .init_resource::<LockDelay>()
+            .init_resource::<ActionTrace>()
+            .insert_resource(board)
+            .add_systems(Update, auto_shift_piece);
+        let piece = Piece::new(PieceType::T);
+        let entity = app.world.spawn((Position { x: 7, y: 5 }, piece)).id();
+        let press_right = |app: &mut App| {
+            let mut keyboard_input = app.world.resource_mut::<ButtonInput<KeyCode>>();
+            keyboard_input.press(KeyCode::ArrowRight);
+            app.update();
+            let mut keyboard_input = app.world.resource_mut::<ButtonInput<KeyCode>>();
+            keyboard_input.release(KeyCode::ArrowRight);
+            app.update();
+            *app.world.get::<Position>(entity).unwrap()
+        };
+
+        // The T's right arm crosses the seam into column 0
+        let position = press_right(&mut app);
+        assert_eq!(position, Position { x: 8, y: 5 });
+        let drawn = drawn_cells(board, game_map, Some((piece, position)), Z_PIECE);
+        assert_eq!(drawn.len(), 4);
+        assert!(drawn.contains(&board.cell_translation(0, 6)));
+        assert!(drawn.contains(&board.cell_translation(9, 6)));
+
+        // One more step would put the arm on the block in column 1
+        assert_eq!(press_right(&mut app), position);
+    }
+
+    #[test]
+    fn same_frame_rotate_move_and_drop_apply_in_that_order() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<GameMap>()
+            .init_resource::<BoardConfig>()
+            .init_resource::<Score>()
+            .init_resource::<LockDelay>()
+            .init_resource::<RulesConfig>()
+            .init_resource::<PieceQueue>()
+            .init_resource::<EntryDelay>()
+            .init_resource::<GameRng>()
+            .init_resource::<HoldSlot>()
+            .init_resource::<PendingGarbage>()
+            .init_resource::<LastKick>()
+            .init_resource::<LastRotation>()
+            .init_resource::<KickAttempt>()
+            .init_resource::<AutoShift>()
+            .init_resource::<ActionTrace>()
+            .init_resource::<SurvivalMode>()
+            .init_resource::<DebugSettings>()
+            .init_resource::<NextState<GameState>>()
+            .add_event::<PieceHeld>()
+            .add_event::<PieceLocked>()
+            .add_systems(
+                Update,
+                (handle_input, auto_shift_piece, hard_drop_piece).chain(),
+            );
+        // A flat T against the left wall: moving first would be blocked, so
+        // only rotate-then-move lets the upright T step into column 0
+        app.world
+            .spawn((Position { x: 0, y: 0 }, Piece::new(PieceType::T)));
+        let mut keyboard_input = app.world.resource_mut::<ButtonInput<KeyCode>>();
+        for key in [KeyCode::Space, KeyCode::ArrowLeft, KeyCode::ArrowUp] {
+            keyboard_input.press(key);
+        }
+        app.update();
+
+        assert_eq!(
+            app.world.resource::<ActionTrace>().frame(),
+            [
+                GameAction::RotateCw,
+                GameAction::MoveLeft,
+                GameAction::HardDrop
+            ]
+        );
+        let events = app.world.resource::<Events<PieceLocked>>();
+        let locked: Vec<_> = events.get_reader().read(events).copied().collect();
+        assert_eq!(locked.len(), 1);
+        let mut cells = locked[0].cells;
+        cells.sort();
+        let bottom = GameMap::default().height() as isize - 1;
+        assert_eq!(
+            cells,
+            [
+                (0, bottom - 2),
+                (0, bottom - 1),
+                (0, bottom),
+                (1, bottom - 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn entering_play_resizes_a_map_that_does_not_match_the_board() {
+        let board = BoardConfig::default();
+        // A narrow, short map with a ragged row, as a bad saved board might be
+        let mut game_map = GameMap::from_compact_string("..../r.../gggg").unwrap();
+        game_map.rows[1].truncate(2);
+        let mut app = App::new();
+        app.insert_resource(board)
+            .init_resource::<EventLog>()
+            .insert_resource(game_map)
+            .add_systems(Update, validate_board_dimensions);
+        app.update();
+
+        let game_map = app.world.resource::<GameMap>().clone();
+        assert_eq!(game_map.height(), board.height);
+        assert!(game_map.rows.iter().all(|row| row.len() == board.width));
+        let bottom = board.height as isize - 1;
+        assert_eq!(
+            game_map.cell(0, bottom - 1),
+            Some(Presence::Yes(GameColor::Red))
+        );
+        assert_eq!(
+            game_map.cell(3, bottom),
+            Some(Presence::Yes(GameColor::Green))
+        );
+        assert_eq!(game_map.cell(4, bottom), Some(Presence::No));
+
+        // Drawing and collision cover the whole configured board
+        let piece = Piece::new(PieceType::I);
+        let edge = Position {
+            x: 6,
+            y: bottom - 1,
+        };
+        assert!(can_move(&piece, &edge, edge.y, &game_map));
+        assert_eq!(drawn_cells(board, game_map, None, Z_BLOCKS).len(), 5);
+    }
+
+    // An app running only handle_input under `rules`
+    fn handle_input_app(rules: RulesConfig) -> App {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<GameMap>()
+            .init_resource::<BoardConfig>()
+            .init_resource::<LockDelay>()
+            .insert_resource(rules)
+            .init_resource::<PieceQueue>()
+            .init_resource::<GameRng>()
+            .init_resource::<HoldSlot>()
+            .init_resource::<LastKick>()
+            .init_resource::<LastRotation>()
+            .init_resource::<KickAttempt>()
+            .init_resource::<ActionTrace>()
+            .init_resource::<NextState<GameState>>()
+            .add_event::<PieceHeld>()
+            .add_systems(Update, handle_input);
+        app
+    }
+
+    #[test]
+    fn no_rotation_challenge_ignores_every_rotate_press() {
+        let mut app = handle_input_app(RulesConfig {
+            allow_rotation: false,
+            ..RulesConfig::default()
+        });
+        let piece = app
+            .world
+            .spawn((Position { x: 3, y: 5 }, Piece::new(PieceType::T)))
+            .id();
+
+        for _ in 0..4 {
+            let mut keyboard_input = app.world.resource_mut::<ButtonInput<KeyCode>>();
+            keyboard_input.release(KeyCode::ArrowUp);
+            keyboard_input.clear();
+            keyboard_input.press(KeyCode::ArrowUp);
+            app.update();
+            assert_eq!(app.world.get::<Piece>(piece).unwrap().current_state, 0);
+        }
+        assert_eq!(
+            *app.world.get::<Position>(piece).unwrap(),
+            Position { x: 3, y: 5 }
+        );
+        assert!(app.world.resource::<ActionTrace>().frame().is_empty());
+    }
+
+    #[test]
+    fn rows_above_a_clear_are_drawn_falling_over_the_collapse_duration() {
+        let collapse = Duration::from_millis(200);
+        let visual_settings = VisualSettings {
+            collapse_duration: collapse,
+            ..VisualSettings::default()
+        };
+        let mut app = clear_lines_app("..../r.../gggg", visual_settings);
+        let board = BoardConfig::sized(4, 3);
+        app.insert_resource(board)
+            .init_resource::<ColorTheme>()
+            .init_resource::<HoldSwapAnimation>()
+            .add_systems(
+                Update,
+                (line_clear::advance_row_collapse, draw_blocks)
+                    .chain()
+                    .before(clear_lines),
+            );
+        let red_y = |app: &mut App| {
+            let mut query = app.world.query_filtered::<&Transform, With<Block>>();
+            let drawn: Vec<f32> = query
+                .iter(&app.world)
+                .filter(|transform| transform.translation.z == Z_BLOCKS)
+                .map(|transform| transform.translation.y)
+                .collect();
+            assert_eq!(drawn.len(), 1);
+            drawn[0]
+        };
+
+        let flash = app.world.resource::<LineClearAnimation>().timer.duration();
+        clear_frame(&mut app, Duration::ZERO);
+        assert_eq!(clear_frame(&mut app, flash), "..../..../r...");
+        let (from, to) = (
+            board.cell_translation(0, 1).y,
+            board.cell_translation(0, 2).y,
+        );
+
+        // The map dropped the row at once; its sprite starts from the old row
+        clear_frame(&mut app, Duration::ZERO);
+        assert_eq!(red_y(&mut app), from);
+        clear_frame(&mut app, collapse / 2);
+        assert!((red_y(&mut app) - (from + to) / 2.0).abs() < 0.01);
+        clear_frame(&mut app, collapse / 2);
+        assert_eq!(red_y(&mut app), to);
+    }
+
+    #[test]
+    fn held_soft_drop_and_left_repeat_at_their_own_rates() {
+        let mut app = move_piece_down_app(
+            GameMap::default(),
+            Timer::from_seconds(2.0, TimerMode::Repeating),
+            DebugSettings::default(),
+        );
+        app.init_resource::<AutoShift>()
+            .add_systems(Update, auto_shift_piece.before(move_piece_down));
+        let piece = app
+            .world
+            .spawn((Position { x: 7, y: 0 }, Piece::new(PieceType::T)))
+            .id();
+        let (das, arr) = {
+            let auto_shift = app.world.resource::<AutoShift>();
+            (auto_shift.das, auto_shift.arr)
+        };
+        let soft_drop_interval =
+            Duration::from_secs(2) / app.world.resource::<RulesConfig>().soft_drop_factor;
+
+        let mut input = app.world.resource_mut::<ButtonInput<KeyCode>>();
+        input.press(KeyCode::ArrowDown);
+        input.press(KeyCode::ArrowLeft);
+        app.update();
+        assert_eq!(app.world.get::<Position>(piece).unwrap().x, 6);
+
+        let frame = Duration::from_millis(10);
+        let held = Duration::from_millis(500);
+        for _ in 0..held.as_millis() / frame.as_millis() {
+            app.world.resource_mut::<ButtonInput<KeyCode>>().clear();
+            app.world.resource_mut::<Time>().advance_by(frame);
+            app.update();
+        }
+        let position = *app.world.get::<Position>(piece).unwrap();
+        let drops = held.as_millis() / soft_drop_interval.as_millis();
+        let repeats = (held - das).as_millis() / arr.as_millis();
+        assert_eq!(drops, 5);
+        assert_eq!(repeats, 6);
+        assert_eq!(position.y, drops as isize);
+        assert_eq!(position.x, 6 - repeats as isize);
+    }
+
+    #[test]
+    fn piece_spawned_onto_the_stack_keeps_its_lock_delay_under_soft_drop() {
+        let mut game_map = GameMap::default();
+        // A ledge right under a new O piece, with a gap so no row is full
+        for x in 0..9 {
+            game_map.set_cell(x, 2, Presence::Yes(GameColor::Gray));
+        }
+        let gravity = Timer::from_seconds(2.0, TimerMode::Repeating);
+        let mut app = move_piece_down_app(game_map, gravity, DebugSettings::default());
+        app.insert_resource(RulesConfig {
+            soft_drop_locks: true,
+            ..RulesConfig::default()
+        })
+        .init_resource::<AutoShift>()
+        .init_resource::<EntryDelay>()
+        .init_resource::<GameRng>()
+        .init_resource::<HoldSlot>()
+        .init_resource::<PendingGarbage>()
+        .init_resource::<NextState<GameState>>()
+        .init_resource::<LastRotation>()
+        .add_event::<PieceLocked>()
+        .add_systems(
+            Update,
+            (auto_shift_piece, move_piece_down, lock_grounded_piece).chain(),
+        );
+        let piece = app
+            .world
+            .spawn((Position { x: 3, y: 0 }, Piece::new(PieceType::O)))
+            .id();
+        let lock_window = app.world.resource::<LockDelay>().timer.duration();
+        let frame = Duration::from_millis(100);
+        let step = |app: &mut App| {
+            app.world.resource_mut::<Time>().advance_by(frame);
+            app.update();
+            app.world.resource_mut::<ButtonInput<KeyCode>>().clear();
+            !app.world.resource::<Events<PieceLocked>>().is_empty()
+        };
+
+        // Soft drop held from the spawn frame cannot lock the grounded piece
+        app.world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::ArrowDown);
+        assert!(!step(&mut app));
+
+        // There is still time to slide it along the ledge
+        app.world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::ArrowLeft);
+        assert!(!step(&mut app));
+        assert_eq!(app.world.get::<Position>(piece).unwrap().x, 2);
+        app.world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .release(KeyCode::ArrowLeft);
+
+        let mut held = frame * 2;
+        while !step(&mut app) {
+            held += frame;
+            assert!(held < lock_window * 4, "the piece never locked");
+        }
+        assert!(held >= lock_window, "locked after {:?}", held);
+    }
+
+    #[test]
+    fn extra_piece_guard_keeps_only_the_newest_piece() {
+        let mut world = World::new();
+        let stale = world
+            .spawn((Position { x: 3, y: 9 }, Piece::new(PieceType::T)))
+            .id();
+        world.run_system_once(despawn_extra_pieces);
+        let fresh = world
+            .spawn((Position { x: 3, y: 0 }, Piece::new(PieceType::I)))
+            .id();
+        world.run_system_once(despawn_extra_pieces);
+
+        let pieces: Vec<Entity> = world
+            .query_filtered::<Entity, With<Piece>>()
+            .iter(&world)
+            .collect();
+        assert_eq!(pieces, vec![fresh]);
+        assert!(world.get_entity(stale).is_none());
+    }
+
+    #[test]
+    fn soft_drop_scores_only_rows_descended_while_playing() {
+        let mut app = App::new();
+        app.init_state::<GameState>()
+            .insert_resource(NextState(Some(GameState::Paused)))
+            .init_resource::<Time>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<RulesConfig>()
+            .init_resource::<GravityTimer>()
+            .init_resource::<LockDelay>()
+            .init_resource::<GameMap>()
+            .init_resource::<BoardConfig>()
+            .init_resource::<ActionTrace>()
+            .init_resource::<DebugSettings>()
+            .init_resource::<Metronome>()
+            .init_resource::<Score>()
+            .init_resource::<SurvivalMode>()
+            .add_event::<PieceMovedDown>()
+            .add_systems(Update, move_piece_down.run_if(in_state(GameState::Playing)));
+        let piece = app
+            .world
+            .spawn((Position { x: 3, y: 0 }, Piece::new(PieceType::O)))
+            .id();
+        let hold_soft_drop = |app: &mut App| {
+            app.world
+                .resource_mut::<ButtonInput<KeyCode>>()
+                .press(KeyCode::ArrowDown);
+            for _ in 0..5 {
+                app.world
+                    .resource_mut::<Time>()
+                    .advance_by(Duration::from_millis(100));
+                app.update();
+                app.world.resource_mut::<ButtonInput<KeyCode>>().clear();
+            }
+            app.world
+                .resource_mut::<ButtonInput<KeyCode>>()
+                .release(KeyCode::ArrowDown);
+            app.update();
+        };
+
+        hold_soft_drop(&mut app);
+        assert_eq!(app.world.get::<Position>(piece).unwrap().y, 0);
+        assert_eq!(app.world.resource::<Score>().value, 0);
+
+        app.world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Playing);
+        app.update();
+        hold_soft_drop(&mut app);
+        let rows = app.world.get::<Position>(piece).unwrap().y;
+        let points = app.world.resource::<RulesConfig>().soft_drop_points;
+        assert!(rows > 0);
+        assert_eq!(app.world.resource::<Score>().value, rows as u32 * points);
+    }
+
+    #[test]
+    fn kick_overlay_records_the_kick_the_rotation_used() {
+        let mut app = handle_input_app(RulesConfig::default());
+        // A vertical I against the left wall has to kick to lie flat
+        let mut piece = Piece::new(PieceType::I);
+        piece.current_state = 1;
+        let origin = Position { x: -2, y: 5 };
+        let entity = app.world.spawn((origin, piece)).id();
+        app.world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::ArrowUp);
+        app.update();
+
+        let attempt = app.world.resource::<KickAttempt>().clone();
+        let (dx, dy) = attempt.accepted.expect("the rotation found no kick");
+        assert_ne!((dx, dy), (0, 0));
+        assert_eq!(attempt.candidates.first(), Some(&(0, 0)));
+        assert!(attempt.candidates.contains(&(dx, dy)));
+        assert_eq!(app.world.resource::<LastKick>().0, Some((dx, dy)));
+        assert_eq!(app.world.get::<Piece>(entity).unwrap().current_state, 2);
+        assert_eq!(
+            *app.world.get::<Position>(entity).unwrap(),
+            Position {
+                x: origin.x + dx,
+                y: origin.y + dy
+            }
+        );
+        let (rotated, from) = attempt.rotated.unwrap();
+        assert_eq!((rotated.current_state, from), (2, origin));
+    }
+
+    #[test]
+    fn ghost_stops_drawing_once_the_level_passes_the_limit() {
+        let mut app = App::new();
+        app.init_resource::<BoardConfig>()
+            .init_resource::<GameMap>()
+            .insert_resource(VisualSettings {
+                ghost_hidden_above: Some(5),
+                ..VisualSettings::default()
+            })
+            .init_resource::<LineClearAnimation>()
+            .init_resource::<ColorTheme>()
+            .init_resource::<RowCollapse>()
+            .insert_resource(Level {
+                value: 5,
+                ..Level::default()
+            })
+            .init_resource::<HoldSwapAnimation>()
+            .add_systems(Update, draw_blocks);
+        let position = Position { x: 3, y: 0 };
+        app.world.spawn((
+            Piece::new(PieceType::T),
+            position,
+            VisualOffset::new(position),
+        ));
+        let ghost_sprites = |app: &mut App| {
+            app.update();
+            let mut query = app.world.query_filtered::<&Transform, With<Block>>();
+            query
+                .iter(&app.world)
+                .filter(|transform| transform.translation.z == Z_GHOST)
+                .count()
+        };
+
+        assert_eq!(ghost_sprites(&mut app), 4);
+        app.world.resource_mut::<Level>().value = 6;
+        assert_eq!(ghost_sprites(&mut app), 0);
+    }
+
+    #[test]
+    fn hold_swap_animates_the_outgoing_piece_then_cleans_up() {
+        let mut app = handle_input_app(RulesConfig::default());
+        app.init_resource::<Time>()
+            .init_resource::<ColorTheme>()
+            .init_resource::<HoldSwapAnimation>()
+            .add_systems(
+                Update,
+                (hold::start_hold_swap_animation, hold::animate_hold_swap)
+                    .chain()
+                    .after(handle_input),
+            );
+        app.world
+            .resource_mut::<PieceQueue>()
+            .force_next(PieceType::I);
+        let entity = app
+            .world
+            .spawn((Position { x: 3, y: 5 }, Piece::new(PieceType::T)))
+            .id();
+        app.world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyC);
+        app.update();
+
+        // The swap is immediate; only the drawing lags behind
+        assert_eq!(
+            app.world.get::<Piece>(entity).unwrap().piece_type,
+            PieceType::I
+        );
+        let outgoing: Vec<OutgoingHoldCell> = app
+            .world
+            .query::<&OutgoingHoldCell>()
+            .iter(&app.world)
+            .copied()
+            .collect();
+        assert_eq!(
+            outgoing,
+            [OutgoingHoldCell {
+                piece_type: PieceType::T
+            }; 4]
+        );
+        let held: Vec<PieceHeld> = app
+            .world
+            .resource_mut::<Events<PieceHeld>>()
+            .drain()
+            .collect();
+        assert_eq!(held.len(), 1);
+        assert_eq!(held[0].outgoing.piece_type, PieceType::T);
+        assert_eq!(held[0].incoming, PieceType::I);
+        assert!(app.world.resource::<HoldSwapAnimation>().incoming_scale() < 1.0);
+
+        app.world.resource_mut::<ButtonInput<KeyCode>>().clear();
+        app.world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_millis(250));
+        app.update();
+        assert_eq!(
+            app.world
+                .query::<&OutgoingHoldCell>()
+                .iter(&app.world)
+                .count(),
+            0
+        );
+        assert_eq!(
+            app.world.resource::<HoldSwapAnimation>().incoming_scale(),
+            1.0
+        );
+    }
+
+    #[test]
+    fn soft_drop_release_grace_skips_gravity_on_the_release_frame() {
+        // Rows the piece falls on the frame soft drop is released, with a
+        // whole gravity interval passing in that frame
+        let rows_on_release = |soft_drop_release_grace: bool| {
+            let gravity = Timer::from_seconds(0.1, TimerMode::Repeating);
+            let mut app =
+                move_piece_down_app(GameMap::default(), gravity, DebugSettings::default());
+            app.insert_resource(RulesConfig {
+                soft_drop_release_grace,
+                ..RulesConfig::default()
+            });
+            let piece = app
+                .world
+                .spawn((Position { x: 3, y: 0 }, Piece::new(PieceType::O)))
+                .id();
+            app.world
+                .resource_mut::<ButtonInput<KeyCode>>()
+                .press(KeyCode::ArrowDown);
+            app.update();
+            let mut keyboard_input = app.world.resource_mut::<ButtonInput<KeyCode>>();
+            keyboard_input.clear();
+            keyboard_input.release(KeyCode::ArrowDown);
+            let released_at = app.world.get::<Position>(piece).unwrap().y;
+            app.world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_millis(120));
+            app.update();
+            app.world.get::<Position>(piece).unwrap().y - released_at
+        };
+
+        assert_eq!(rows_on_release(false), 1);
+        assert_eq!(rows_on_release(true), 0);
     }
 }
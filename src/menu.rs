@@ -0,0 +1,844 @@
+use crate::accessibility::{ColumnTargeting, MirrorBoard};
+use crate::blitz::PieceLimit;
+use crate::difficulty::Difficulty;
+use crate::event_log::EventLog;
+use crate::game_constants::FONT_SIZE_LOG;
+use crate::game_constants::TALL_BOARD_ROWS;
+use crate::game_types::{
+    BigMode, BoardConfig, BoardSize, GameMap, ReverseGravity, TallBoard, WrapEdges,
+};
+use crate::garbage::{GarbageChallenge, GarbageChallengeTimer};
+use crate::gravity_curve::{GravityCurve, LevelSelect};
+use crate::hud_layout::UiLayout;
+use crate::line_clear;
+use crate::mouse::MouseControls;
+use crate::practice::{PracticeGarbage, PracticeMode};
+use crate::puzzle::ActivePuzzle;
+use crate::queue::PieceQueue;
+use crate::restart::{QuickRestart, start_run};
+use crate::rhythm::RhythmMode;
+use crate::rng::GameRng;
+use crate::rules::RulesConfig;
+use crate::run_stats::RunStatsExport;
+use crate::savegame::ResumableGame;
+use crate::seed::SeedEntry;
+use crate::streak::StreakHud;
+use crate::survival::SurvivalMode;
+use crate::theme::ColorTheme;
+use crate::touch::TouchControls;
+use crate::visual::VisualSettings;
+use crate::{AutoShift, GameState, Level, LockDelay, spawn_piece};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+// Marker component for the main menu text
+#[derive(Component)]
+pub struct MainMenuUi;
+
+// Settings that can be toggled from the main menu before a game starts
+#[derive(SystemParam)]
+pub struct MenuOptions<'w> {
+    big_mode: ResMut<'w, BigMode>,
+    tall_board: ResMut<'w, TallBoard>,
+    practice: ResMut<'w, PracticeMode>,
+    practice_garbage: ResMut<'w, PracticeGarbage>,
+    challenge: ResMut<'w, GarbageChallenge>,
+    visual: ResMut<'w, VisualSettings>,
+    rules: ResMut<'w, RulesConfig>,
+    column_targeting: ResMut<'w, ColumnTargeting>,
+    mirror: ResMut<'w, MirrorBoard>,
+    puzzle: ResMut<'w, ActivePuzzle>,
+    seed_entry: ResMut<'w, SeedEntry>,
+    survival: ResMut<'w, SurvivalMode>,
+    rhythm: ResMut<'w, RhythmMode>,
+    reverse_gravity: ResMut<'w, ReverseGravity>,
+    wrap_edges: ResMut<'w, WrapEdges>,
+    piece_limit: ResMut<'w, PieceLimit>,
+    theme: ResMut<'w, ColorTheme>,
+    touch_controls: ResMut<'w, TouchControls>,
+    mouse_controls: ResMut<'w, MouseControls>,
+    level_select: ResMut<'w, LevelSelect>,
+    layout: ResMut<'w, UiLayout>,
+    run_stats_export: ResMut<'w, RunStatsExport>,
+    streak_hud: ResMut<'w, StreakHud>,
+    page: ResMut<'w, MenuPage>,
+    curve: Res<'w, GravityCurve>,
+    board_size: Res<'w, BoardSize>,
+    resumable: Res<'w, ResumableGame>,
+}
+
+// Pages the menu's options are split over, so each page fits the window
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MenuPage {
+    #[default]
+    Modes,
+    Rules,
+    Display,
+    Extras,
+}
+
+impl MenuPage {
+    const ALL: [MenuPage; 4] = [
+        MenuPage::Modes,
+        MenuPage::Rules,
+        MenuPage::Display,
+        MenuPage::Extras,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            MenuPage::Modes => "Modes",
+            MenuPage::Rules => "Rules",
+            MenuPage::Display => "Display",
+            MenuPage::Extras => "Controls and extras",
+        }
+    }
+
+    // The next page, or the previous one when `back`, wrapping around
+    fn turned(&self, back: bool) -> MenuPage {
+        let count = MenuPage::ALL.len();
+        let step = if back { count - 1 } else { 1 };
+        MenuPage::ALL[(*self as usize + step) % count]
+    }
+}
+
+// One option of the menu: the key that changes it, the page listing it and
+// how its line reads
+struct MenuOption {
+    key: KeyCode,
+    key_name: &'static str,
+    page: MenuPage,
+    label: &'static str,
+    value: fn(&MenuOptions) -> String,
+    change: fn(&mut MenuOptions),
+}
+
+// Every option the menu lists, in page order; the keys that change them,
+// the pages and the lines shown are all read from here
+const MENU_OPTIONS: &[MenuOption] = &[
+    MenuOption {
+        key: KeyCode::KeyB,
+        key_name: "B",
+        page: MenuPage::Modes,
+        label: "Big mode",
+        value: |options| on_off(options.big_mode.0).to_string(),
+        change: |options| options.big_mode.0 = !options.big_mode.0,
+    },
+    MenuOption {
+        key: KeyCode::KeyT,
+        key_name: "T",
+        page: MenuPage::Modes,
+        label: "Tall board",
+        value: |options| on_off(options.tall_board.0).to_string(),
+        change: |options| options.tall_board.0 = !options.tall_board.0,
+    },
+    MenuOption {
+        key: KeyCode::KeyP,
+        key_name: "P",
+        page: MenuPage::Modes,
+        label: "Practice",
+        value: |options| on_off(options.practice.0).to_string(),
+        change: |options| options.practice.0 = !options.practice.0,
+    },
+    MenuOption {
+        key: KeyCode::Insert,
+        key_name: "Insert",
+        page: MenuPage::Modes,
+        label: "Practice garbage",
+        value: |options| options.practice_garbage.name().to_string(),
+        change: |options| options.practice_garbage.cycle(),
+    },
+    MenuOption {
+        key: KeyCode::KeyG,
+        key_name: "G",
+        page: MenuPage::Modes,
+        label: "Garbage challenge",
+        value: |options| on_off(options.challenge.0).to_string(),
+        change: |options| options.challenge.0 = !options.challenge.0,
+    },
+    MenuOption {
+        key: KeyCode::Backquote,
+        key_name: "`",
+        page: MenuPage::Modes,
+        label: "Garbage style",
+        value: |options| options.rules.garbage_style.name().to_string(),
+        change: |options| options.rules.garbage_style = options.rules.garbage_style.cycled(),
+    },
+    MenuOption {
+        key: KeyCode::KeyV,
+        key_name: "V",
+        page: MenuPage::Modes,
+        label: "Survival",
+        value: |options| on_off(options.survival.0).to_string(),
+        change: |options| options.survival.0 = !options.survival.0,
+    },
+    MenuOption {
+        key: KeyCode::KeyY,
+        key_name: "Y",
+        page: MenuPage::Modes,
+        label: "Rhythm",
+        value: |options| options.rhythm.name().to_string(),
+        change: |options| options.rhythm.cycle(),
+    },
+    MenuOption {
+        key: KeyCode::Tab,
+        key_name: "Tab",
+        page: MenuPage::Modes,
+        label: "Piece limit",
+        value: |options| options.piece_limit.name().to_string(),
+        change: |options| options.piece_limit.cycle(),
+    },
+    MenuOption {
+        key: KeyCode::KeyU,
+        key_name: "U",
+        page: MenuPage::Modes,
+        label: "Puzzle",
+        value: |options| options.puzzle.name().to_string(),
+        change: |options| options.puzzle.cycle(),
+    },
+    MenuOption {
+        key: KeyCode::KeyE,
+        key_name: "E",
+        page: MenuPage::Modes,
+        label: "Seed",
+        value: |options| options.seed_entry.label(),
+        change: |options| options.seed_entry.editing = true,
+    },
+    MenuOption {
+        key: KeyCode::KeyK,
+        key_name: "K",
+        page: MenuPage::Rules,
+        label: "Wall kicks",
+        value: |options| on_off(options.rules.use_wall_kicks).to_string(),
+        change: |options| options.rules.use_wall_kicks = !options.rules.use_wall_kicks,
+    },
+    MenuOption {
+        key: KeyCode::Quote,
+        key_name: "'",
+        page: MenuPage::Rules,
+        label: "No rotation challenge",
+        value: |options| on_off(!options.rules.allow_rotation).to_string(),
+        change: |options| options.rules.allow_rotation = !options.rules.allow_rotation,
+    },
+    MenuOption {
+        key: KeyCode::BracketRight,
+        key_name: "]",
+        page: MenuPage::Rules,
+        label: "All spin",
+        value: |options| on_off(options.rules.all_spin).to_string(),
+        change: |options| options.rules.all_spin = !options.rules.all_spin,
+    },
+    MenuOption {
+        key: KeyCode::KeyD,
+        key_name: "D",
+        page: MenuPage::Rules,
+        label: "Soft drop locks",
+        value: |options| on_off(options.rules.soft_drop_locks).to_string(),
+        change: |options| options.rules.soft_drop_locks = !options.rules.soft_drop_locks,
+    },
+    MenuOption {
+        key: KeyCode::PageDown,
+        key_name: "PgDn",
+        page: MenuPage::Rules,
+        label: "Soft drop release grace",
+        value: |options| on_off(options.rules.soft_drop_release_grace).to_string(),
+        change: |options| {
+            options.rules.soft_drop_release_grace = !options.rules.soft_drop_release_grace
+        },
+    },
+    MenuOption {
+        key: KeyCode::KeyX,
+        key_name: "X",
+        page: MenuPage::Rules,
+        label: "Random colors",
+        value: |options| on_off(options.rules.random_colors).to_string(),
+        change: |options| options.rules.random_colors = !options.rules.random_colors,
+    },
+    MenuOption {
+        key: KeyCode::KeyF,
+        key_name: "F",
+        page: MenuPage::Rules,
+        label: "Line clear pause",
+        value: |options| on_off(options.rules.line_clear_pause).to_string(),
+        change: |options| options.rules.line_clear_pause = !options.rules.line_clear_pause,
+    },
+    MenuOption {
+        key: KeyCode::KeyQ,
+        key_name: "Q",
+        page: MenuPage::Rules,
+        label: "Flat side down spawns",
+        value: |options| on_off(options.rules.flat_side_down_spawns()).to_string(),
+        change: |options| options.rules.toggle_flat_side_down_spawns(),
+    },
+    MenuOption {
+        key: KeyCode::KeyR,
+        key_name: "R",
+        page: MenuPage::Rules,
+        label: "Rotation",
+        value: |options| options.rules.rotation_system.name().to_string(),
+        change: |options| options.rules.rotation_system = options.rules.rotation_system.toggled(),
+    },
+    MenuOption {
+        key: KeyCode::KeyL,
+        key_name: "L",
+        page: MenuPage::Rules,
+        label: "Lines per level",
+        value: |options| options.rules.lines_per_level.to_string(),
+        change: |options| options.rules.cycle_lines_per_level(),
+    },
+    MenuOption {
+        key: KeyCode::KeyN,
+        key_name: "N",
+        page: MenuPage::Rules,
+        label: "Entry delay",
+        value: |options| format!("{} ms", options.rules.entry_delay.as_millis()),
+        change: |options| options.rules.cycle_entry_delay(),
+    },
+    MenuOption {
+        key: KeyCode::KeyI,
+        key_name: "I",
+        page: MenuPage::Rules,
+        label: "I piece odds",
+        value: |options| options.rules.piece_weights.i_piece_odds().to_string(),
+        change: |options| options.rules.piece_weights.cycle_i_piece_odds(),
+    },
+    MenuOption {
+        key: KeyCode::KeyH,
+        key_name: "H",
+        page: MenuPage::Rules,
+        label: "Holds per piece",
+        value: |options| options.rules.holds_per_piece_name(),
+        change: |options| options.rules.cycle_holds_per_piece(),
+    },
+    MenuOption {
+        key: KeyCode::KeyS,
+        key_name: "S",
+        page: MenuPage::Display,
+        label: "Smooth movement",
+        value: |options| on_off(options.visual.smooth_movement).to_string(),
+        change: |options| options.visual.smooth_movement = !options.visual.smooth_movement,
+    },
+    MenuOption {
+        key: KeyCode::KeyW,
+        key_name: "W",
+        page: MenuPage::Display,
+        label: "Soft drop glide",
+        value: |options| on_off(options.visual.glide_soft_drop).to_string(),
+        change: |options| options.visual.glide_soft_drop = !options.visual.glide_soft_drop,
+    },
+    MenuOption {
+        key: KeyCode::KeyJ,
+        key_name: "J",
+        page: MenuPage::Display,
+        label: "Trajectory lines",
+        value: |options| on_off(options.visual.show_trajectory).to_string(),
+        change: |options| options.visual.show_trajectory = !options.visual.show_trajectory,
+    },
+    MenuOption {
+        key: KeyCode::Semicolon,
+        key_name: ";",
+        page: MenuPage::Display,
+        label: "Drop distance",
+        value: |options| on_off(options.visual.show_drop_distance).to_string(),
+        change: |options| options.visual.show_drop_distance = !options.visual.show_drop_distance,
+    },
+    MenuOption {
+        key: KeyCode::Digit5,
+        key_name: "5",
+        page: MenuPage::Display,
+        label: "Completed row preview",
+        value: |options| on_off(options.visual.preview_completed_rows).to_string(),
+        change: |options| {
+            options.visual.preview_completed_rows = !options.visual.preview_completed_rows
+        },
+    },
+    MenuOption {
+        key: KeyCode::KeyO,
+        key_name: "O",
+        page: MenuPage::Display,
+        label: "Ghost",
+        value: |options| options.visual.ghost_style.name().to_string(),
+        change: |options| options.visual.ghost_style = options.visual.ghost_style.toggled(),
+    },
+    MenuOption {
+        key: KeyCode::End,
+        key_name: "End",
+        page: MenuPage::Display,
+        label: "Hide ghost",
+        value: |options| options.visual.ghost_level_limit_name(),
+        change: |options| options.visual.cycle_ghost_level_limit(),
+    },
+    MenuOption {
+        key: KeyCode::KeyA,
+        key_name: "A",
+        page: MenuPage::Display,
+        label: "Line clear",
+        value: |options| options.visual.line_clear_style.name().to_string(),
+        change: |options| {
+            options.visual.line_clear_style = options.visual.line_clear_style.cycled()
+        },
+    },
+    MenuOption {
+        key: KeyCode::BracketLeft,
+        key_name: "[",
+        page: MenuPage::Display,
+        label: "Row collapse",
+        value: |options| format!("{} ms", options.visual.collapse_duration.as_millis()),
+        change: |options| {
+            options.visual.collapse_duration =
+                line_clear::cycled_collapse_duration(options.visual.collapse_duration)
+        },
+    },
+    MenuOption {
+        key: KeyCode::Period,
+        key_name: ".",
+        page: MenuPage::Display,
+        label: "Color theme",
+        value: |options| options.theme.name().to_string(),
+        change: |options| *options.theme = options.theme.cycled(),
+    },
+    MenuOption {
+        key: KeyCode::Backslash,
+        key_name: "\\",
+        page: MenuPage::Display,
+        label: "HUD layout",
+        value: |options| options.layout.name().to_string(),
+        change: |options| *options.layout = options.layout.cycled(),
+    },
+    MenuOption {
+        key: KeyCode::PageUp,
+        key_name: "PgUp",
+        page: MenuPage::Display,
+        label: "Tetris streak",
+        value: |options| on_off(options.streak_hud.0).to_string(),
+        change: |options| options.streak_hud.0 = !options.streak_hud.0,
+    },
+    MenuOption {
+        key: KeyCode::KeyC,
+        key_name: "C",
+        page: MenuPage::Extras,
+        label: "Column targeting",
+        value: |options| on_off(options.column_targeting.0).to_string(),
+        change: |options| options.column_targeting.0 = !options.column_targeting.0,
+    },
+    MenuOption {
+        key: KeyCode::KeyM,
+        key_name: "M",
+        page: MenuPage::Extras,
+        label: "Mirror board",
+        value: |options| on_off(options.mirror.0).to_string(),
+        change: |options| options.mirror.0 = !options.mirror.0,
+    },
+    MenuOption {
+        key: KeyCode::KeyZ,
+        key_name: "Z",
+        page: MenuPage::Extras,
+        label: "Reverse gravity (experimental)",
+        value: |options| on_off(options.reverse_gravity.0).to_string(),
+        change: |options| options.reverse_gravity.0 = !options.reverse_gravity.0,
+    },
+    MenuOption {
+        key: KeyCode::Comma,
+        key_name: ",",
+        page: MenuPage::Extras,
+        label: "Wrap edges (experimental)",
+        value: |options| on_off(options.wrap_edges.0).to_string(),
+        change: |options| options.wrap_edges.0 = !options.wrap_edges.0,
+    },
+    MenuOption {
+        key: KeyCode::Slash,
+        key_name: "/",
+        page: MenuPage::Extras,
+        label: "Touch buttons",
+        value: |options| on_off(options.touch_controls.0).to_string(),
+        change: |options| options.touch_controls.0 = !options.touch_controls.0,
+    },
+    MenuOption {
+        key: KeyCode::Digit4,
+        key_name: "4",
+        page: MenuPage::Extras,
+        label: "Mouse controls",
+        value: |options| on_off(options.mouse_controls.0).to_string(),
+        change: |options| options.mouse_controls.0 = !options.mouse_controls.0,
+    },
+    MenuOption {
+        key: KeyCode::Home,
+        key_name: "Home",
+        page: MenuPage::Extras,
+        label: "Export run stats",
+        value: |options| on_off(options.run_stats_export.0).to_string(),
+        change: |options| options.run_stats_export.0 = !options.run_stats_export.0,
+    },
+];
+
+impl MenuOptions<'_> {
+    fn text(&self) -> String {
+        let mut text = String::from("TETRIS\n");
+        if self.resumable.0.is_some() {
+            text.push_str("\nEnter - Continue saved game\n");
+        }
+        text.push_str("\nSelect difficulty:");
+        for (i, difficulty) in Difficulty::ALL.iter().enumerate() {
+            text.push_str(&format!("\n{} - {}", i + 1, difficulty.name()));
+        }
+        text.push_str(&format!(
+            "\n-/+ - Start level: {}",
+            self.level_select.label(&self.curve)
+        ));
+        text.push_str(&format!(
+            "\n\nLeft/Right - Page {} of {}: {}\n",
+            *self.page as usize + 1,
+            MenuPage::ALL.len(),
+            self.page.name()
+        ));
+        for line in self.page_lines() {
+            text.push('\n');
+            text.push_str(&line);
+        }
+        text
+    }
+
+    // The options listed on the current page
+    fn page_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for option in MENU_OPTIONS
+            .iter()
+            .filter(|option| option.page == *self.page)
+        {
+            lines.push(format!(
+                "{} - {}: {}",
+                option.key_name,
+                option.label,
+                (option.value)(self)
+            ));
+            if option.key == KeyCode::KeyE && self.seed_entry.editing {
+                lines.push(String::from("  (type or Ctrl+V, Enter to finish)"));
+            }
+        }
+        lines
+    }
+
+    // Flips the setting bound to any pressed key; returns true if one changed
+    fn toggle(&mut self, keyboard_input: &ButtonInput<KeyCode>) -> bool {
+        if self.seed_entry.editing {
+            self.seed_entry.edit(keyboard_input);
+            return keyboard_input.get_just_pressed().next().is_some();
+        }
+
+        let mut changed = false;
+        for key in keyboard_input.get_just_pressed() {
+            match key {
+                KeyCode::Equal => self.level_select.raise(&self.curve),
+                KeyCode::Minus => self.level_select.lower(),
+                KeyCode::ArrowRight => *self.page = self.page.turned(false),
+                KeyCode::ArrowLeft => *self.page = self.page.turned(true),
+                _ => {
+                    let Some(option) = MENU_OPTIONS.iter().find(|option| option.key == *key) else {
+                        continue;
+                    };
+                    (option.change)(self);
+                    // Changing an option from another page turns to it
+                    *self.page = option.page;
+                }
+            }
+            changed = true;
+        }
+        changed
+    }
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled { "on" } else { "off" }
+}
+
+pub fn setup_main_menu(mut commands: Commands, options: MenuOptions) {
+    commands.spawn((
+        TextBundle::from_section(
+            options.text(),
+            TextStyle {
+                font_size: FONT_SIZE_LOG,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(2.0),
+            left: Val::Percent(10.0),
+            ..default()
+        }),
+        MainMenuUi,
+    ));
+}
+
+// Picks a difficulty preset with the number keys and starts the game
+#[allow(clippy::too_many_arguments)]
+pub fn main_menu_input(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut game_map: ResMut<GameMap>,
+    mut board: ResMut<BoardConfig>,
+    mut options: MenuOptions,
+    mut level: ResMut<Level>,
+    mut quick_restart: ResMut<QuickRestart>,
+    mut lock_delay: ResMut<LockDelay>,
+    (mut auto_shift, mut chosen): (ResMut<AutoShift>, ResMut<Difficulty>),
+    (mut queue, mut challenge_timer): (ResMut<PieceQueue>, ResMut<GarbageChallengeTimer>),
+    mut rng: ResMut<GameRng>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut query_menu: Query<(Entity, &mut Text), With<MainMenuUi>>,
+    mut log: ResMut<EventLog>,
+) {
+    if options.toggle(&keyboard_input) {
+        for (_, mut text) in query_menu.iter_mut() {
+            text.sections[0].value = options.text();
+        }
+    }
+    if options.seed_entry.editing {
+        return;
+    }
+
+    let keys = [KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3];
+    let Some(difficulty) = keys
+        .iter()
+        .zip(Difficulty::ALL)
+        .find(|(key, _)| keyboard_input.just_pressed(**key))
+        .map(|(_, difficulty)| difficulty)
+    else {
+        return;
+    };
+
+    *chosen = difficulty;
+    difficulty.config().apply(
+        &mut level,
+        &mut lock_delay,
+        &mut auto_shift,
+        &mut options.rules,
+    );
+    if let Some(start_level) = options.level_select.0 {
+        level.value = start_level;
+    }
+    quick_restart.start_level = level.value;
+    // Puzzle boards are laid out for the normal board size
+    *board = if options.puzzle.0.is_some() {
+        BoardConfig::default()
+    } else if let Some((width, height)) = options.board_size.0 {
+        BoardConfig::sized(width, height)
+    } else if options.big_mode.0 {
+        BoardConfig::big()
+    } else if options.tall_board.0 {
+        BoardConfig::tall(TALL_BOARD_ROWS)
+    } else {
+        BoardConfig::default()
+    };
+    board.mirrored = options.mirror.0;
+    board.reversed_gravity = options.reverse_gravity.0;
+    *game_map = GameMap::new(board.width, board.height);
+    game_map.wrap_edges = options.wrap_edges.0;
+    start_run(
+        &mut game_map,
+        &options.puzzle,
+        &options.seed_entry,
+        options.practice.0.then_some(&*options.practice_garbage),
+        options.rules.garbage_style,
+        &mut rng,
+        &mut queue,
+        &mut challenge_timer,
+    );
+    log.push(format!(
+        "Starting {} game with seed {}",
+        difficulty.name(),
+        rng.seed()
+    ));
+
+    for (entity, _) in query_menu.iter() {
+        commands.entity(entity).despawn();
+    }
+    game_state.set(GameState::Playing);
+    spawn_piece(
+        &mut commands,
+        &game_map,
+        &board,
+        &options.rules,
+        &mut queue,
+        &mut rng,
+        &mut game_state,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_color::GameColor;
+    use crate::game_constants::HEIGHT;
+    use crate::game_types::Presence;
+    use crate::practice::GravityMultiplier;
+    use crate::{GravityTimer, update_gravity_speed};
+
+    // The main menu with every option at its default, `curve` loaded, and
+    // gravity following the level the way it does in play
+    fn menu_app(curve: GravityCurve) -> App {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<EventLog>()
+            .init_resource::<GarbageChallengeTimer>()
+            .init_resource::<Difficulty>()
+            .init_resource::<GameMap>()
+            .init_resource::<BoardConfig>()
+            .init_resource::<BigMode>()
+            .init_resource::<TallBoard>()
+            .init_resource::<PracticeMode>()
+            .init_resource::<PracticeGarbage>()
+            .init_resource::<GarbageChallenge>()
+            .init_resource::<VisualSettings>()
+            .init_resource::<RulesConfig>()
+            .init_resource::<ColumnTargeting>()
+            .init_resource::<MirrorBoard>()
+            .init_resource::<ActivePuzzle>()
+            .init_resource::<SeedEntry>()
+            .init_resource::<SurvivalMode>()
+            .init_resource::<RhythmMode>()
+            .init_resource::<ReverseGravity>()
+            .init_resource::<WrapEdges>()
+            .init_resource::<PieceLimit>()
+            .init_resource::<ColorTheme>()
+            .init_resource::<TouchControls>()
+            .init_resource::<MouseControls>()
+            .init_resource::<LevelSelect>()
+            .init_resource::<UiLayout>()
+            .init_resource::<RunStatsExport>()
+            .init_resource::<StreakHud>()
+            .init_resource::<MenuPage>()
+            .insert_resource(curve)
+            .init_resource::<BoardSize>()
+            .init_resource::<ResumableGame>()
+            .init_resource::<Level>()
+            .init_resource::<QuickRestart>()
+            .init_resource::<LockDelay>()
+            .init_resource::<AutoShift>()
+            .init_resource::<PieceQueue>()
+            .init_resource::<GameRng>()
+            .init_resource::<NextState<GameState>>()
+            .init_resource::<GravityMultiplier>()
+            .init_resource::<GravityTimer>()
+            .add_systems(Startup, setup_main_menu)
+            .add_systems(Update, (main_menu_input, update_gravity_speed).chain());
+        app.update();
+        app
+    }
+
+    fn press(app: &mut App, key: KeyCode) {
+        let mut keyboard_input = app.world.resource_mut::<ButtonInput<KeyCode>>();
+        keyboard_input.clear();
+        keyboard_input.press(key);
+        app.update();
+        app.world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .release(key);
+    }
+
+    #[test]
+    fn selected_start_level_sets_the_level_and_its_curve_gravity() {
+        let curve = GravityCurve::parse("1000\n800\n450\n200\n").unwrap();
+        let mut app = menu_app(curve.clone());
+        for _ in 0..3 {
+            press(&mut app, KeyCode::Equal);
+        }
+        assert_eq!(app.world.resource::<LevelSelect>().0, Some(2));
+        let mut query = app.world.query_filtered::<&Text, With<MainMenuUi>>();
+        let text = &query.single(&app.world).sections[0].value;
+        assert!(
+            text.contains("Start level: 2 of 4 (0.45 s/row)"),
+            "{}",
+            text
+        );
+
+        press(&mut app, KeyCode::Digit1);
+        assert_eq!(
+            app.world.resource::<NextState<GameState>>().0,
+            Some(GameState::Playing)
+        );
+        assert_eq!(app.world.resource::<Level>().value, 2);
+        let gravity = app.world.resource::<GravityTimer>().timer.duration();
+        assert_eq!(
+            (gravity.as_secs_f32() * 1000.0).round() as u64,
+            curve.millis_for_level(2)
+        );
+    }
+
+    #[test]
+    fn practice_garbage_height_starts_the_board_with_seeded_garbage() {
+        let mut app = menu_app(GravityCurve::default());
+        app.world.resource_mut::<SeedEntry>().text = String::from("7");
+        press(&mut app, KeyCode::KeyP);
+        press(&mut app, KeyCode::Insert);
+        press(&mut app, KeyCode::Insert);
+        assert_eq!(*app.world.resource::<PracticeGarbage>(), PracticeGarbage(4));
+        press(&mut app, KeyCode::Digit1);
+
+        let style = app.world.resource::<RulesConfig>().garbage_style;
+        let game_map = app.world.resource::<GameMap>();
+        let holes = style.hole_columns(4, game_map.width(), &mut GameRng::new(7));
+        let garbage_top = game_map.height() - 4;
+        for (y, row) in game_map.rows.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let expected = if y < garbage_top || x == holes[y - garbage_top] {
+                    Presence::No
+                } else {
+                    Presence::Yes(GameColor::DarkGray)
+                };
+                assert_eq!(*cell, expected, "cell ({}, {})", x, y);
+            }
+        }
+    }
+
+    fn menu_text(app: &mut App) -> String {
+        let mut query = app.world.query_filtered::<&Text, With<MainMenuUi>>();
+        query.single(&app.world).sections[0].value.clone()
+    }
+
+    #[test]
+    fn every_menu_page_fits_the_window() {
+        let mut app = menu_app(GravityCurve::default());
+        // Room for a line of text with the default line spacing
+        let line_height = FONT_SIZE_LOG * 1.25;
+        for page in MenuPage::ALL {
+            assert_eq!(*app.world.resource::<MenuPage>(), page);
+            let lines = menu_text(&mut app).lines().count();
+            assert!(
+                lines as f32 * line_height <= HEIGHT as f32 * 0.98,
+                "{:?} has {} lines",
+                page,
+                lines
+            );
+            press(&mut app, KeyCode::ArrowRight);
+        }
+        assert_eq!(*app.world.resource::<MenuPage>(), MenuPage::Modes);
+        press(&mut app, KeyCode::ArrowLeft);
+        assert_eq!(*app.world.resource::<MenuPage>(), MenuPage::Extras);
+    }
+
+    #[test]
+    fn changing_an_option_turns_to_its_page() {
+        let mut app = menu_app(GravityCurve::default());
+        assert!(!menu_text(&mut app).contains("Completed row preview"));
+        press(&mut app, KeyCode::Digit5);
+        assert_eq!(*app.world.resource::<MenuPage>(), MenuPage::Display);
+        let text = menu_text(&mut app);
+        assert!(text.contains("Page 3 of 4: Display"), "{}", text);
+        assert!(text.contains("5 - Completed row preview: on"), "{}", text);
+    }
+
+    #[test]
+    fn every_option_has_its_own_key() {
+        for (i, option) in MENU_OPTIONS.iter().enumerate() {
+            assert!(
+                MENU_OPTIONS[i + 1..]
+                    .iter()
+                    .all(|other| other.key != option.key),
+                "{} is bound twice",
+                option.key_name
+            );
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use crate::GameState;
+use crate::game_constants::FONT_SIZE_LOG;
+use crate::garbage::GarbageChallenge;
+use crate::practice::PracticeMode;
+use crate::puzzle::ActivePuzzle;
+use crate::rules::RulesConfig;
+use crate::survival::SurvivalMode;
+use bevy::prelude::*;
+
+// The kind of run being played, derived from the mode toggles of the menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    Marathon,
+    Practice,
+    GarbageChallenge,
+    Survival,
+    Puzzle,
+}
+
+impl GameMode {
+    // Puzzles override every other mode, then survival, garbage and practice
+    pub fn current(
+        practice: &PracticeMode,
+        challenge: &GarbageChallenge,
+        survival: &SurvivalMode,
+        puzzle: &ActivePuzzle,
+    ) -> GameMode {
+        if puzzle.0.is_some() {
+            GameMode::Puzzle
+        } else if survival.0 {
+            GameMode::Survival
+        } else if challenge.0 {
+            GameMode::GarbageChallenge
+        } else if practice.0 {
+            GameMode::Practice
+        } else {
+            GameMode::Marathon
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Marathon => "Marathon",
+            GameMode::Practice => "Practice",
+            GameMode::GarbageChallenge => "Garbage",
+            GameMode::Survival => "Survival",
+            GameMode::Puzzle => "Puzzle",
+        }
+    }
+}
+
+// One line naming the mode and the rules most likely to surprise a player
+pub fn mode_summary(mode: GameMode, rules: &RulesConfig) -> String {
+    let kicks = if rules.use_wall_kicks { "on" } else { "off" };
+    let mut summary = format!(
+        "{} | {} | kicks {} | hold {}",
+        mode.name(),
+        rules.rotation_system.name(),
+        kicks,
+        rules.holds_per_piece_name()
+    );
+    if !rules.allow_rotation {
+        summary.push_str(" | no rotation");
+    }
+    if rules.all_spin {
+        summary.push_str(" | all spin");
+    }
+    summary
+}
+
+// Marker component for the mode and rules line under the score
+#[derive(Component)]
+pub struct ModeSummaryDisplay;
+
+pub fn setup_mode_summary(mut commands: Commands) {
+    let mut text_bundle = TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: FONT_SIZE_LOG,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        top: Val::Px(110.0),
+        left: Val::Px(10.0),
+        ..default()
+    });
+    text_bundle.visibility = Visibility::Hidden;
+
+    commands.spawn((text_bundle, ModeSummaryDisplay));
+}
+
+// Refreshes the summary whenever a mode toggle or rule changes, hiding it
+// while the main menu is up
+pub fn update_mode_summary(
+    game_state: Res<State<GameState>>,
+    rules: Res<RulesConfig>,
+    practice: Res<PracticeMode>,
+    challenge: Res<GarbageChallenge>,
+    survival: Res<SurvivalMode>,
+    puzzle: Res<ActivePuzzle>,
+    mut query_text: Query<(&mut Text, &mut Visibility), With<ModeSummaryDisplay>>,
+) {
+    let changed = game_state.is_changed()
+        || rules.is_changed()
+        || practice.is_changed()
+        || challenge.is_changed()
+        || survival.is_changed()
+        || puzzle.is_changed();
+    if changed && let Some((mut text, mut visibility)) = query_text.iter_mut().next() {
+        let mode = GameMode::current(&practice, &challenge, &survival, &puzzle);
+        text.sections[0].value = mode_summary(mode, &rules);
+        *visibility = if *game_state.get() == GameState::MainMenu {
+            Visibility::Hidden
+        } else {
+            Visibility::Visible
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary_text(app: &mut App) -> String {
+        app.update();
+        let mut query = app
+            .world
+            .query_filtered::<&Text, With<ModeSummaryDisplay>>();
+        query.single(&app.world).sections[0].value.clone()
+    }
+
+    #[test]
+    fn hud_shows_the_mode_and_follows_rule_changes() {
+        let mut app = App::new();
+        app.insert_resource(State::new(GameState::Playing))
+            .insert_resource(RulesConfig {
+                use_wall_kicks: false,
+                ..RulesConfig::default()
+            })
+            .init_resource::<PracticeMode>()
+            .init_resource::<GarbageChallenge>()
+            .insert_resource(SurvivalMode(true))
+            .init_resource::<ActivePuzzle>()
+            .add_systems(Startup, setup_mode_summary)
+            .add_systems(Update, update_mode_summary);
+        assert_eq!(
+            summary_text(&mut app),
+            "Survival | SRS | kicks off | hold 1"
+        );
+
+        app.world.resource_mut::<RulesConfig>().holds_per_piece = None;
+        assert_eq!(
+            summary_text(&mut app),
+            "Survival | SRS | kicks off | hold unlimited"
+        );
+    }
+}
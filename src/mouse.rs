@@ -0,0 +1,199 @@
+use crate::event_log::EventLog;
+use crate::game_types::BoardConfig;
+use crate::touch::TouchButton;
+use bevy::input::mouse::MouseWheel;
+use bevy::prelude::*;
+use std::fs;
+
+const MOUSE_BINDINGS_FILE: &str = "mouse_bindings.txt";
+
+// Lets mouse buttons and the scroll wheel play, through MouseBindings
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MouseControls(pub bool);
+
+// A mouse button or one direction of the scroll wheel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseInput {
+    Button(MouseButton),
+    ScrollUp,
+    ScrollDown,
+}
+
+impl MouseInput {
+    fn from_name(name: &str) -> Option<MouseInput> {
+        match name {
+            "left_click" => Some(MouseInput::Button(MouseButton::Left)),
+            "right_click" => Some(MouseInput::Button(MouseButton::Right)),
+            "middle_click" => Some(MouseInput::Button(MouseButton::Middle)),
+            "scroll_up" => Some(MouseInput::ScrollUp),
+            "scroll_down" => Some(MouseInput::ScrollDown),
+            _ => None,
+        }
+    }
+}
+
+// The game action each mouse input performs. Like the touch buttons, an
+// input presses the key its action stands for, so the keyboard systems
+// handle it; a button holds the key down and a scroll notch taps it.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct MouseBindings(pub Vec<(MouseInput, TouchButton)>);
+
+impl Default for MouseBindings {
+    fn default() -> Self {
+        MouseBindings(vec![
+            (MouseInput::Button(MouseButton::Left), TouchButton::HardDrop),
+            (MouseInput::Button(MouseButton::Right), TouchButton::Hold),
+            (MouseInput::ScrollUp, TouchButton::Rotate),
+        ])
+    }
+}
+
+impl MouseBindings {
+    // Parses one "input = action" binding per line, such as
+    // "scroll_up = rotate". Blank lines and lines starting with '#' are
+    // skipped. A later line for the same input replaces the earlier one.
+    pub fn parse(text: &str) -> Result<MouseBindings, String> {
+        let mut bindings: Vec<(MouseInput, TouchButton)> = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((input, action)) = line.split_once('=') else {
+                return Err(format!("'{}' is not an input = action binding", line));
+            };
+            let input = MouseInput::from_name(input.trim())
+                .ok_or_else(|| format!("unknown mouse input '{}'", input.trim()))?;
+            let action = TouchButton::from_name(action.trim())
+                .ok_or_else(|| format!("unknown action '{}'", action.trim()))?;
+            bindings.retain(|&(bound, _)| bound != input);
+            bindings.push((input, action));
+        }
+        Ok(MouseBindings(bindings))
+    }
+}
+
+// Replaces the default bindings with the ones in the bindings file, when
+// there is one and it is valid
+pub fn load_mouse_bindings(mut bindings: ResMut<MouseBindings>, mut log: ResMut<EventLog>) {
+    let Ok(text) = fs::read_to_string(MOUSE_BINDINGS_FILE) else {
+        return;
+    };
+    match MouseBindings::parse(&text) {
+        Ok(custom) => {
+            log.push(format!("Loaded {} mouse bindings", custom.0.len()));
+            *bindings = custom;
+        }
+        Err(err) => log.push(format!("Ignoring {}: {}", MOUSE_BINDINGS_FILE, err)),
+    }
+}
+
+// Presses the keys of the bound actions. Keys tapped by the scroll wheel
+// last frame are released first; a held button's key is released when the
+// button is, even if mouse controls were turned off in between. A click on
+// an on-screen button, such as a touch button, is left to that button.
+#[allow(clippy::too_many_arguments)]
+pub fn press_mouse_bindings(
+    controls: Res<MouseControls>,
+    bindings: Res<MouseBindings>,
+    board: Res<BoardConfig>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut wheel: EventReader<MouseWheel>,
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut tapped: Local<Vec<KeyCode>>,
+    query_ui: Query<&Interaction>,
+) {
+    for key in tapped.drain(..) {
+        keyboard_input.release(key);
+    }
+    let scroll: f32 = wheel.read().map(|event| event.y).sum();
+    let over_ui = query_ui
+        .iter()
+        .any(|interaction| *interaction != Interaction::None);
+
+    for &(input, action) in &bindings.0 {
+        let key = action.key(&board);
+        match input {
+            MouseInput::Button(button) => {
+                if controls.0 && !over_ui && mouse_input.just_pressed(button) {
+                    keyboard_input.press(key);
+                }
+                if mouse_input.just_released(button) {
+                    keyboard_input.release(key);
+                }
+            }
+            MouseInput::ScrollUp | MouseInput::ScrollDown => {
+                let scrolled = match input {
+                    MouseInput::ScrollUp => scroll > 0.0,
+                    _ => scroll < 0.0,
+                };
+                if controls.0 && scrolled {
+                    keyboard_input.press(key);
+                    tapped.push(key);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_binds_left_click_to_rotate() {
+        assert_eq!(
+            MouseBindings::parse("left_click = rotate"),
+            Ok(MouseBindings(vec![(
+                MouseInput::Button(MouseButton::Left),
+                TouchButton::Rotate
+            )]))
+        );
+    }
+
+    fn mouse_app(bindings: &str) -> App {
+        let mut app = App::new();
+        app.insert_resource(MouseControls(true))
+            .insert_resource(MouseBindings::parse(bindings).unwrap())
+            .init_resource::<BoardConfig>()
+            .init_resource::<ButtonInput<MouseButton>>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .add_event::<MouseWheel>()
+            .add_systems(Update, press_mouse_bindings);
+        app
+    }
+
+    fn click(app: &mut App) -> bool {
+        app.world
+            .resource_mut::<ButtonInput<MouseButton>>()
+            .press(MouseButton::Left);
+        app.update();
+        let rotate_key = app.world.resource::<BoardConfig>().rotate_key();
+        app.world
+            .resource::<ButtonInput<KeyCode>>()
+            .just_pressed(rotate_key)
+    }
+
+    #[test]
+    fn left_click_bound_to_rotate_presses_the_rotate_key() {
+        let mut app = mouse_app("left_click = rotate");
+        assert!(click(&mut app));
+
+        let mut mouse_input = app.world.resource_mut::<ButtonInput<MouseButton>>();
+        mouse_input.clear();
+        mouse_input.release(MouseButton::Left);
+        app.update();
+        let rotate_key = app.world.resource::<BoardConfig>().rotate_key();
+        assert!(
+            !app.world
+                .resource::<ButtonInput<KeyCode>>()
+                .pressed(rotate_key)
+        );
+    }
+
+    #[test]
+    fn left_click_on_an_on_screen_button_is_not_a_binding() {
+        let mut app = mouse_app("left_click = rotate");
+        app.world.spawn(Interaction::Pressed);
+        assert!(!click(&mut app));
+    }
+}
@@ -0,0 +1,151 @@
+use crate::GameState;
+use crate::game_constants::FONT_SIZE_HUD;
+use bevy::prelude::*;
+
+// Pauses a run in progress and resumes it
+pub const PAUSE_KEY: KeyCode = KeyCode::Escape;
+
+// Marker component for the banner shown while paused
+#[derive(Component)]
+pub struct PauseBanner;
+
+pub fn setup_pause_banner(mut commands: Commands) {
+    let mut text_bundle = TextBundle::from_section(
+        "PAUSED\nEsc to resume",
+        TextStyle {
+            font_size: FONT_SIZE_HUD,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        top: Val::Percent(40.0),
+        left: Val::Percent(25.0),
+        ..default()
+    })
+    .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6));
+    text_bundle.visibility = Visibility::Hidden;
+
+    commands.spawn((text_bundle, PauseBanner));
+}
+
+pub fn toggle_pause(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    game_state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // A state change already queued this frame, such as a game over, wins
+    if !keyboard_input.just_pressed(PAUSE_KEY) || next_state.0.is_some() {
+        return;
+    }
+    match game_state.get() {
+        GameState::Playing => next_state.set(GameState::Paused),
+        GameState::Paused => next_state.set(GameState::Playing),
+        _ => {}
+    }
+}
+
+// Stops the virtual clock on pause. Playing-only systems stop on their own;
+// this also holds every timer that runs in any state, such as the line clear
+// animation and row collapse, so nothing has moved on at resume.
+pub fn pause_game(
+    mut time: ResMut<Time<Virtual>>,
+    mut query_banner: Query<&mut Visibility, With<PauseBanner>>,
+) {
+    time.pause();
+    for mut visibility in query_banner.iter_mut() {
+        *visibility = Visibility::Visible;
+    }
+}
+
+pub fn resume_game(
+    mut time: ResMut<Time<Virtual>>,
+    mut query_banner: Query<&mut Visibility, With<PauseBanner>>,
+) {
+    time.unpause();
+    for mut visibility in query_banner.iter_mut() {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{Piece, Position};
+    use crate::debug::DebugSettings;
+    use crate::events::PieceLocked;
+    use crate::game_types::{GameMap, PieceType};
+    use crate::garbage::PendingGarbage;
+    use crate::hold::HoldSlot;
+    use crate::rng::GameRng;
+    use crate::rotation::LastRotation;
+    use crate::rules::RulesConfig;
+    use crate::{EntryDelay, LockDelay, lock_grounded_piece};
+    use std::time::Duration;
+
+    #[test]
+    fn pausing_holds_the_lock_delay_where_it_was() {
+        let mut app = App::new();
+        app.init_state::<GameState>()
+            .insert_resource(NextState(Some(GameState::Playing)))
+            .init_resource::<Time>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<LockDelay>()
+            .init_resource::<GameMap>()
+            .init_resource::<RulesConfig>()
+            .init_resource::<EntryDelay>()
+            .init_resource::<GameRng>()
+            .init_resource::<HoldSlot>()
+            .init_resource::<PendingGarbage>()
+            .init_resource::<DebugSettings>()
+            .init_resource::<LastRotation>()
+            .add_event::<PieceLocked>()
+            .add_systems(
+                Update,
+                (
+                    toggle_pause,
+                    lock_grounded_piece.run_if(in_state(GameState::Playing)),
+                ),
+            );
+        // An O resting on the floor, so its lock delay is running
+        let floor = GameMap::default().height() as isize - 2;
+        app.world
+            .spawn((Position { x: 3, y: floor }, Piece::new(PieceType::O)));
+        app.update();
+        let frame = |app: &mut App, millis: u64, key: Option<KeyCode>| {
+            let mut keyboard_input = app.world.resource_mut::<ButtonInput<KeyCode>>();
+            keyboard_input.clear();
+            keyboard_input.release_all();
+            if let Some(key) = key {
+                keyboard_input.press(key);
+            }
+            app.world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_millis(millis));
+            app.update();
+        };
+        let elapsed = |app: &App| app.world.resource::<LockDelay>().timer.elapsed();
+
+        frame(&mut app, 200, None);
+        let before_pause = elapsed(&app);
+        assert_eq!(before_pause, Duration::from_millis(200));
+
+        frame(&mut app, 0, Some(PAUSE_KEY));
+        for _ in 0..20 {
+            frame(&mut app, 100, None);
+        }
+        assert_eq!(
+            *app.world.resource::<State<GameState>>().get(),
+            GameState::Paused
+        );
+        frame(&mut app, 0, Some(PAUSE_KEY));
+        frame(&mut app, 0, None);
+        assert_eq!(
+            *app.world.resource::<State<GameState>>().get(),
+            GameState::Playing
+        );
+        assert_eq!(elapsed(&app), before_pause);
+        assert!(app.world.resource::<Events<PieceLocked>>().is_empty());
+    }
+}
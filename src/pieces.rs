@@ -0,0 +1,304 @@
+use crate::components::Piece;
+use crate::game_color::GameColor;
+use crate::game_types::{PieceType, Presence};
+use crate::get_block_matrix;
+use crate::rng::GameRng;
+use crate::rules::{RotationSystem, RulesConfig};
+
+// Rotation states and color of one piece type
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PieceDefinition {
+    pub piece_type: PieceType,
+    pub states: [u16; 4],
+    pub color: GameColor,
+}
+
+// Every piece's 4x4 state encodings, in the order of PieceType::ALL. States
+// follow SRS: spawn, then each clockwise turn, with J, L, S, T and Z turning
+// about the center of their top-left 3x3 box and I about the 4x4 center.
+pub const PIECE_DEFINITIONS: [PieceDefinition; 7] = [
+    PieceDefinition {
+        piece_type: PieceType::L,
+        states: [11776, 17504, 3712, 50240],
+        color: GameColor::Orange,
+    },
+    PieceDefinition {
+        piece_type: PieceType::J,
+        states: [36352, 25664, 3616, 17600],
+        color: GameColor::Blue,
+    },
+    PieceDefinition {
+        piece_type: PieceType::S,
+        states: [27648, 17952, 1728, 35904],
+        color: GameColor::Green,
+    },
+    PieceDefinition {
+        piece_type: PieceType::Z,
+        states: [50688, 9792, 3168, 19584],
+        color: GameColor::Red,
+    },
+    PieceDefinition {
+        piece_type: PieceType::T,
+        states: [19968, 17984, 3648, 19520],
+        color: GameColor::Purple,
+    },
+    PieceDefinition {
+        piece_type: PieceType::I,
+        states: [3840, 8738, 240, 17476],
+        color: GameColor::Cyan,
+    },
+    PieceDefinition {
+        piece_type: PieceType::O,
+        states: [26112, 26112, 26112, 26112],
+        color: GameColor::Yellow,
+    },
+];
+
+// ARS state encodings in the order of PieceType::ALL. Pieces spawn flat side
+// up in the lower rows of their 3x3 box and rotate about its center.
+const ARS_STATES: [[u16; 4]; 7] = [
+    [3712, 50240, 736, 17504],  // L
+    [3616, 17600, 2272, 25664], // J
+    [1728, 35904, 1728, 35904], // S
+    [3168, 9792, 3168, 9792],   // Z
+    [3648, 19520, 1248, 17984], // T
+    [3840, 8738, 3840, 8738],   // I
+    [1632, 1632, 1632, 1632],   // O
+];
+
+pub fn definition(piece_type: PieceType) -> &'static PieceDefinition {
+    let definition = &PIECE_DEFINITIONS[piece_type as usize];
+    debug_assert_eq!(definition.piece_type, piece_type);
+    definition
+}
+
+impl Piece {
+    pub fn new(piece_type: PieceType) -> Self {
+        let definition = definition(piece_type);
+        debug_assert!(
+            definition
+                .states
+                .iter()
+                .all(|state| state.count_ones() == 4),
+            "{:?} has a state that does not decode to 4 cells",
+            piece_type
+        );
+
+        Piece {
+            piece_type,
+            states: definition.states,
+            color: definition.color,
+            current_state: 0,
+        }
+    }
+
+    // A piece using the spawn orientation and states of the given rotation system
+    pub fn with_rotation_system(piece_type: PieceType, system: RotationSystem) -> Self {
+        let mut piece = Piece::new(piece_type);
+        if system == RotationSystem::Ars {
+            piece.states = ARS_STATES[piece_type as usize];
+            debug_assert!(
+                piece.states.iter().all(|state| state.count_ones() == 4),
+                "{:?} has an ARS state that does not decode to 4 cells",
+                piece_type
+            );
+        }
+        piece
+    }
+
+    // A new piece as the current rules spawn it: the rotation system's states
+    // in the rules' spawn state and, with random colors on, a color drawn
+    // from the run's RNG
+    pub fn for_rules(piece_type: PieceType, rules: &RulesConfig, rng: &mut GameRng) -> Self {
+        let mut piece = Piece::with_rotation_system(piece_type, rules.rotation_system);
+        piece.current_state = rules.spawn_state(piece_type);
+        if rules.random_colors {
+            piece.color = rng.piece_color();
+        }
+        piece
+    }
+}
+
+impl From<PieceType> for Piece {
+    fn from(piece_type: PieceType) -> Piece {
+        Piece::new(piece_type)
+    }
+}
+
+// Occupied (column, row) cells of one rotation state, in row-major order
+pub fn state_cells(piece: &Piece, state: usize) -> Vec<(usize, usize)> {
+    let matrix = get_block_matrix(piece.states[state], piece.color);
+    let mut cells = Vec::new();
+    for (my, row) in matrix.iter().enumerate() {
+        for (mx, cell) in row.iter().enumerate() {
+            if let Presence::Yes(_) = *cell {
+                cells.push((mx, my));
+            }
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Known-good occupied cells, as (column, row) inside the 4x4 matrix, for
+    // every rotation state of every piece in the order of PieceType::ALL
+    const REFERENCE_CELLS: [[[(usize, usize); 4]; 4]; 7] = [
+        // L
+        [
+            [(2, 0), (0, 1), (1, 1), (2, 1)],
+            [(1, 0), (1, 1), (1, 2), (2, 2)],
+            [(0, 1), (1, 1), (2, 1), (0, 2)],
+            [(0, 0), (1, 0), (1, 1), (1, 2)],
+        ],
+        // J
+        [
+            [(0, 0), (0, 1), (1, 1), (2, 1)],
+            [(1, 0), (2, 0), (1, 1), (1, 2)],
+            [(0, 1), (1, 1), (2, 1), (2, 2)],
+            [(1, 0), (1, 1), (0, 2), (1, 2)],
+        ],
+        // S
+        [
+            [(1, 0), (2, 0), (0, 1), (1, 1)],
+            [(1, 0), (1, 1), (2, 1), (2, 2)],
+            [(1, 1), (2, 1), (0, 2), (1, 2)],
+            [(0, 0), (0, 1), (1, 1), (1, 2)],
+        ],
+        // Z
+        [
+            [(0, 0), (1, 0), (1, 1), (2, 1)],
+            [(2, 0), (1, 1), (2, 1), (1, 2)],
+            [(0, 1), (1, 1), (1, 2), (2, 2)],
+            [(1, 0), (0, 1), (1, 1), (0, 2)],
+        ],
+        // T
+        [
+            [(1, 0), (0, 1), (1, 1), (2, 1)],
+            [(1, 0), (1, 1), (2, 1), (1, 2)],
+            [(0, 1), (1, 1), (2, 1), (1, 2)],
+            [(1, 0), (0, 1), (1, 1), (1, 2)],
+        ],
+        // I
+        [
+            [(0, 1), (1, 1), (2, 1), (3, 1)],
+            [(2, 0), (2, 1), (2, 2), (2, 3)],
+            [(0, 2), (1, 2), (2, 2), (3, 2)],
+            [(1, 0), (1, 1), (1, 2), (1, 3)],
+        ],
+        // O
+        [
+            [(1, 0), (2, 0), (1, 1), (2, 1)],
+            [(1, 0), (2, 0), (1, 1), (2, 1)],
+            [(1, 0), (2, 0), (1, 1), (2, 1)],
+            [(1, 0), (2, 0), (1, 1), (2, 1)],
+        ],
+    ];
+
+    #[test]
+    fn new_builds_each_type_from_its_definition() {
+        let colors = [
+            (PieceType::L, GameColor::Orange),
+            (PieceType::J, GameColor::Blue),
+            (PieceType::S, GameColor::Green),
+            (PieceType::Z, GameColor::Red),
+            (PieceType::T, GameColor::Purple),
+            (PieceType::I, GameColor::Cyan),
+            (PieceType::O, GameColor::Yellow),
+        ];
+        for (piece_type, color) in colors {
+            let piece = Piece::new(piece_type);
+            assert_eq!(piece.piece_type, piece_type);
+            assert_eq!(piece.color, color);
+            assert_eq!(piece.current_state, 0);
+            for state in 0..4 {
+                assert_eq!(state_cells(&piece, state).len(), 4);
+            }
+        }
+    }
+
+    // Every 4x4 state encoding decodes to the reference shape, catching a
+    // mistyped u16 constant
+    #[test]
+    fn every_state_matches_its_reference_cells() {
+        for (piece_type, reference) in PieceType::ALL.iter().zip(REFERENCE_CELLS.iter()) {
+            let piece = Piece::new(*piece_type);
+            for (state, expected) in reference.iter().enumerate() {
+                let cells = state_cells(&piece, state);
+                assert_eq!(cells.len(), 4);
+                assert_eq!(
+                    cells.as_slice(),
+                    expected.as_slice(),
+                    "{:?} state {} does not match its reference cells",
+                    piece_type,
+                    state
+                );
+            }
+        }
+    }
+
+    // Side of the square box an SRS piece turns inside; the O piece never turns
+    fn rotation_box(piece_type: PieceType) -> Option<usize> {
+        match piece_type {
+            PieceType::O => None,
+            PieceType::I => Some(4),
+            _ => Some(3),
+        }
+    }
+
+    // Each state is the previous one turned clockwise about the center of the
+    // piece's rotation box, so a turn in open space never shifts the piece
+    // beyond what the kick tables add
+    #[test]
+    fn every_state_turns_about_its_rotation_center() {
+        for piece in PieceType::ALL.map(Piece::new) {
+            check_rotation_pivot(&piece);
+        }
+    }
+
+    fn check_rotation_pivot(piece: &Piece) {
+        for state in 0..4 {
+            let next = (state + 1) % 4;
+            let mut turned: Vec<(usize, usize)> = match rotation_box(piece.piece_type) {
+                Some(size) => state_cells(piece, state)
+                    .into_iter()
+                    .map(|(column, row)| (size - 1 - row, column))
+                    .collect(),
+                None => state_cells(piece, state),
+            };
+            turned.sort_by_key(|&(column, row)| (row, column));
+            assert_eq!(
+                turned,
+                state_cells(piece, next),
+                "{:?} state {} does not turn about its rotation center into state {}",
+                piece.piece_type,
+                state,
+                next
+            );
+        }
+    }
+
+    // Colors of `count` L pieces spawned with random colors from `seed`
+    fn random_colors(seed: u64, count: usize) -> Vec<GameColor> {
+        let rules = RulesConfig {
+            random_colors: true,
+            ..RulesConfig::default()
+        };
+        let mut rng = GameRng::new(seed);
+        (0..count)
+            .map(|_| Piece::for_rules(PieceType::L, &rules, &mut rng).color)
+            .collect()
+    }
+
+    #[test]
+    fn random_colors_follow_the_seed() {
+        let colors = random_colors(42, 20);
+        assert_eq!(colors, random_colors(42, 20));
+        let mut distinct = colors.clone();
+        distinct.sort_by_key(|color| *color as usize);
+        distinct.dedup();
+        assert!(distinct.len() > 1, "only {:?}", distinct);
+    }
+}
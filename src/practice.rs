@@ -0,0 +1,457 @@
+use crate::can_move;
+use crate::components::{Piece, Position};
+use crate::event_log::EventLog;
+use crate::game_constants::{FONT_SIZE_STATUS, Z_HINTS};
+use crate::game_types::{BoardConfig, GameMap, PieceType};
+use crate::garbage::GarbageStyle;
+use crate::line_clear::LineClearAnimation;
+use crate::queue::PieceQueue;
+use crate::rng::GameRng;
+use crate::rules::RulesConfig;
+use crate::simulation::Board;
+use bevy::prelude::*;
+
+// Multipliers the +/- keys step through, slowest to fastest
+const GRAVITY_MULTIPLIER_STEPS: [f32; 10] = [0.1, 0.25, 0.5, 0.75, 1.0, 1.5, 2.0, 3.0, 4.0, 5.0];
+const DEFAULT_STEP: usize = 4;
+
+// Practice mode unlocks training aids that are off in normal play
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PracticeMode(pub bool);
+
+// Garbage rows the practice board starts with, to drill digging without
+// building a stack first
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PracticeGarbage(pub usize);
+
+impl PracticeGarbage {
+    const STEPS: [usize; 5] = [0, 2, 4, 6, 8];
+
+    pub fn cycle(&mut self) {
+        let index = PracticeGarbage::STEPS
+            .iter()
+            .position(|&rows| rows == self.0)
+            .map_or(0, |i| (i + 1) % PracticeGarbage::STEPS.len());
+        self.0 = PracticeGarbage::STEPS[index];
+    }
+
+    pub fn name(&self) -> String {
+        match self.0 {
+            0 => String::from("off"),
+            rows => format!("{} rows", rows),
+        }
+    }
+
+    // Raises the garbage into a fresh board, holes drawn from the seeded RNG
+    // in the rules' garbage style. At most half the board is filled so small
+    // boards keep room to spawn.
+    pub fn fill(&self, game_map: &mut GameMap, style: GarbageStyle, rng: &mut GameRng) {
+        let rows = self.0.min(game_map.height() / 2);
+        if rows == 0 {
+            return;
+        }
+        let holes = style.hole_columns(rows, game_map.width(), rng);
+        game_map.insert_garbage_rows(&holes);
+    }
+}
+
+// Practice overlay marking where the current piece lands without making a hole
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PlacementHints(pub bool);
+
+// Marker component for the landing cells drawn by the placement hints
+#[derive(Component)]
+pub struct PlacementHint;
+
+// Speeds gravity up (> 1) or slows it down (< 1) on top of the level curve
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct GravityMultiplier {
+    step: usize,
+}
+
+impl Default for GravityMultiplier {
+    fn default() -> Self {
+        GravityMultiplier { step: DEFAULT_STEP }
+    }
+}
+
+impl GravityMultiplier {
+    pub fn value(&self) -> f32 {
+        GRAVITY_MULTIPLIER_STEPS[self.step]
+    }
+
+    // Seconds per row after applying the multiplier to the base interval
+    pub fn scale(&self, base_secs: f32) -> f32 {
+        base_secs / self.value()
+    }
+
+    pub fn faster(&mut self) {
+        self.step = (self.step + 1).min(GRAVITY_MULTIPLIER_STEPS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.step = self.step.saturating_sub(1);
+    }
+}
+
+// Keys that force the next spawn to a given piece for drilling placements
+const FORCE_PIECE_KEYS: [(KeyCode, PieceType); 7] = [
+    (KeyCode::KeyI, PieceType::I),
+    (KeyCode::KeyO, PieceType::O),
+    (KeyCode::KeyT, PieceType::T),
+    (KeyCode::KeyS, PieceType::S),
+    (KeyCode::KeyZ, PieceType::Z),
+    (KeyCode::KeyJ, PieceType::J),
+    (KeyCode::KeyL, PieceType::L),
+];
+
+// Marker component for the gravity multiplier readout
+#[derive(Component)]
+pub struct GravityMultiplierDisplay;
+
+pub fn adjust_gravity_multiplier(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    practice: Res<PracticeMode>,
+    mut multiplier: ResMut<GravityMultiplier>,
+) {
+    if !practice.0 {
+        return;
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        multiplier.faster();
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        multiplier.slower();
+    }
+}
+
+pub fn force_next_piece(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    practice: Res<PracticeMode>,
+    mut queue: ResMut<PieceQueue>,
+    mut log: ResMut<EventLog>,
+) {
+    if !practice.0 {
+        return;
+    }
+    for (key, piece_type) in FORCE_PIECE_KEYS {
+        if keyboard_input.just_pressed(key) {
+            queue.force_next(piece_type);
+            log.push(format!("Next piece forced to {:?}", piece_type));
+        }
+    }
+}
+
+// Key that turns the active piece into the next piece type where it stands
+const CYCLE_PIECE_KEY: KeyCode = KeyCode::Tab;
+
+// Swaps the active piece for the next type in PieceType::ALL at the same
+// position in state 0, for checking how each piece draws and collides at one
+// spot. The swap is skipped if the new piece would overlap the board.
+pub fn cycle_active_piece(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    practice: Res<PracticeMode>,
+    rules: Res<RulesConfig>,
+    game_map: Res<GameMap>,
+    mut query_piece: Query<(&mut Piece, &Position)>,
+    mut log: ResMut<EventLog>,
+) {
+    if !practice.0 || !keyboard_input.just_pressed(CYCLE_PIECE_KEY) {
+        return;
+    }
+    let Ok((mut piece, position)) = query_piece.get_single_mut() else {
+        return;
+    };
+    let mut cycled = Piece::with_rotation_system(piece.piece_type.next(), rules.rotation_system);
+    if rules.random_colors {
+        cycled.color = piece.color;
+    }
+    if !can_move(&cycled, position, position.y, &game_map) {
+        log.push(format!(
+            "No room to turn the piece into {:?}",
+            cycled.piece_type
+        ));
+        return;
+    }
+    log.push(format!("Active piece cycled to {:?}", cycled.piece_type));
+    *piece = cycled;
+}
+
+// Backspace removes the bottom row, full or not, to try out the row shift
+pub fn clear_bottom_row(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    practice: Res<PracticeMode>,
+    animation: Res<LineClearAnimation>,
+    mut game_map: ResMut<GameMap>,
+    mut log: ResMut<EventLog>,
+) {
+    if !practice.0 || animation.is_active() || !keyboard_input.just_pressed(KeyCode::Backspace) {
+        return;
+    }
+    let bottom = game_map.height().saturating_sub(1);
+    game_map.remove_row(bottom);
+    log.push("Cleared the bottom row".to_string());
+}
+
+// Delete empties the whole board at once
+pub fn clear_whole_board(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    practice: Res<PracticeMode>,
+    animation: Res<LineClearAnimation>,
+    mut game_map: ResMut<GameMap>,
+    mut log: ResMut<EventLog>,
+) {
+    if !practice.0 || animation.is_active() || !keyboard_input.just_pressed(KeyCode::Delete) {
+        return;
+    }
+    game_map.clear();
+    log.push("Cleared the board".to_string());
+}
+
+// H turns the placement hints on and off
+pub fn toggle_placement_hints(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    practice: Res<PracticeMode>,
+    mut hints: ResMut<PlacementHints>,
+) {
+    if practice.0 && keyboard_input.just_pressed(KeyCode::KeyH) {
+        hints.0 = !hints.0;
+    }
+}
+
+// Shades the landing cells of every clean placement of the current piece,
+// rebuilt when a new piece spawns or the board changes
+#[allow(clippy::too_many_arguments)]
+pub fn update_placement_hints(
+    mut commands: Commands,
+    practice: Res<PracticeMode>,
+    hints: Res<PlacementHints>,
+    board: Res<BoardConfig>,
+    game_map: Res<GameMap>,
+    rules: Res<RulesConfig>,
+    query_new_piece: Query<(), Added<Piece>>,
+    query_piece: Query<(&Piece, &Position)>,
+    query_hints: Query<Entity, With<PlacementHint>>,
+) {
+    if !hints.is_changed() && !game_map.is_changed() && query_new_piece.is_empty() {
+        return;
+    }
+    for entity in query_hints.iter() {
+        commands.entity(entity).despawn();
+    }
+    let Ok((piece, position)) = query_piece.get_single() else {
+        return;
+    };
+    if !practice.0 || !hints.0 {
+        return;
+    }
+
+    let headless = Board {
+        map: game_map.clone(),
+        active: Some((*piece, *position)),
+    };
+    for cells in headless.clean_placements(rules.allow_rotation) {
+        for (x, y) in board.visible_cells(cells) {
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgba(0.3, 1.0, 0.3, 0.15),
+                        custom_size: Some(Vec2::splat(board.cell_size)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(
+                        board.cell_translation(x, y).extend(Z_HINTS),
+                    ),
+                    ..default()
+                },
+                PlacementHint,
+            ));
+        }
+    }
+}
+
+pub fn setup_gravity_multiplier_display(mut commands: Commands) {
+    let mut text_bundle = TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: FONT_SIZE_STATUS,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        bottom: Val::Px(10.0),
+        left: Val::Px(10.0),
+        ..default()
+    });
+    text_bundle.visibility = Visibility::Hidden;
+
+    commands.spawn((text_bundle, GravityMultiplierDisplay));
+}
+
+pub fn update_gravity_multiplier_display(
+    practice: Res<PracticeMode>,
+    multiplier: Res<GravityMultiplier>,
+    mut query_text: Query<(&mut Text, &mut Visibility), With<GravityMultiplierDisplay>>,
+) {
+    if (practice.is_changed() || multiplier.is_changed())
+        && let Some((mut text, mut visibility)) = query_text.iter_mut().next()
+    {
+        text.sections[0].value = format!("Gravity x{} (+/-)", multiplier.value());
+        *visibility = if practice.0 {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gravity_curve::GravityCurve;
+    use crate::gravity_for_level;
+    use crate::{GameState, spawn_piece};
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn half_speed_multiplier_doubles_the_gravity_interval() {
+        let curve = GravityCurve::default();
+        let base = GravityMultiplier::default();
+        let mut half = base;
+        half.slower();
+        half.slower();
+        assert_eq!(half.value(), 0.5);
+
+        for level in 0..curve.levels() {
+            let normal = gravity_for_level(level, &curve, &base).as_millis();
+            let slowed = gravity_for_level(level, &curve, &half).as_millis();
+            assert!(slowed.abs_diff(normal * 2) <= 1, "level {}", level);
+        }
+    }
+
+    // Runs force_next_piece with `key` pressed, then spawns the next piece
+    fn spawn_after_pressing(practice: bool, key: KeyCode) -> PieceType {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .insert_resource(PracticeMode(practice))
+            .init_resource::<EventLog>()
+            .init_resource::<PieceQueue>()
+            .init_resource::<GameMap>()
+            .init_resource::<BoardConfig>()
+            .init_resource::<RulesConfig>()
+            .insert_resource(GameRng::new(7))
+            .init_resource::<NextState<GameState>>()
+            .add_systems(Update, force_next_piece);
+        app.world.resource_mut::<ButtonInput<KeyCode>>().press(key);
+        app.update();
+
+        app.world.run_system_once(
+            |mut commands: Commands,
+             game_map: Res<GameMap>,
+             board: Res<BoardConfig>,
+             rules: Res<RulesConfig>,
+             mut queue: ResMut<PieceQueue>,
+             mut rng: ResMut<GameRng>,
+             mut game_state: ResMut<NextState<GameState>>| {
+                spawn_piece(
+                    &mut commands,
+                    &game_map,
+                    &board,
+                    &rules,
+                    &mut queue,
+                    &mut rng,
+                    &mut game_state,
+                );
+            },
+        );
+        let mut query = app.world.query::<&Piece>();
+        query.single(&app.world).piece_type
+    }
+
+    #[test]
+    fn forced_piece_spawns_next_in_practice() {
+        assert_eq!(spawn_after_pressing(true, KeyCode::KeyS), PieceType::S);
+    }
+
+    #[test]
+    fn forcing_is_ignored_outside_practice() {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .insert_resource(PracticeMode(false))
+            .init_resource::<EventLog>()
+            .init_resource::<PieceQueue>()
+            .add_systems(Update, force_next_piece);
+        app.world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyS);
+        app.update();
+
+        assert!(app.world.resource::<PieceQueue>().upcoming().is_empty());
+    }
+
+    // The board after `system` sees `key` pressed on `board`
+    fn board_after_key<M>(
+        system: impl IntoSystemConfigs<M>,
+        key: KeyCode,
+        practice: bool,
+        board: &str,
+    ) -> String {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .insert_resource(PracticeMode(practice))
+            .init_resource::<EventLog>()
+            .init_resource::<LineClearAnimation>()
+            .insert_resource(GameMap::from_compact_string(board).unwrap())
+            .add_systems(Update, system);
+        app.world.resource_mut::<ButtonInput<KeyCode>>().press(key);
+        app.update();
+        app.world.resource::<GameMap>().to_compact_string()
+    }
+
+    #[test]
+    fn backspace_removes_the_bottom_row_and_shifts_the_rest_down() {
+        let board = "r.../.g../..b./ya.y";
+        assert_eq!(
+            board_after_key(clear_bottom_row, KeyCode::Backspace, true, board),
+            "..../r.../.g../..b."
+        );
+        assert_eq!(
+            board_after_key(clear_bottom_row, KeyCode::Backspace, false, board),
+            board
+        );
+    }
+
+    #[test]
+    fn cycle_key_turns_an_l_into_a_j_in_spawn_state() {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .insert_resource(PracticeMode(true))
+            .init_resource::<EventLog>()
+            .init_resource::<RulesConfig>()
+            .init_resource::<GameMap>()
+            .add_systems(Update, cycle_active_piece);
+        let mut piece = Piece::new(PieceType::L);
+        piece.current_state = 2;
+        let position = Position { x: 3, y: 5 };
+        let entity = app.world.spawn((piece, position)).id();
+
+        app.world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(CYCLE_PIECE_KEY);
+        app.update();
+
+        let cycled = app.world.get::<Piece>(entity).unwrap();
+        let j = Piece::new(PieceType::J);
+        assert_eq!(cycled.piece_type, PieceType::J);
+        assert_eq!(cycled.states, j.states);
+        assert_eq!(cycled.color, j.color);
+        assert_eq!(cycled.current_state, 0);
+        assert_eq!(*app.world.get::<Position>(entity).unwrap(), position);
+        assert_eq!(
+            app.world.resource::<EventLog>().lines.back().unwrap(),
+            "Active piece cycled to J"
+        );
+    }
+}
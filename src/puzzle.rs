@@ -0,0 +1,152 @@
+use crate::components::Piece;
+use crate::event_log::EventLog;
+use crate::game_types::{BoardConfig, GameMap};
+use crate::queue::PieceQueue;
+use crate::rng::GameRng;
+use crate::rules::RulesConfig;
+use crate::{EntryDelay, GameState, LockDelay, spawn_piece};
+use bevy::prelude::*;
+
+// A predefined starting board, stored in GameMap's compact string format
+pub struct Puzzle {
+    pub name: &'static str,
+    pub board: &'static str,
+}
+
+pub const PUZZLES: [Puzzle; 3] = [
+    Puzzle {
+        name: "Tetris well",
+        board: concat!(
+            "........../........../........../........../........../........../",
+            "........../........../........../........../........../........../",
+            "........../........../dddddddd../ddddddddd./ddddddddd./ddddddddd.",
+        ),
+    },
+    Puzzle {
+        name: "T-slot",
+        board: concat!(
+            "........../........../........../........../........../........../",
+            "........../........../........../........../........../........../",
+            "........../dd......../ddd...dddd/dddd.ddddd/dddd.ddddd/ddddd.dddd",
+        ),
+    },
+    Puzzle {
+        name: "Staircase",
+        board: concat!(
+            "........../........../........../........../........../........../",
+            "........../........../........../........../........../d........./",
+            "dd......../ddd......./dddd....../ddddd...../dddddd..../ddddddd...",
+        ),
+    },
+];
+
+// Puzzle chosen in the main menu; a top out restarts it from its board
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ActivePuzzle(pub Option<usize>);
+
+impl ActivePuzzle {
+    pub fn name(&self) -> &'static str {
+        self.0.map_or("off", |index| PUZZLES[index].name)
+    }
+
+    // Steps through off and every puzzle in turn
+    pub fn cycle(&mut self) {
+        self.0 = match self.0 {
+            None => Some(0),
+            Some(index) if index + 1 < PUZZLES.len() => Some(index + 1),
+            Some(_) => None,
+        };
+    }
+
+    // The checkpoint board the puzzle starts and restarts from
+    pub fn initial_board(&self) -> Option<GameMap> {
+        let puzzle = &PUZZLES[self.0?];
+        let board = GameMap::from_compact_string(puzzle.board);
+        debug_assert!(board.is_some(), "puzzle {} has a bad board", puzzle.name);
+        board
+    }
+}
+
+// Catches a top out during a puzzle and resets to the puzzle's board instead
+// of ending the game
+#[allow(clippy::too_many_arguments)]
+pub fn restart_puzzle_on_top_out(
+    mut commands: Commands,
+    puzzle: Res<ActivePuzzle>,
+    query_piece: Query<Entity, With<Piece>>,
+    mut game_map: ResMut<GameMap>,
+    board_config: Res<BoardConfig>,
+    mut lock_delay: ResMut<LockDelay>,
+    mut entry_delay: ResMut<EntryDelay>,
+    rules: Res<RulesConfig>,
+    mut queue: ResMut<PieceQueue>,
+    mut rng: ResMut<GameRng>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut log: ResMut<EventLog>,
+) {
+    if game_state.0 != Some(GameState::GameOver) {
+        return;
+    }
+    let Some(board) = puzzle.initial_board() else {
+        return;
+    };
+
+    log.push(format!("Topped out, restarting puzzle {}", puzzle.name()));
+    game_state.0 = None;
+    *game_map = GameMap {
+        wrap_edges: game_map.wrap_edges,
+        ..board
+    };
+    lock_delay.reset();
+    entry_delay.cancel();
+    for entity in query_piece.iter() {
+        commands.entity(entity).despawn();
+    }
+    spawn_piece(
+        &mut commands,
+        &game_map,
+        &board_config,
+        &rules,
+        &mut queue,
+        &mut rng,
+        &mut game_state,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_color::GameColor;
+    use crate::game_types::Presence;
+
+    #[test]
+    fn top_out_resets_to_the_puzzle_board() {
+        let puzzle = ActivePuzzle(Some(0));
+        let initial = puzzle.initial_board().unwrap();
+        let mut played = initial.clone();
+        for x in 0..played.width() as isize {
+            played.set_cell(x, 0, Presence::Yes(GameColor::Red));
+        }
+
+        let mut app = App::new();
+        app.init_resource::<BoardConfig>()
+            .init_resource::<EventLog>()
+            .init_resource::<LockDelay>()
+            .init_resource::<EntryDelay>()
+            .init_resource::<RulesConfig>()
+            .init_resource::<PieceQueue>()
+            .init_resource::<GameRng>()
+            .insert_resource(puzzle)
+            .insert_resource(played)
+            .insert_resource(NextState(Some(GameState::GameOver)))
+            .add_systems(Update, restart_puzzle_on_top_out);
+        app.update();
+
+        let game_map = app.world.resource::<GameMap>();
+        assert_eq!(game_map.to_compact_string(), initial.to_compact_string());
+        assert_ne!(
+            app.world.resource::<NextState<GameState>>().0,
+            Some(GameState::GameOver)
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use crate::game_types::PieceType;
+use crate::rng::GameRng;
+use crate::rules::BagWeights;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+// Pieces waiting to spawn ahead of the random generator
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Default)]
+pub struct PieceQueue {
+    upcoming: VecDeque<PieceType>,
+}
+
+impl PieceQueue {
+    // Takes the next queued piece, drawing a weighted random one when the
+    // queue is empty
+    pub fn next(&mut self, rng: &mut GameRng, weights: &BagWeights) -> PieceType {
+        self.upcoming
+            .pop_front()
+            .unwrap_or_else(|| rng.piece_type(weights))
+    }
+
+    // Makes piece_type the very next piece to spawn
+    pub fn force_next(&mut self, piece_type: PieceType) {
+        self.upcoming.push_front(piece_type);
+    }
+
+    // Pieces already queued, next first
+    pub fn upcoming(&self) -> Vec<PieceType> {
+        self.upcoming.iter().copied().collect()
+    }
+
+    pub fn set_upcoming(&mut self, pieces: &[PieceType]) {
+        self.upcoming = pieces.iter().copied().collect();
+    }
+
+    pub fn clear(&mut self) {
+        self.upcoming.clear();
+    }
+}
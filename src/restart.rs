@@ -0,0 +1,333 @@
+use crate::blitz::PiecesPlaced;
+use crate::components::Piece;
+use crate::event_log::EventLog;
+use crate::game_constants::FONT_SIZE_STATUS;
+use crate::game_types::{BoardConfig, GameMap};
+use crate::garbage::{GarbageChallengeTimer, GarbageStyle, PendingGarbage};
+use crate::hold::HoldSlot;
+use crate::line_clear::{LineClearAnimation, RowCollapse};
+use crate::practice::{PracticeGarbage, PracticeMode};
+use crate::puzzle::ActivePuzzle;
+use crate::queue::PieceQueue;
+use crate::rhythm::Metronome;
+use crate::rng::GameRng;
+use crate::rules::RulesConfig;
+use crate::run_stats::RunStats;
+use crate::seed::SeedEntry;
+use crate::streak::ClearStreak;
+use crate::survival::SurvivalClock;
+use crate::{EntryDelay, GameState, GravityTimer, Level, LockDelay, Score, spawn_piece};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use std::time::Duration;
+
+// Held with either Shift key to throw away the run and start over
+const QUICK_RESTART_KEY: KeyCode = KeyCode::KeyR;
+// Runs scoring more than this need a second press within the confirm window
+const CONFIRM_SCORE: u32 = 1000;
+const CONFIRM_WINDOW: Duration = Duration::from_secs(2);
+
+// Level the run started at, to restart from, and the pending confirmation
+#[derive(Resource, Debug, Clone, Default)]
+pub struct QuickRestart {
+    pub start_level: u32,
+    confirm: Option<Timer>,
+}
+
+impl QuickRestart {
+    pub fn awaiting_confirm(&self) -> bool {
+        self.confirm.is_some()
+    }
+}
+
+// Everything a run accumulates that a restart puts back to its start
+#[derive(SystemParam)]
+pub struct RunProgress<'w> {
+    score: ResMut<'w, Score>,
+    level: ResMut<'w, Level>,
+    lock_delay: ResMut<'w, LockDelay>,
+    entry_delay: ResMut<'w, EntryDelay>,
+    hold: ResMut<'w, HoldSlot>,
+    pending_garbage: ResMut<'w, PendingGarbage>,
+    animation: ResMut<'w, LineClearAnimation>,
+    collapse: ResMut<'w, RowCollapse>,
+    gravity: ResMut<'w, GravityTimer>,
+    clock: ResMut<'w, SurvivalClock>,
+    metronome: ResMut<'w, Metronome>,
+    placed: ResMut<'w, PiecesPlaced>,
+    stats: ResMut<'w, RunStats>,
+    streak: ResMut<'w, ClearStreak>,
+}
+
+impl RunProgress<'_> {
+    fn reset(&mut self, start_level: u32) {
+        *self.score = Score::default();
+        *self.level = Level {
+            value: start_level,
+            lines_cleared_in_level: 0,
+        };
+        self.lock_delay.reset();
+        self.entry_delay.cancel();
+        *self.hold = HoldSlot::default();
+        *self.pending_garbage = PendingGarbage::default();
+        self.animation.finish();
+        self.collapse.finish();
+        self.gravity.timer.reset();
+        *self.clock = SurvivalClock::default();
+        *self.metronome = Metronome::default();
+        *self.placed = PiecesPlaced::default();
+        *self.stats = RunStats::default();
+        *self.streak = ClearStreak::default();
+    }
+}
+
+// Sets up the board and piece sequence of a run starting from scratch, from
+// the main menu or a quick restart. `game_map` comes in as the empty board to
+// play on and keeps its wrapping edges and blocked cells: a puzzle swaps in
+// its own layout, and practice without one starts under its garbage.
+#[allow(clippy::too_many_arguments)]
+pub fn start_run(
+    game_map: &mut GameMap,
+    puzzle: &ActivePuzzle,
+    seed_entry: &SeedEntry,
+    practice_garbage: Option<&PracticeGarbage>,
+    garbage_style: GarbageStyle,
+    rng: &mut GameRng,
+    queue: &mut PieceQueue,
+    challenge_timer: &mut GarbageChallengeTimer,
+) {
+    let puzzle_board = puzzle.initial_board();
+    let is_puzzle = puzzle_board.is_some();
+    if let Some(mut board) = puzzle_board {
+        board.wrap_edges = game_map.wrap_edges;
+        *game_map = board;
+    }
+    // A seeded run replays its piece sequence, and its practice garbage,
+    // from the start
+    if let Some(seed) = seed_entry.seed() {
+        *rng = GameRng::new(seed);
+    }
+    if let Some(practice_garbage) = practice_garbage
+        && !is_puzzle
+    {
+        practice_garbage.fill(game_map, garbage_style, rng);
+    }
+    queue.clear();
+    challenge_timer.0.reset();
+}
+
+// Marker component for the prompt asking to press the restart key again
+#[derive(Component)]
+pub struct QuickRestartPrompt;
+
+// Shift+R starts the run over on a fresh board at the starting level,
+// staying in Playing, after a confirming second press for a run worth keeping
+#[allow(clippy::too_many_arguments)]
+pub fn quick_restart(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    mut quick_restart: ResMut<QuickRestart>,
+    mut progress: RunProgress,
+    mut game_map: ResMut<GameMap>,
+    board_config: Res<BoardConfig>,
+    puzzle: Res<ActivePuzzle>,
+    seed_entry: Res<SeedEntry>,
+    rules: Res<RulesConfig>,
+    (practice, practice_garbage): (Res<PracticeMode>, Res<PracticeGarbage>),
+    (mut queue, mut challenge_timer): (ResMut<PieceQueue>, ResMut<GarbageChallengeTimer>),
+    mut rng: ResMut<GameRng>,
+    mut game_state: ResMut<NextState<GameState>>,
+    query_piece: Query<Entity, With<Piece>>,
+    mut log: ResMut<EventLog>,
+) {
+    if quick_restart.awaiting_confirm()
+        && let Some(timer) = &mut quick_restart.confirm
+        && timer.tick(time.delta()).finished()
+    {
+        quick_restart.confirm = None;
+    }
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !shift || !keyboard_input.just_pressed(QUICK_RESTART_KEY) {
+        return;
+    }
+    if progress.score.value > CONFIRM_SCORE && !quick_restart.awaiting_confirm() {
+        quick_restart.confirm = Some(Timer::new(CONFIRM_WINDOW, TimerMode::Once));
+        return;
+    }
+
+    quick_restart.confirm = None;
+    progress.reset(quick_restart.start_level);
+    game_map.clear();
+    start_run(
+        &mut game_map,
+        &puzzle,
+        &seed_entry,
+        practice.0.then_some(&*practice_garbage),
+        rules.garbage_style,
+        &mut rng,
+        &mut queue,
+        &mut challenge_timer,
+    );
+    log.push(format!("Quick restart with seed {}", rng.seed()));
+
+    for entity in query_piece.iter() {
+        commands.entity(entity).despawn();
+    }
+    spawn_piece(
+        &mut commands,
+        &game_map,
+        &board_config,
+        &rules,
+        &mut queue,
+        &mut rng,
+        &mut game_state,
+    );
+}
+
+pub fn setup_quick_restart_prompt(mut commands: Commands) {
+    let mut text_bundle = TextBundle::from_section(
+        "Shift+R again to restart",
+        TextStyle {
+            font_size: FONT_SIZE_STATUS,
+            color: Color::ORANGE,
+            ..default()
+        },
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        top: Val::Percent(45.0),
+        left: Val::Percent(25.0),
+        ..default()
+    })
+    .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6));
+    text_bundle.visibility = Visibility::Hidden;
+
+    commands.spawn((text_bundle, QuickRestartPrompt));
+}
+
+pub fn update_quick_restart_prompt(
+    quick_restart: Res<QuickRestart>,
+    mut query_prompt: Query<&mut Visibility, With<QuickRestartPrompt>>,
+) {
+    if !quick_restart.is_changed() {
+        return;
+    }
+    for mut visibility in query_prompt.iter_mut() {
+        *visibility = if quick_restart.awaiting_confirm() {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::Position;
+    use crate::game_color::GameColor;
+    use crate::game_types::{PieceType, Presence};
+
+    fn restart_app(score: u32) -> App {
+        let mut game_map = GameMap::default();
+        game_map.set_cell(0, 17, Presence::Yes(GameColor::Red));
+        game_map.block_cell(9, 17);
+        let mut challenge_timer = GarbageChallengeTimer::default();
+        challenge_timer.0.tick(Duration::from_secs(3));
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<EventLog>()
+            .init_resource::<Time>()
+            .init_resource::<QuickRestart>()
+            .insert_resource(Score { value: score })
+            .insert_resource(Level {
+                value: 3,
+                lines_cleared_in_level: 4,
+            })
+            .init_resource::<LockDelay>()
+            .init_resource::<EntryDelay>()
+            .init_resource::<HoldSlot>()
+            .init_resource::<PendingGarbage>()
+            .init_resource::<LineClearAnimation>()
+            .init_resource::<RowCollapse>()
+            .init_resource::<GravityTimer>()
+            .init_resource::<SurvivalClock>()
+            .init_resource::<Metronome>()
+            .init_resource::<PiecesPlaced>()
+            .init_resource::<RunStats>()
+            .init_resource::<ClearStreak>()
+            .insert_resource(game_map)
+            .init_resource::<BoardConfig>()
+            .init_resource::<ActivePuzzle>()
+            .init_resource::<SeedEntry>()
+            .init_resource::<RulesConfig>()
+            .init_resource::<PracticeMode>()
+            .init_resource::<PracticeGarbage>()
+            .init_resource::<PieceQueue>()
+            .insert_resource(challenge_timer)
+            .init_resource::<GameRng>()
+            .init_resource::<NextState<GameState>>()
+            .add_systems(Update, quick_restart);
+        app.world
+            .spawn((Piece::new(PieceType::T), Position { x: 3, y: 12 }));
+        app
+    }
+
+    fn press_shift_r(app: &mut App) {
+        let mut keyboard_input = app.world.resource_mut::<ButtonInput<KeyCode>>();
+        keyboard_input.release(QUICK_RESTART_KEY);
+        keyboard_input.clear();
+        keyboard_input.press(KeyCode::ShiftLeft);
+        keyboard_input.press(QUICK_RESTART_KEY);
+        app.update();
+    }
+
+    // Positions of the active pieces
+    fn pieces(app: &mut App) -> Vec<Position> {
+        let mut query = app.world.query_filtered::<&Position, With<Piece>>();
+        query.iter(&app.world).copied().collect()
+    }
+
+    #[test]
+    fn restart_mid_game_resets_the_run_and_spawns_a_fresh_piece() {
+        let mut app = restart_app(200);
+        press_shift_r(&mut app);
+
+        let game_map = app.world.resource::<GameMap>();
+        assert!(
+            game_map
+                .rows
+                .iter()
+                .flatten()
+                .all(|cell| *cell == Presence::No)
+        );
+        assert_eq!(game_map.blocked, vec![(9, 17)]);
+        assert!(
+            app.world
+                .resource::<GarbageChallengeTimer>()
+                .0
+                .elapsed()
+                .is_zero()
+        );
+        assert_eq!(app.world.resource::<Score>().value, 0);
+        assert_eq!(app.world.resource::<Level>().lines_cleared_in_level, 0);
+        assert_eq!(app.world.resource::<NextState<GameState>>().0, None);
+        let spawned = pieces(&mut app);
+        assert_eq!(spawned.len(), 1);
+        assert_eq!(spawned[0].y, 0);
+    }
+
+    #[test]
+    fn a_run_worth_keeping_needs_a_second_press() {
+        let mut app = restart_app(CONFIRM_SCORE + 1);
+        press_shift_r(&mut app);
+        assert!(app.world.resource::<QuickRestart>().awaiting_confirm());
+        assert_eq!(app.world.resource::<Score>().value, CONFIRM_SCORE + 1);
+        assert_eq!(pieces(&mut app), vec![Position { x: 3, y: 12 }]);
+
+        press_shift_r(&mut app);
+        assert_eq!(app.world.resource::<Score>().value, 0);
+        assert!(!app.world.resource::<QuickRestart>().awaiting_confirm());
+    }
+}
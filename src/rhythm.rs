@@ -0,0 +1,249 @@
+use crate::Score;
+use crate::event_log::EventLog;
+use crate::events::PieceLocked;
+use crate::game_constants::FONT_SIZE_LOG;
+use bevy::prelude::*;
+use std::time::Duration;
+
+// Tempos offered by the menu, in beats per minute
+const TEMPOS: [u32; 3] = [90, 120, 150];
+
+// A lock this close to a beat, either side of it, counts as on the beat
+const BEAT_WINDOW: Duration = Duration::from_millis(100);
+// Points added for every lock on the beat
+const RHYTHM_BONUS: u32 = 50;
+
+// The metronome click, a short high note
+const TICK_FREQUENCY: f32 = 1760.0;
+const TICK_DURATION: Duration = Duration::from_millis(40);
+
+// Rhythm play: gravity steps once per beat of a metronome at this tempo
+// instead of following the level curve
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RhythmMode(pub Option<u32>);
+
+impl RhythmMode {
+    pub fn name(&self) -> String {
+        match self.0 {
+            Some(bpm) => format!("{} BPM", bpm),
+            None => String::from("off"),
+        }
+    }
+
+    // Steps through off and every tempo in turn
+    pub fn cycle(&mut self) {
+        let next = match self.0 {
+            None => Some(0),
+            Some(bpm) => TEMPOS
+                .iter()
+                .position(|&tempo| tempo == bpm)
+                .map(|index| index + 1)
+                .filter(|&index| index < TEMPOS.len()),
+        };
+        self.0 = next.map(|index| TEMPOS[index]);
+    }
+}
+
+// Time between beats at a tempo
+pub fn beat_duration(bpm: u32) -> Duration {
+    Duration::from_secs_f32(60.0 / bpm.max(1) as f32)
+}
+
+// Beat clock for rhythm play, and how many locks so far landed on the beat
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Metronome {
+    pub timer: Option<Timer>,
+    pub locks: u32,
+    pub on_beat_locks: u32,
+}
+
+impl Metronome {
+    // Beats that fell this frame, or None when rhythm play is off
+    pub fn beats_this_frame(&self) -> Option<u32> {
+        self.timer.as_ref().map(Timer::times_finished_this_tick)
+    }
+
+    // Whether now is within BEAT_WINDOW of the last or the next beat
+    pub fn is_on_beat(&self) -> bool {
+        self.timer
+            .as_ref()
+            .is_some_and(|timer| timer.elapsed() <= BEAT_WINDOW || timer.remaining() <= BEAT_WINDOW)
+    }
+
+    // Scores a lock against the beat; returns the bonus it earned
+    pub fn judge_lock(&mut self) -> u32 {
+        self.locks += 1;
+        if self.is_on_beat() {
+            self.on_beat_locks += 1;
+            RHYTHM_BONUS
+        } else {
+            0
+        }
+    }
+
+    // Share of locks on the beat, as a whole percentage
+    pub fn accuracy(&self) -> u32 {
+        if self.locks == 0 {
+            return 0;
+        }
+        self.on_beat_locks * 100 / self.locks
+    }
+}
+
+// Advances the beat clock at the chosen tempo and clicks on every beat
+pub fn tick_metronome(
+    mut commands: Commands,
+    time: Res<Time>,
+    rhythm: Res<RhythmMode>,
+    mut metronome: ResMut<Metronome>,
+    mut pitches: ResMut<Assets<Pitch>>,
+) {
+    let Some(bpm) = rhythm.0 else {
+        metronome.timer = None;
+        return;
+    };
+    let timer = metronome
+        .timer
+        .get_or_insert_with(|| Timer::new(beat_duration(bpm), TimerMode::Repeating));
+    timer.tick(time.delta());
+    if timer.times_finished_this_tick() > 0 {
+        commands.spawn(PitchBundle {
+            source: pitches.add(Pitch::new(TICK_FREQUENCY, TICK_DURATION)),
+            settings: PlaybackSettings::DESPAWN,
+        });
+    }
+}
+
+// Adds the rhythm bonus for every lock that lands on the beat
+pub fn award_rhythm_bonus(
+    mut locked: EventReader<PieceLocked>,
+    mut metronome: ResMut<Metronome>,
+    mut score: ResMut<Score>,
+    mut log: ResMut<EventLog>,
+) {
+    for _ in locked.read() {
+        if metronome.timer.is_none() {
+            continue;
+        }
+        let bonus = metronome.judge_lock();
+        if bonus > 0 {
+            score.value += bonus;
+            log.push(format!("On the beat, +{}", bonus));
+        }
+    }
+}
+
+// Marker component for the on-beat accuracy readout
+#[derive(Component)]
+pub struct RhythmDisplay;
+
+pub fn setup_rhythm_display(mut commands: Commands) {
+    let mut text_bundle = TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: FONT_SIZE_LOG,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        top: Val::Px(128.0),
+        left: Val::Px(10.0),
+        ..default()
+    });
+    text_bundle.visibility = Visibility::Hidden;
+
+    commands.spawn((text_bundle, RhythmDisplay));
+}
+
+pub fn update_rhythm_display(
+    rhythm: Res<RhythmMode>,
+    metronome: Res<Metronome>,
+    mut query_text: Query<(&mut Text, &mut Visibility), With<RhythmDisplay>>,
+) {
+    if (rhythm.is_changed() || metronome.is_changed())
+        && let Some((mut text, mut visibility)) = query_text.iter_mut().next()
+    {
+        text.sections[0].value = format!(
+            "{} | on beat {}/{} ({}%)",
+            rhythm.name(),
+            metronome.on_beat_locks,
+            metronome.locks,
+            metronome.accuracy()
+        );
+        *visibility = if metronome.timer.is_some() {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action_trace::ActionTrace;
+    use crate::components::{Piece, Position};
+    use crate::debug::DebugSettings;
+    use crate::events::PieceMovedDown;
+    use crate::game_types::{BoardConfig, GameMap, PieceType};
+    use crate::rules::RulesConfig;
+    use crate::survival::SurvivalMode;
+    use crate::{GravityTimer, LockDelay, move_piece_down};
+
+    #[test]
+    fn each_beat_steps_gravity_clicks_and_rewards_an_on_beat_lock() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<EventLog>()
+            .insert_resource(RhythmMode(Some(120)))
+            .init_resource::<Metronome>()
+            .init_resource::<Assets<Pitch>>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<RulesConfig>()
+            .init_resource::<GravityTimer>()
+            .init_resource::<LockDelay>()
+            .init_resource::<GameMap>()
+            .init_resource::<BoardConfig>()
+            .init_resource::<ActionTrace>()
+            .init_resource::<DebugSettings>()
+            .init_resource::<Score>()
+            .init_resource::<SurvivalMode>()
+            .add_event::<PieceMovedDown>()
+            .add_event::<PieceLocked>()
+            .add_systems(
+                Update,
+                (tick_metronome, move_piece_down, award_rhythm_bonus).chain(),
+            );
+        let piece = app
+            .world
+            .spawn((Position { x: 3, y: 0 }, Piece::new(PieceType::O)))
+            .id();
+        let beat = beat_duration(120);
+        let clicks = |app: &mut App| app.world.query::<&Handle<Pitch>>().iter(&app.world).count();
+
+        app.update();
+        app.world.resource_mut::<Time>().advance_by(beat / 2);
+        app.update();
+        assert_eq!(app.world.get::<Position>(piece).unwrap().y, 0);
+        assert_eq!(clicks(&mut app), 0);
+
+        app.world.resource_mut::<Time>().advance_by(beat / 2);
+        app.update();
+        assert_eq!(app.world.get::<Position>(piece).unwrap().y, 1);
+        assert_eq!(clicks(&mut app), 1);
+
+        // A lock right on the beat just struck earns the bonus
+        app.world.send_event(PieceLocked {
+            piece_type: PieceType::O,
+            position: Position { x: 3, y: 1 },
+            cells: [(4, 1), (5, 1), (4, 2), (5, 2)],
+            spin: false,
+        });
+        app.world.resource_mut::<Time>().advance_by(Duration::ZERO);
+        app.update();
+        assert_eq!(app.world.resource::<Score>().value, RHYTHM_BONUS);
+        assert_eq!(app.world.resource::<Metronome>().on_beat_locks, 1);
+    }
+}
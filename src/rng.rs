@@ -0,0 +1,95 @@
+use crate::game_color::{GameColor, PIECE_COLORS};
+use crate::game_types::PieceType;
+use crate::rules::BagWeights;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// Seeded random source so a run can be reproduced from its seed
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng::new(rand::random())
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Restarts the generator from a seed drawn from its own stream and
+    // returns that seed, so a saved run resumes the exact same sequence
+    pub fn checkpoint(&mut self) -> u64 {
+        let stream = self.rng.random();
+        self.rng = StdRng::seed_from_u64(stream);
+        stream
+    }
+
+    // A run's generator picked back up at a checkpoint
+    pub fn resume(seed: u64, stream: u64) -> Self {
+        GameRng {
+            seed,
+            rng: StdRng::seed_from_u64(stream),
+        }
+    }
+
+    pub fn column(&mut self, width: usize) -> usize {
+        self.rng.random_range(0..width)
+    }
+
+    // Draws a piece type with odds proportional to its weight
+    pub fn piece_type(&mut self, weights: &BagWeights) -> PieceType {
+        let roll = self.rng.random_range(0..weights.total());
+        weights.piece_for_roll(roll)
+    }
+
+    pub fn piece_color(&mut self) -> GameColor {
+        PIECE_COLORS[self.rng.random_range(0..PIECE_COLORS.len())]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // How many of `draws` weighted draws from a fixed seed are `piece_type`
+    fn count_drawn(weights: &BagWeights, piece_type: PieceType, draws: usize) -> usize {
+        let mut rng = GameRng::new(7);
+        (0..draws)
+            .filter(|_| rng.piece_type(weights) == piece_type)
+            .count()
+    }
+
+    #[test]
+    fn zero_weight_excludes_a_piece_and_a_high_weight_dominates() {
+        let mut weights = BagWeights::default();
+        assert!(weights.set(PieceType::I, 0));
+        assert_eq!(count_drawn(&weights, PieceType::I, 1000), 0);
+
+        assert!(weights.set(PieceType::T, 100));
+        assert!(count_drawn(&weights, PieceType::T, 1000) > 700);
+    }
+
+    #[test]
+    fn the_last_nonzero_weight_cannot_be_cleared() {
+        let mut weights = BagWeights::default();
+        for piece_type in &PieceType::ALL[..6] {
+            assert!(weights.set(*piece_type, 0));
+        }
+        assert!(!weights.set(PieceType::ALL[6], 0));
+        assert_eq!(count_drawn(&weights, PieceType::ALL[6], 100), 100);
+    }
+}
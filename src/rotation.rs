@@ -0,0 +1,231 @@
+use crate::can_rotate;
+use crate::components::{Piece, Position};
+use crate::game_types::{GameMap, PieceType, Presence};
+use crate::pieces::state_cells;
+use crate::rules::{RotationSystem, RulesConfig};
+use bevy::prelude::*;
+
+// SRS clockwise kick tests for J, L, S, T and Z, indexed by the state being
+// rotated from. Offsets are (x, y) with y pointing down the board.
+const JLSTZ_KICKS: [[(isize, isize); 5]; 4] = [
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+];
+
+// SRS clockwise kick tests for the I piece
+const I_KICKS: [[(isize, isize); 5]; 4] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)],
+    [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)],
+    [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)],
+    [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)],
+];
+
+// ARS kicks: rotate in place, else one column right, else one column left
+const ARS_KICKS: [(isize, isize); 3] = [(0, 0), (1, 0), (-1, 0)];
+
+// Kick offset used by the most recent successful rotation, for the debug overlay
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LastKick(pub Option<(isize, isize)>);
+
+// Where the active piece stood after its most recent successful rotation;
+// a lock at that spot means rotating was the last thing the piece did
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LastRotation(pub Option<Position>);
+
+// The latest rotation attempt, whether or not it turned the piece, for the
+// kick debug overlay
+#[derive(Resource, Debug, Clone, PartialEq, Default)]
+pub struct KickAttempt {
+    /// The piece turned to its new state, at the position it turned from.
+    pub rotated: Option<(Piece, Position)>,
+    /// Kick offsets tried, in order.
+    pub candidates: Vec<(isize, isize)>,
+    /// The offset the rotation used; None if every candidate was blocked.
+    pub accepted: Option<(isize, isize)>,
+}
+
+impl KickAttempt {
+    // Records an attempt to rotate `piece` and its `result` from rotate_cw
+    pub fn record(
+        &mut self,
+        piece: &Piece,
+        position: &Position,
+        game_map: &GameMap,
+        rules: &RulesConfig,
+        result: Option<Rotation>,
+    ) {
+        let (rotated, candidates) = kick_candidates(piece, position, game_map, rules);
+        *self = KickAttempt {
+            rotated: Some((rotated, *position)),
+            candidates: candidates.to_vec(),
+            accepted: result.map(|rotation| rotation.kick),
+        };
+    }
+}
+
+// Outcome of a successful rotation
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rotation {
+    pub state: usize,
+    pub position: Position,
+    pub kick: (isize, isize),
+}
+
+// Offsets tried, in order, when rotating clockwise out of `from_state`
+pub fn kick_offsets(piece_type: PieceType, from_state: usize) -> &'static [(isize, isize)] {
+    match piece_type {
+        PieceType::O => &JLSTZ_KICKS[0][..1],
+        PieceType::I => &I_KICKS[from_state],
+        _ => &JLSTZ_KICKS[from_state],
+    }
+}
+
+// ARS offsets for a rotation. I and O never kick. L, J and T may not kick
+// when the first blocked cell, in reading order, is in the center column of
+// their 3x3 box, so they cannot climb over a block they are rotating around.
+fn ars_kick_offsets(
+    rotated: &Piece,
+    position: &Position,
+    game_map: &GameMap,
+) -> &'static [(isize, isize)] {
+    match rotated.piece_type {
+        PieceType::I | PieceType::O => &ARS_KICKS[..1],
+        PieceType::L | PieceType::J | PieceType::T => {
+            let first_blocked = state_cells(rotated, rotated.current_state)
+                .into_iter()
+                .find(|&(column, row)| {
+                    let cell = Position {
+                        x: position.x + column as isize,
+                        y: position.y + row as isize,
+                    };
+                    !cell_is_open(&cell, game_map)
+                });
+            match first_blocked {
+                Some((1, _)) => &ARS_KICKS[..1],
+                _ => &ARS_KICKS,
+            }
+        }
+        PieceType::S | PieceType::Z => &ARS_KICKS,
+    }
+}
+
+// True when a single board cell is inside the walls and floor and empty;
+// rows above the board count as open
+fn cell_is_open(cell: &Position, game_map: &GameMap) -> bool {
+    if game_map.column(cell.x).is_none() {
+        return false;
+    }
+    cell.y < 0 || game_map.cell(cell.x, cell.y) == Some(Presence::No)
+}
+
+// The piece turned clockwise and the kick offsets to try for it under the
+// rules: each wall kick in turn, or only the unshifted rotation when kicks
+// are disabled
+fn kick_candidates(
+    piece: &Piece,
+    position: &Position,
+    game_map: &GameMap,
+    rules: &RulesConfig,
+) -> (Piece, &'static [(isize, isize)]) {
+    let mut rotated = *piece;
+    rotated.current_state = (piece.current_state + 1) % 4;
+
+    let offsets = match (rules.use_wall_kicks, rules.rotation_system) {
+        (false, _) => &[(0, 0)],
+        (true, RotationSystem::Srs) => kick_offsets(piece.piece_type, piece.current_state),
+        (true, RotationSystem::Ars) => ars_kick_offsets(&rotated, position, game_map),
+    };
+    (rotated, offsets)
+}
+
+// Rotates clockwise into the first kick candidate that fits. Never rotates
+// when the rules forbid rotation.
+pub fn rotate_cw(
+    piece: &Piece,
+    position: &Position,
+    game_map: &GameMap,
+    rules: &RulesConfig,
+) -> Option<Rotation> {
+    if !rules.allow_rotation {
+        return None;
+    }
+    let (rotated, offsets) = kick_candidates(piece, position, game_map, rules);
+
+    offsets.iter().find_map(|&(dx, dy)| {
+        let kicked = Position {
+            x: position.x + dx,
+            y: position.y + dy,
+        };
+        can_rotate(&rotated, &kicked, game_map).then_some(Rotation {
+            state: rotated.current_state,
+            position: kicked,
+            kick: (dx, dy),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_color::GameColor;
+
+    #[test]
+    fn rotation_against_the_wall_needs_a_kick() {
+        let game_map = GameMap::default();
+        let mut piece = Piece::new(PieceType::I);
+        piece.current_state = 1;
+        // Vertical I flush with the left wall; lying flat would poke through it
+        let position = Position { x: -2, y: 5 };
+        let kicks_off = RulesConfig {
+            use_wall_kicks: false,
+            ..RulesConfig::default()
+        };
+
+        assert_eq!(rotate_cw(&piece, &position, &game_map, &kicks_off), None);
+        let kicked = rotate_cw(&piece, &position, &game_map, &RulesConfig::default());
+        assert_eq!(kicked.map(|rotation| rotation.state), Some(2));
+        assert_ne!(kicked.map(|rotation| rotation.kick), Some((0, 0)));
+    }
+
+    fn ars_rules() -> RulesConfig {
+        RulesConfig {
+            rotation_system: RotationSystem::Ars,
+            ..RulesConfig::default()
+        }
+    }
+
+    #[test]
+    fn ars_t_turns_flat_side_up_and_kicks_off_the_wall() {
+        let game_map = GameMap::default();
+        let mut piece = Piece::with_rotation_system(PieceType::T, RotationSystem::Ars);
+        piece.current_state = 3;
+        // Pointing right with its stem in column 0; turning back to spawn
+        // would push a cell through the left wall
+        let position = Position { x: -1, y: 5 };
+
+        let rotation = rotate_cw(&piece, &position, &game_map, &ars_rules()).unwrap();
+        assert_eq!(rotation.state, 0);
+        assert_eq!(rotation.kick, (1, 0));
+        assert_eq!(rotation.position, Position { x: 0, y: 5 });
+        // ARS spawns T pointing down in the lower rows of its box, unlike SRS
+        assert_eq!(state_cells(&piece, 0), vec![(0, 1), (1, 1), (2, 1), (1, 2)]);
+        assert_ne!(
+            state_cells(&piece, 0),
+            state_cells(&Piece::new(PieceType::T), 0)
+        );
+    }
+
+    #[test]
+    fn ars_t_does_not_kick_around_a_center_column_block() {
+        let mut game_map = GameMap::default();
+        let piece = Piece::with_rotation_system(PieceType::T, RotationSystem::Ars);
+        let position = Position { x: 3, y: 5 };
+        // Blocks the top of the center column, the first cell state 1 needs
+        game_map.set_cell(4, 5, Presence::Yes(GameColor::Gray));
+
+        assert_eq!(rotate_cw(&piece, &position, &game_map, &ars_rules()), None);
+        assert!(rotate_cw(&piece, &position, &game_map, &RulesConfig::default()).is_some());
+    }
+}
@@ -0,0 +1,254 @@
+use crate::game_types::PieceType;
+use crate::garbage::GarbageStyle;
+use bevy::prelude::*;
+use std::time::Duration;
+
+// Lines-per-level values the main menu cycles through
+const LINES_PER_LEVEL_CHOICES: [u32; 4] = [5, 10, 15, 20];
+
+// Entry delays the main menu cycles through
+const ENTRY_DELAY_CHOICES: [Duration; 4] = [
+    Duration::ZERO,
+    Duration::from_millis(100),
+    Duration::from_millis(200),
+    Duration::from_millis(400),
+];
+
+// Pieces whose flat side faces up in the default spawn state and down in
+// state 2
+const FLAT_SIDED_PIECES: [PieceType; 3] = [PieceType::L, PieceType::J, PieceType::T];
+
+// Hold limits the main menu cycles through; None is unlimited
+const HOLDS_PER_PIECE_CHOICES: [Option<u32>; 3] = [Some(1), None, Some(0)];
+
+// Odds the main menu cycles the I piece through, as a weight next to the
+// default weight of every other piece
+const I_PIECE_ODDS: [(&str, u32); 4] = [
+    ("normal", DEFAULT_PIECE_WEIGHT),
+    ("rare", 1),
+    ("common", 12),
+    ("off", 0),
+];
+
+const DEFAULT_PIECE_WEIGHT: u32 = 4;
+
+// Relative chance of drawing each piece type, in the order of PieceType::ALL.
+// A zero weight leaves that piece out entirely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BagWeights([u32; 7]);
+
+impl Default for BagWeights {
+    fn default() -> Self {
+        BagWeights([DEFAULT_PIECE_WEIGHT; 7])
+    }
+}
+
+impl BagWeights {
+    pub fn weight(&self, piece_type: PieceType) -> u32 {
+        self.0[piece_type as usize]
+    }
+
+    // Sets one piece's weight; refused, returning false, when it would leave
+    // every weight at zero
+    pub fn set(&mut self, piece_type: PieceType, weight: u32) -> bool {
+        let mut weights = self.0;
+        weights[piece_type as usize] = weight;
+        if weights.iter().all(|&weight| weight == 0) {
+            return false;
+        }
+        self.0 = weights;
+        true
+    }
+
+    pub fn total(&self) -> u32 {
+        self.0.iter().sum()
+    }
+
+    // Maps a roll in 0..total() to the piece whose share of the range it hits
+    pub fn piece_for_roll(&self, roll: u32) -> PieceType {
+        let mut remaining = roll;
+        for (piece_type, &weight) in PieceType::ALL.iter().zip(self.0.iter()) {
+            if remaining < weight {
+                return *piece_type;
+            }
+            remaining -= weight;
+        }
+        debug_assert!(false, "roll {} is outside the weight total", roll);
+        PieceType::ALL[0]
+    }
+
+    pub fn i_piece_odds(&self) -> &'static str {
+        I_PIECE_ODDS
+            .iter()
+            .find(|(_, weight)| *weight == self.weight(PieceType::I))
+            .map_or("custom", |(name, _)| name)
+    }
+
+    // Steps the I piece to the next menu odds, wrapping around
+    pub fn cycle_i_piece_odds(&mut self) {
+        let next = I_PIECE_ODDS
+            .iter()
+            .position(|&(_, weight)| weight == self.weight(PieceType::I))
+            .map_or(0, |index| (index + 1) % I_PIECE_ODDS.len());
+        self.set(PieceType::I, I_PIECE_ODDS[next].1);
+    }
+}
+
+// Which rotation system decides spawn orientations and wall kicks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RotationSystem {
+    #[default]
+    Srs,
+    /// Arika Rotation System: flat-side-up spawns, simple one-column kicks.
+    Ars,
+}
+
+impl RotationSystem {
+    pub const ALL: [RotationSystem; 2] = [RotationSystem::Srs, RotationSystem::Ars];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RotationSystem::Srs => "SRS",
+            RotationSystem::Ars => "ARS",
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            RotationSystem::Srs => RotationSystem::Ars,
+            RotationSystem::Ars => RotationSystem::Srs,
+        }
+    }
+}
+
+// Gameplay rules that can be changed between runs
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RulesConfig {
+    /// When the spawn cell is blocked, nudge the new piece upward into the
+    /// hidden rows instead of ending the game right away.
+    pub spawn_push: bool,
+    /// Try SRS wall kicks when a rotation collides. Classic rules turn this
+    /// off so a rotation only succeeds if it fits in place.
+    pub use_wall_kicks: bool,
+    /// Lock the piece the moment a soft drop grounds it. Pieces landed by
+    /// gravity, and pieces that spawn already grounded, still wait out the
+    /// lock delay.
+    pub soft_drop_locks: bool,
+    /// Rotation states and kick tables used for every new piece.
+    pub rotation_system: RotationSystem,
+    /// Lines that must be cleared to advance one level.
+    pub lines_per_level: u32,
+    /// How many times faster than gravity the piece falls while soft drop
+    /// is held.
+    pub soft_drop_factor: u32,
+    /// Points for each row a held soft drop actually moves the piece down.
+    /// Frozen frames and rows the piece cannot fall score nothing.
+    pub soft_drop_points: u32,
+    /// Hold gravity for the frame soft drop is let go, so a late release
+    /// does not carry the piece one row further. Off resumes gravity at once.
+    pub soft_drop_release_grace: bool,
+    /// Give each new piece a random color from the seeded RNG instead of the
+    /// color of its type.
+    pub random_colors: bool,
+    /// Entry delay (ARE): pause between a piece locking and the next one
+    /// appearing. Zero spawns the next piece on the same frame.
+    pub entry_delay: Duration,
+    /// Hold gravity, input and the next spawn while cleared rows animate.
+    /// Off lets play carry on over the animation.
+    pub line_clear_pause: bool,
+    /// How often each piece type comes up from the seeded RNG.
+    pub piece_weights: BagWeights,
+    /// Holds allowed before the active piece locks; None allows any number,
+    /// so holding repeatedly cycles through pieces.
+    pub holds_per_piece: Option<u32>,
+    /// Rotation state each piece type spawns in, in the order of
+    /// PieceType::ALL.
+    pub spawn_states: [usize; 7],
+    /// How the holes line up in garbage rows that rise into the board.
+    pub garbage_style: GarbageStyle,
+    /// Let pieces rotate at all. The no rotation challenge turns this off so
+    /// every piece stays in its spawn orientation.
+    pub allow_rotation: bool,
+    /// Score a lock as a spin when any piece rotates into a spot it cannot
+    /// move out of in any direction, not only the T.
+    pub all_spin: bool,
+}
+
+impl Default for RulesConfig {
+    fn default() -> Self {
+        RulesConfig {
+            spawn_push: false,
+            use_wall_kicks: true,
+            soft_drop_locks: false,
+            rotation_system: RotationSystem::Srs,
+            lines_per_level: 10,
+            soft_drop_factor: 20,
+            soft_drop_points: 1,
+            soft_drop_release_grace: false,
+            random_colors: false,
+            entry_delay: Duration::ZERO,
+            line_clear_pause: true,
+            piece_weights: BagWeights::default(),
+            holds_per_piece: Some(1),
+            spawn_states: [0; 7],
+            garbage_style: GarbageStyle::Clean,
+            allow_rotation: true,
+            all_spin: false,
+        }
+    }
+}
+
+impl RulesConfig {
+    // Steps lines_per_level to the next menu choice, wrapping around
+    pub fn cycle_lines_per_level(&mut self) {
+        let next = LINES_PER_LEVEL_CHOICES
+            .iter()
+            .position(|&lines| lines == self.lines_per_level)
+            .map_or(0, |index| (index + 1) % LINES_PER_LEVEL_CHOICES.len());
+        self.lines_per_level = LINES_PER_LEVEL_CHOICES[next];
+    }
+
+    // Steps entry_delay to the next menu choice, wrapping around
+    pub fn cycle_entry_delay(&mut self) {
+        let next = ENTRY_DELAY_CHOICES
+            .iter()
+            .position(|&delay| delay == self.entry_delay)
+            .map_or(0, |index| (index + 1) % ENTRY_DELAY_CHOICES.len());
+        self.entry_delay = ENTRY_DELAY_CHOICES[next];
+    }
+
+    // Steps holds_per_piece to the next menu choice, wrapping around
+    pub fn cycle_holds_per_piece(&mut self) {
+        let next = HOLDS_PER_PIECE_CHOICES
+            .iter()
+            .position(|&holds| holds == self.holds_per_piece)
+            .map_or(0, |index| (index + 1) % HOLDS_PER_PIECE_CHOICES.len());
+        self.holds_per_piece = HOLDS_PER_PIECE_CHOICES[next];
+    }
+
+    pub fn holds_per_piece_name(&self) -> String {
+        match self.holds_per_piece {
+            None => "unlimited".to_string(),
+            Some(0) => "off".to_string(),
+            Some(limit) => limit.to_string(),
+        }
+    }
+
+    pub fn spawn_state(&self, piece_type: PieceType) -> usize {
+        self.spawn_states[piece_type as usize] % 4
+    }
+
+    pub fn flat_side_down_spawns(&self) -> bool {
+        FLAT_SIDED_PIECES
+            .iter()
+            .all(|&piece_type| self.spawn_state(piece_type) == 2)
+    }
+
+    // Switches L, J and T between spawning flat side up and flat side down
+    pub fn toggle_flat_side_down_spawns(&mut self) {
+        let state = if self.flat_side_down_spawns() { 0 } else { 2 };
+        for piece_type in FLAT_SIDED_PIECES {
+            self.spawn_states[piece_type as usize] = state;
+        }
+    }
+}
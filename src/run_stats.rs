@@ -0,0 +1,194 @@
+use crate::blitz::PiecesPlaced;
+use crate::event_log::EventLog;
+use crate::events::LinesCleared;
+use crate::garbage::GarbageChallenge;
+use crate::mode_summary::GameMode;
+use crate::practice::PracticeMode;
+use crate::puzzle::ActivePuzzle;
+use crate::survival::SurvivalMode;
+use crate::{Level, Score};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const RUN_STATS_FILE: &str = "run_stats.csv";
+const RUN_STATS_HEADER: &str = "recorded_at,mode,score,lines,level,seconds,pps";
+
+// Appends every finished run's stats to the CSV log when on
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RunStatsExport(pub bool);
+
+// Totals for the run in progress that no other resource keeps
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RunStats {
+    pub lines: u32,
+    /// Time spent in play; pauses and menus do not count.
+    pub play_time: Duration,
+}
+
+// One finished run, as a row of the CSV log
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunRecord {
+    /// Unix seconds when the run ended.
+    pub recorded_at: u64,
+    pub mode: GameMode,
+    pub score: u32,
+    pub lines: u32,
+    pub level: u32,
+    pub play_time: Duration,
+    pub pieces: u32,
+}
+
+impl RunRecord {
+    // Pieces placed per second of play
+    pub fn pps(&self) -> f32 {
+        let seconds = self.play_time.as_secs_f32();
+        if seconds > 0.0 {
+            self.pieces as f32 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    // Fields in the order of RUN_STATS_HEADER
+    pub fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{:.3},{:.3}",
+            self.recorded_at,
+            self.mode.name(),
+            self.score,
+            self.lines,
+            self.level,
+            self.play_time.as_secs_f32(),
+            self.pps()
+        )
+    }
+
+    // Appends the row to `path`, starting a new file with the header
+    pub fn append_to(&self, path: &str) -> std::io::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut text = String::new();
+        if file.metadata()?.len() == 0 {
+            text.push_str(RUN_STATS_HEADER);
+            text.push('\n');
+        }
+        text.push_str(&self.to_csv_row());
+        text.push('\n');
+        file.write_all(text.as_bytes())
+    }
+}
+
+pub fn track_run_stats(
+    time: Res<Time>,
+    mut cleared: EventReader<LinesCleared>,
+    mut stats: ResMut<RunStats>,
+) {
+    stats.play_time += time.delta();
+    for event in cleared.read() {
+        stats.lines += event.count;
+    }
+}
+
+// Everything a finished run's record is built from
+#[derive(SystemParam)]
+pub struct FinishedRun<'w> {
+    score: Res<'w, Score>,
+    level: Res<'w, Level>,
+    stats: Res<'w, RunStats>,
+    placed: Res<'w, PiecesPlaced>,
+    practice: Res<'w, PracticeMode>,
+    challenge: Res<'w, GarbageChallenge>,
+    survival: Res<'w, SurvivalMode>,
+    puzzle: Res<'w, ActivePuzzle>,
+}
+
+impl FinishedRun<'_> {
+    // The run as it stands, ended at unix second `recorded_at`
+    pub fn record(&self, recorded_at: u64) -> RunRecord {
+        RunRecord {
+            recorded_at,
+            mode: GameMode::current(
+                &self.practice,
+                &self.challenge,
+                &self.survival,
+                &self.puzzle,
+            ),
+            score: self.score.value,
+            lines: self.stats.lines,
+            level: self.level.value,
+            play_time: self.stats.play_time,
+            pieces: self.placed.0,
+        }
+    }
+}
+
+// Logs the run that just ended, when the export is on
+pub fn export_run_stats(export: Res<RunStatsExport>, run: FinishedRun, mut log: ResMut<EventLog>) {
+    if !export.0 {
+        return;
+    }
+    let record = run.record(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs()),
+    );
+    log.push(match record.append_to(RUN_STATS_FILE) {
+        Ok(()) => format!("Appended run stats to {}", RUN_STATS_FILE),
+        Err(err) => format!("Could not write run stats: {}", err),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use std::fs;
+
+    #[test]
+    fn finished_run_appends_a_row_under_the_header() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .insert_resource(Score { value: 1200 })
+            .insert_resource(Level {
+                value: 3,
+                ..Level::default()
+            })
+            .init_resource::<RunStats>()
+            .insert_resource(PiecesPlaced(25))
+            .insert_resource(SurvivalMode(true))
+            .init_resource::<PracticeMode>()
+            .init_resource::<GarbageChallenge>()
+            .init_resource::<ActivePuzzle>()
+            .add_event::<LinesCleared>()
+            .add_systems(Update, track_run_stats);
+        // Ten seconds of play with a double and a tetris
+        for count in [2, 0, 4, 0, 0, 0, 0, 0, 0, 0] {
+            if count > 0 {
+                app.world.send_event(LinesCleared { count });
+            }
+            app.world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs(1));
+            app.update();
+        }
+
+        let path = std::env::temp_dir().join("tetris_run_stats_test.csv");
+        let path = path.to_str().unwrap();
+        let _ = fs::remove_file(path);
+        let record = app
+            .world
+            .run_system_once(|run: FinishedRun| run.record(1_700_000_000));
+        record.append_to(path).unwrap();
+        record.append_to(path).unwrap();
+        let text = fs::read_to_string(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        let row = "1700000000,Survival,1200,6,3,10.000,2.500";
+        assert_eq!(
+            text.lines().collect::<Vec<_>>(),
+            [RUN_STATS_HEADER, row, row]
+        );
+    }
+}
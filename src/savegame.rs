@@ -0,0 +1,866 @@
+use crate::blitz::{PieceLimit, PiecesPlaced};
+use crate::components::{Piece, Position, VisualOffset};
+use crate::difficulty::Difficulty;
+use crate::event_log::EventLog;
+use crate::game_color::GameColor;
+use crate::game_types::{BoardConfig, GameMap, PieceType};
+use crate::garbage::{GarbageChallenge, GarbageStyle, PendingGarbage};
+use crate::hold::HoldSlot;
+use crate::menu::MainMenuUi;
+use crate::practice::PracticeMode;
+use crate::puzzle::ActivePuzzle;
+use crate::queue::PieceQueue;
+use crate::rhythm::{Metronome, RhythmMode, beat_duration};
+use crate::rng::GameRng;
+use crate::rules::{RotationSystem, RulesConfig};
+use crate::seed::SeedEntry;
+use crate::survival::{SurvivalClock, SurvivalMode};
+use crate::{AutoShift, EntryDelay, GameState, Level, LockDelay, Score};
+use bevy::app::AppExit;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::str::FromStr;
+use std::time::Duration;
+
+const SAVE_FILE: &str = "saved_game.txt";
+
+// Key that picks up the saved run from the main menu
+pub const CONTINUE_KEY: KeyCode = KeyCode::Enter;
+
+// A run left mid-game, with everything needed to carry on from the same
+// frame, including the difficulty and rules it was started with
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedGame {
+    pub board: BoardConfig,
+    pub map: GameMap,
+    pub score: u32,
+    pub level: Level,
+    /// The falling piece, or None when the save fell between pieces, during
+    /// the entry delay or a line clear animation.
+    pub active: Option<(Piece, Position)>,
+    /// Time left before the next piece spawns, when one is pending.
+    pub entry_delay: Option<Duration>,
+    pub upcoming: Vec<PieceType>,
+    pub hold: HoldSlot,
+    pub pending_garbage: u32,
+    pub elapsed: Duration,
+    pub survival: bool,
+    pub practice: bool,
+    pub challenge: bool,
+    pub puzzle: Option<usize>,
+    pub piece_limit: Option<u32>,
+    pub pieces_placed: u32,
+    /// Tempo of rhythm play, if on, with the time into the current beat and
+    /// the locks judged so far, all and on the beat.
+    pub rhythm: Option<u32>,
+    pub beat_elapsed: Duration,
+    pub beat_locks: (u32, u32),
+    pub seed: u64,
+    /// Checkpoint of the RNG stream, see GameRng::checkpoint.
+    pub stream: u64,
+    pub difficulty: Difficulty,
+    pub rules: RulesConfig,
+    /// Lock delay and the move resets allowed per piece.
+    pub lock_delay: (Duration, u32),
+    /// DAS and ARR.
+    pub auto_shift: (Duration, Duration),
+}
+
+impl SavedGame {
+    // One tab-separated key and value per line
+    pub fn serialize(&self) -> String {
+        let board = &self.board;
+        let rules = &self.rules;
+        let hold = match self.hold.piece_type {
+            Some(piece_type) => format!("{:?}", piece_type),
+            None => String::from("-"),
+        };
+        let (piece, position) = match &self.active {
+            Some((piece, position)) => (
+                format!(
+                    "{:?} {} {} {}",
+                    piece.piece_type,
+                    piece.color.code(),
+                    piece.current_state,
+                    piece.states.map(|state| state.to_string()).join(" ")
+                ),
+                format!("{} {}", position.x, position.y),
+            ),
+            None => (String::from("-"), String::from("-")),
+        };
+        let upcoming = self
+            .upcoming
+            .iter()
+            .map(|piece_type| format!("{:?}", piece_type))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let lines = [
+            format!(
+                "board\t{} {} {} {} {} {}",
+                board.width,
+                board.height,
+                board.visible_height,
+                board.cell_size,
+                board.mirrored,
+                board.reversed_gravity
+            ),
+            format!("map\t{}", self.map.to_compact_string()),
+            format!("wrap_edges\t{}", self.map.wrap_edges),
+            format!("score\t{}", self.score),
+            format!(
+                "level\t{} {}",
+                self.level.value, self.level.lines_cleared_in_level
+            ),
+            format!("piece\t{}", piece),
+            format!("position\t{}", position),
+            format!(
+                "entry_delay_ms\t{}",
+                optional(self.entry_delay.map(|delay| delay.as_millis()))
+            ),
+            format!("upcoming\t{}", upcoming),
+            format!("hold\t{} {}", hold, self.hold.holds_used),
+            format!("garbage\t{}", self.pending_garbage),
+            format!("elapsed_ms\t{}", self.elapsed.as_millis()),
+            format!(
+                "modes\t{} {} {}",
+                self.survival, self.practice, self.challenge
+            ),
+            format!("puzzle\t{}", optional(self.puzzle)),
+            format!(
+                "blitz\t{} {}",
+                optional(self.piece_limit),
+                self.pieces_placed
+            ),
+            format!(
+                "rhythm\t{} {} {} {}",
+                optional(self.rhythm),
+                self.beat_elapsed.as_millis(),
+                self.beat_locks.0,
+                self.beat_locks.1
+            ),
+            format!("rng\t{} {}", self.seed, self.stream),
+            format!("difficulty\t{}", self.difficulty.name()),
+            format!(
+                "rotation_rules\t{} {} {} {}",
+                rules.rotation_system.name(),
+                rules.use_wall_kicks,
+                rules.allow_rotation,
+                rules.all_spin
+            ),
+            format!(
+                "soft_drop_rules\t{} {} {} {}",
+                rules.soft_drop_locks,
+                rules.soft_drop_factor,
+                rules.soft_drop_points,
+                rules.soft_drop_release_grace
+            ),
+            format!(
+                "spawn_rules\t{} {} {} {}",
+                rules.spawn_push,
+                rules.entry_delay.as_millis(),
+                rules.random_colors,
+                optional(rules.holds_per_piece)
+            ),
+            format!("spawn_states\t{}", words_of(rules.spawn_states)),
+            format!(
+                "piece_weights\t{}",
+                words_of(PieceType::ALL.map(|piece_type| rules.piece_weights.weight(piece_type)))
+            ),
+            format!(
+                "clear_rules\t{} {} {}",
+                rules.lines_per_level,
+                rules.line_clear_pause,
+                rules.garbage_style.name()
+            ),
+            format!(
+                "lock_delay\t{} {}",
+                self.lock_delay.0.as_millis(),
+                self.lock_delay.1
+            ),
+            format!(
+                "auto_shift\t{} {}",
+                self.auto_shift.0.as_millis(),
+                self.auto_shift.1.as_millis()
+            ),
+        ];
+        lines.map(|line| line + "\n").concat()
+    }
+
+    // Reads a save written by `serialize`; None if any field is missing or
+    // malformed
+    pub fn parse(text: &str) -> Option<SavedGame> {
+        let fields: HashMap<&str, &str> = text
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .collect();
+        let words = |key: &str| -> Option<Vec<&str>> {
+            Some(fields.get(key)?.split_whitespace().collect())
+        };
+
+        let board = match words("board")?[..] {
+            [width, height, visible, cell_size, mirrored, reversed] => BoardConfig {
+                width: width.parse().ok()?,
+                height: height.parse().ok()?,
+                visible_height: visible.parse().ok()?,
+                scroll_row: 0,
+                cell_size: cell_size.parse().ok()?,
+                mirrored: mirrored.parse().ok()?,
+                reversed_gravity: reversed.parse().ok()?,
+            },
+            _ => return None,
+        };
+        let mut map = GameMap::from_compact_string(fields.get("map")?)?;
+        map.wrap_edges = fields.get("wrap_edges")?.parse().ok()?;
+        let level = match words("level")?[..] {
+            [value, lines] => Level {
+                value: value.parse().ok()?,
+                lines_cleared_in_level: lines.parse().ok()?,
+            },
+            _ => return None,
+        };
+        let active = match (&words("piece")?[..], &words("position")?[..]) {
+            (["-"], ["-"]) => None,
+            ([piece_type, color, current_state, s0, s1, s2, s3], [x, y]) => Some((
+                Piece {
+                    piece_type: piece_type_from_name(piece_type)?,
+                    color: GameColor::from_code(color.chars().next()?)?,
+                    current_state: current_state.parse().ok()?,
+                    states: [
+                        s0.parse().ok()?,
+                        s1.parse().ok()?,
+                        s2.parse().ok()?,
+                        s3.parse().ok()?,
+                    ],
+                },
+                Position {
+                    x: x.parse().ok()?,
+                    y: y.parse().ok()?,
+                },
+            )),
+            _ => return None,
+        };
+        let entry_delay =
+            parse_optional::<u64>(fields.get("entry_delay_ms")?)?.map(Duration::from_millis);
+        let upcoming = words("upcoming")?
+            .into_iter()
+            .map(piece_type_from_name)
+            .collect::<Option<Vec<_>>>()?;
+        let hold = match words("hold")?[..] {
+            [piece_type, holds_used] => HoldSlot {
+                piece_type: match piece_type {
+                    "-" => None,
+                    name => Some(piece_type_from_name(name)?),
+                },
+                holds_used: holds_used.parse().ok()?,
+            },
+            _ => return None,
+        };
+        let (survival, practice, challenge) = match words("modes")?[..] {
+            [survival, practice, challenge] => (
+                survival.parse().ok()?,
+                practice.parse().ok()?,
+                challenge.parse().ok()?,
+            ),
+            _ => return None,
+        };
+        let (piece_limit, pieces_placed) = match words("blitz")?[..] {
+            [limit, placed] => (parse_optional(limit)?, placed.parse().ok()?),
+            _ => return None,
+        };
+        let (rhythm, beat_elapsed, beat_locks) = match words("rhythm")?[..] {
+            [bpm, elapsed_ms, locks, on_beat_locks] => (
+                parse_optional(bpm)?,
+                Duration::from_millis(elapsed_ms.parse().ok()?),
+                (locks.parse().ok()?, on_beat_locks.parse().ok()?),
+            ),
+            _ => return None,
+        };
+        let (seed, stream) = match words("rng")?[..] {
+            [seed, stream] => (seed.parse().ok()?, stream.parse().ok()?),
+            _ => return None,
+        };
+        let difficulty_name = *fields.get("difficulty")?;
+        let difficulty = Difficulty::ALL
+            .into_iter()
+            .find(|difficulty| difficulty.name() == difficulty_name)?;
+        let lock_delay = match words("lock_delay")?[..] {
+            [millis, max_resets] => (
+                Duration::from_millis(millis.parse().ok()?),
+                max_resets.parse().ok()?,
+            ),
+            _ => return None,
+        };
+        let auto_shift = match words("auto_shift")?[..] {
+            [das, arr] => (
+                Duration::from_millis(das.parse().ok()?),
+                Duration::from_millis(arr.parse().ok()?),
+            ),
+            _ => return None,
+        };
+
+        if map.width() != board.width || map.height() != board.height {
+            return None;
+        }
+        Some(SavedGame {
+            board,
+            map,
+            score: fields.get("score")?.parse().ok()?,
+            level,
+            active,
+            entry_delay,
+            upcoming,
+            hold,
+            pending_garbage: fields.get("garbage")?.parse().ok()?,
+            elapsed: Duration::from_millis(fields.get("elapsed_ms")?.parse().ok()?),
+            survival,
+            practice,
+            challenge,
+            puzzle: parse_optional(fields.get("puzzle")?)?,
+            piece_limit,
+            pieces_placed,
+            rhythm,
+            beat_elapsed,
+            beat_locks,
+            seed,
+            stream,
+            difficulty,
+            rules: parse_rules(&fields)?,
+            lock_delay,
+            auto_shift,
+        })
+    }
+}
+
+// Reads the rules lines of a save
+fn parse_rules(fields: &HashMap<&str, &str>) -> Option<RulesConfig> {
+    let words =
+        |key: &str| -> Option<Vec<&str>> { Some(fields.get(key)?.split_whitespace().collect()) };
+    let mut rules = RulesConfig::default();
+    match words("rotation_rules")?[..] {
+        [system, wall_kicks, allow_rotation, all_spin] => {
+            rules.rotation_system = RotationSystem::ALL
+                .into_iter()
+                .find(|rotation_system| rotation_system.name() == system)?;
+            rules.use_wall_kicks = wall_kicks.parse().ok()?;
+            rules.allow_rotation = allow_rotation.parse().ok()?;
+            rules.all_spin = all_spin.parse().ok()?;
+        }
+        _ => return None,
+    }
+    match words("soft_drop_rules")?[..] {
+        [locks, factor, points, release_grace] => {
+            rules.soft_drop_locks = locks.parse().ok()?;
+            rules.soft_drop_factor = factor.parse().ok()?;
+            rules.soft_drop_points = points.parse().ok()?;
+            rules.soft_drop_release_grace = release_grace.parse().ok()?;
+        }
+        _ => return None,
+    }
+    match words("spawn_rules")?[..] {
+        [spawn_push, entry_delay_ms, random_colors, holds_per_piece] => {
+            rules.spawn_push = spawn_push.parse().ok()?;
+            rules.entry_delay = Duration::from_millis(entry_delay_ms.parse().ok()?);
+            rules.random_colors = random_colors.parse().ok()?;
+            rules.holds_per_piece = parse_optional(holds_per_piece)?;
+        }
+        _ => return None,
+    }
+    rules.spawn_states = parse_words(&words("spawn_states")?)?;
+    let weights: [u32; 7] = parse_words(&words("piece_weights")?)?;
+    for (piece_type, weight) in PieceType::ALL.into_iter().zip(weights) {
+        // Weights that are all zero are refused, as in the menu
+        if !rules.piece_weights.set(piece_type, weight) {
+            return None;
+        }
+    }
+    match words("clear_rules")?[..] {
+        [lines_per_level, line_clear_pause, garbage_style] => {
+            rules.lines_per_level = lines_per_level.parse().ok()?;
+            rules.line_clear_pause = line_clear_pause.parse().ok()?;
+            rules.garbage_style = GarbageStyle::ALL
+                .into_iter()
+                .find(|style| style.name() == garbage_style)?;
+        }
+        _ => return None,
+    }
+    Some(rules)
+}
+
+// Values saved on one line, separated by spaces
+fn words_of<T: ToString>(values: [T; 7]) -> String {
+    values.map(|value| value.to_string()).join(" ")
+}
+
+// Reads the seven values written by `words_of`; None if any is malformed
+fn parse_words<T: FromStr + Copy + Default>(words: &[&str]) -> Option<[T; 7]> {
+    let mut values = [T::default(); 7];
+    if words.len() != values.len() {
+        return None;
+    }
+    for (value, word) in values.iter_mut().zip(words) {
+        *value = word.parse().ok()?;
+    }
+    Some(values)
+}
+
+// A value as saved, or "-" for None
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| String::from("-"), |value| value.to_string())
+}
+
+// Reads a value written by `optional`; None if it is malformed
+fn parse_optional<T: FromStr>(word: &str) -> Option<Option<T>> {
+    match word {
+        "-" => Some(None),
+        word => word.parse().ok().map(Some),
+    }
+}
+
+fn piece_type_from_name(name: &str) -> Option<PieceType> {
+    PieceType::ALL
+        .into_iter()
+        .find(|piece_type| format!("{:?}", piece_type) == name)
+}
+
+// The save found at startup, offered from the main menu until it is used
+#[derive(Resource, Debug, Clone, PartialEq, Default)]
+pub struct ResumableGame(pub Option<SavedGame>);
+
+// Every resource a save captures and a resume puts back
+#[derive(SystemParam)]
+pub struct SavedRun<'w> {
+    board: ResMut<'w, BoardConfig>,
+    map: ResMut<'w, GameMap>,
+    score: ResMut<'w, Score>,
+    level: ResMut<'w, Level>,
+    entry_delay: ResMut<'w, EntryDelay>,
+    queue: ResMut<'w, PieceQueue>,
+    hold: ResMut<'w, HoldSlot>,
+    pending_garbage: ResMut<'w, PendingGarbage>,
+    clock: ResMut<'w, SurvivalClock>,
+    rng: ResMut<'w, GameRng>,
+    modes: SavedModes<'w>,
+    settings: SavedSettings<'w>,
+}
+
+// The difficulty, rules and handling timings the run was started with
+#[derive(SystemParam)]
+pub struct SavedSettings<'w> {
+    difficulty: ResMut<'w, Difficulty>,
+    rules: ResMut<'w, RulesConfig>,
+    lock_delay: ResMut<'w, LockDelay>,
+    auto_shift: ResMut<'w, AutoShift>,
+}
+
+// The mode settings and mode progress a save carries
+#[derive(SystemParam)]
+pub struct SavedModes<'w> {
+    survival: ResMut<'w, SurvivalMode>,
+    practice: ResMut<'w, PracticeMode>,
+    challenge: ResMut<'w, GarbageChallenge>,
+    puzzle: ResMut<'w, ActivePuzzle>,
+    piece_limit: ResMut<'w, PieceLimit>,
+    pieces_placed: ResMut<'w, PiecesPlaced>,
+    rhythm: ResMut<'w, RhythmMode>,
+    metronome: ResMut<'w, Metronome>,
+}
+
+impl SavedRun<'_> {
+    // Captures the run with the active piece, if any; checkpoints the RNG so
+    // the run and its save draw the same pieces from here on
+    fn snapshot(&mut self, active: Option<(Piece, Position)>) -> SavedGame {
+        let modes = &self.modes;
+        let settings = &self.settings;
+        // Between pieces a spawn is always due, even if no delay is running
+        let entry_delay = match active {
+            Some(_) => self.entry_delay.remaining(),
+            None => Some(self.entry_delay.remaining().unwrap_or_default()),
+        };
+        SavedGame {
+            board: *self.board,
+            map: self.map.clone(),
+            score: self.score.value,
+            level: self.level.clone(),
+            active,
+            entry_delay,
+            upcoming: self.queue.upcoming(),
+            hold: *self.hold,
+            pending_garbage: self.pending_garbage.rows,
+            elapsed: self.clock.elapsed,
+            survival: modes.survival.0,
+            practice: modes.practice.0,
+            challenge: modes.challenge.0,
+            puzzle: modes.puzzle.0,
+            piece_limit: modes.piece_limit.0,
+            pieces_placed: modes.pieces_placed.0,
+            rhythm: modes.rhythm.0,
+            beat_elapsed: modes
+                .metronome
+                .timer
+                .as_ref()
+                .map_or(Duration::ZERO, Timer::elapsed),
+            beat_locks: (modes.metronome.locks, modes.metronome.on_beat_locks),
+            seed: self.rng.seed(),
+            stream: self.rng.checkpoint(),
+            difficulty: *settings.difficulty,
+            rules: *settings.rules,
+            lock_delay: (
+                settings.lock_delay.timer.duration(),
+                settings.lock_delay.max_resets,
+            ),
+            auto_shift: (settings.auto_shift.das, settings.auto_shift.arr),
+        }
+    }
+
+    fn restore(&mut self, saved: &SavedGame) {
+        *self.board = saved.board;
+        *self.map = saved.map.clone();
+        self.score.value = saved.score;
+        *self.level = saved.level.clone();
+        match saved.entry_delay {
+            Some(remaining) => self.entry_delay.start(remaining),
+            None => self.entry_delay.cancel(),
+        }
+        self.queue.set_upcoming(&saved.upcoming);
+        *self.hold = saved.hold;
+        self.pending_garbage.rows = saved.pending_garbage;
+        self.clock.elapsed = saved.elapsed;
+        *self.rng = GameRng::resume(saved.seed, saved.stream);
+
+        let modes = &mut self.modes;
+        modes.survival.0 = saved.survival;
+        modes.practice.0 = saved.practice;
+        modes.challenge.0 = saved.challenge;
+        modes.puzzle.0 = saved.puzzle;
+        modes.piece_limit.0 = saved.piece_limit;
+        modes.pieces_placed.0 = saved.pieces_placed;
+        modes.rhythm.0 = saved.rhythm;
+        *modes.metronome = Metronome {
+            timer: saved.rhythm.map(|bpm| {
+                let mut timer = Timer::new(beat_duration(bpm), TimerMode::Repeating);
+                timer.set_elapsed(saved.beat_elapsed);
+                timer
+            }),
+            locks: saved.beat_locks.0,
+            on_beat_locks: saved.beat_locks.1,
+        };
+
+        let settings = &mut self.settings;
+        *settings.difficulty = saved.difficulty;
+        *settings.rules = saved.rules;
+        settings.lock_delay.set_duration(saved.lock_delay.0);
+        settings.lock_delay.max_resets = saved.lock_delay.1;
+        settings.lock_delay.reset();
+        settings.auto_shift.das = saved.auto_shift.0;
+        settings.auto_shift.arr = saved.auto_shift.1;
+    }
+}
+
+pub fn load_saved_game(mut resumable: ResMut<ResumableGame>, mut log: ResMut<EventLog>) {
+    let Ok(text) = fs::read_to_string(SAVE_FILE) else {
+        return;
+    };
+    resumable.0 = SavedGame::parse(&text);
+    if resumable.0.is_none() {
+        log.push(format!("Ignoring unreadable {}", SAVE_FILE));
+    }
+}
+
+// Saves the run in progress, paused or not, when the app is closed mid-game,
+// including between pieces
+pub fn save_game_on_exit(
+    mut exit: EventReader<AppExit>,
+    game_state: Res<State<GameState>>,
+    mut run: SavedRun,
+    query_piece: Query<(Entity, &Piece, &Position)>,
+    mut log: ResMut<EventLog>,
+) {
+    let in_run = matches!(game_state.get(), GameState::Playing | GameState::Paused);
+    if exit.read().next().is_none() || !in_run {
+        return;
+    }
+    write_save(&mut run, &query_piece, &mut log);
+}
+
+// Saves the run each time it is paused, so it can still be continued if the
+// game is not closed cleanly afterwards
+pub fn save_game_on_pause(
+    mut run: SavedRun,
+    query_piece: Query<(Entity, &Piece, &Position)>,
+    mut log: ResMut<EventLog>,
+) {
+    write_save(&mut run, &query_piece, &mut log);
+}
+
+// A run that ended leaves nothing to continue, even if it was saved while
+// paused along the way
+pub fn discard_saved_game() {
+    let _ = fs::remove_file(SAVE_FILE);
+}
+
+fn write_save(
+    run: &mut SavedRun,
+    query_piece: &Query<(Entity, &Piece, &Position)>,
+    log: &mut EventLog,
+) {
+    // Like despawn_extra_pieces, a stray extra piece gives way to the newest
+    let active = query_piece
+        .iter()
+        .max_by_key(|(entity, _, _)| *entity)
+        .map(|(_, piece, position)| (*piece, *position));
+    let saved = run.snapshot(active);
+    log.push(match fs::write(SAVE_FILE, saved.serialize()) {
+        Ok(()) => format!("Saved game to {}", SAVE_FILE),
+        Err(err) => format!("Could not save game: {}", err),
+    });
+}
+
+// Picks the saved run back up from the main menu. The save is used up, so
+// pausing or closing mid-game again is what writes a new one.
+#[allow(clippy::too_many_arguments)]
+pub fn continue_saved_game(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    seed_entry: Res<SeedEntry>,
+    mut resumable: ResMut<ResumableGame>,
+    mut run: SavedRun,
+    mut game_state: ResMut<NextState<GameState>>,
+    query_menu: Query<Entity, With<MainMenuUi>>,
+    mut log: ResMut<EventLog>,
+) {
+    // Enter also finishes typing a seed
+    if seed_entry.editing || !keyboard_input.just_pressed(CONTINUE_KEY) {
+        return;
+    }
+    let Some(saved) = resumable.0.take() else {
+        return;
+    };
+    run.restore(&saved);
+    if let Err(err) = fs::remove_file(SAVE_FILE) {
+        log.push(format!("Could not remove {}: {}", SAVE_FILE, err));
+    }
+    log.push(format!("Continuing saved game with seed {}", saved.seed));
+
+    for entity in query_menu.iter() {
+        commands.entity(entity).despawn();
+    }
+    // Without a piece the restored entry delay spawns the next one
+    if let Some((piece, position)) = saved.active {
+        commands.spawn((piece, position, VisualOffset::new(position)));
+    }
+    game_state.set(GameState::Playing);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_types::Presence;
+    use crate::rules::BagWeights;
+    use bevy::ecs::system::RunSystemOnce;
+
+    // Every resource a save touches, at its defaults
+    fn run_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<BoardConfig>()
+            .init_resource::<GameMap>()
+            .init_resource::<Score>()
+            .init_resource::<Level>()
+            .init_resource::<EntryDelay>()
+            .init_resource::<PieceQueue>()
+            .init_resource::<HoldSlot>()
+            .init_resource::<PendingGarbage>()
+            .init_resource::<SurvivalClock>()
+            .init_resource::<GameRng>()
+            .init_resource::<SurvivalMode>()
+            .init_resource::<PracticeMode>()
+            .init_resource::<GarbageChallenge>()
+            .init_resource::<ActivePuzzle>()
+            .init_resource::<PieceLimit>()
+            .init_resource::<PiecesPlaced>()
+            .init_resource::<RhythmMode>()
+            .init_resource::<Metronome>()
+            .init_resource::<Difficulty>()
+            .init_resource::<RulesConfig>()
+            .init_resource::<LockDelay>()
+            .init_resource::<AutoShift>();
+        app
+    }
+
+    // Saves `app` as the game does on exit, then loads the written text into
+    // a fresh run
+    fn save_and_resume(app: &mut App, active: Option<(Piece, Position)>) -> (SavedGame, App) {
+        let saved = app
+            .world
+            .run_system_once(move |mut run: SavedRun| run.snapshot(active));
+        let loaded = SavedGame::parse(&saved.serialize()).unwrap();
+        assert_eq!(loaded, saved);
+
+        let mut resumed = run_app();
+        resumed
+            .world
+            .run_system_once(move |mut run: SavedRun| run.restore(&loaded));
+        (saved, resumed)
+    }
+
+    // The next piece the run's queue hands out
+    fn next_piece(app: &mut App) -> PieceType {
+        app.world.resource_scope(|world, mut rng: Mut<GameRng>| {
+            world
+                .resource_mut::<PieceQueue>()
+                .next(&mut rng, &BagWeights::default())
+        })
+    }
+
+    #[test]
+    fn save_between_pieces_round_trips_the_whole_run() {
+        let mut app = run_app();
+        let mut game_map = GameMap::default();
+        let bottom = game_map.height() as isize - 1;
+        game_map.set_cell(2, bottom, Presence::Yes(GameColor::Blue));
+        app.insert_resource(game_map)
+            .insert_resource(Score { value: 1234 })
+            .insert_resource(Level {
+                value: 4,
+                lines_cleared_in_level: 3,
+            })
+            .insert_resource(HoldSlot {
+                piece_type: Some(PieceType::S),
+                holds_used: 1,
+            })
+            .insert_resource(PendingGarbage { rows: 2 })
+            .insert_resource(SurvivalClock {
+                elapsed: Duration::from_millis(61_500),
+            })
+            .insert_resource(GameRng::new(99))
+            .insert_resource(ActivePuzzle(Some(1)))
+            .insert_resource(PieceLimit(Some(25)))
+            .insert_resource(PiecesPlaced(7))
+            .insert_resource(RhythmMode(Some(120)));
+        app.world.resource_mut::<PieceQueue>().set_upcoming(&[
+            PieceType::I,
+            PieceType::T,
+            PieceType::O,
+        ]);
+        app.world
+            .resource_mut::<EntryDelay>()
+            .start(Duration::from_millis(200));
+        let mut beat = Timer::new(beat_duration(120), TimerMode::Repeating);
+        beat.set_elapsed(Duration::from_millis(150));
+        app.insert_resource(Metronome {
+            timer: Some(beat),
+            locks: 5,
+            on_beat_locks: 3,
+        });
+        let mut rules = RulesConfig {
+            rotation_system: RotationSystem::Ars,
+            soft_drop_factor: 40,
+            entry_delay: Duration::from_millis(300),
+            holds_per_piece: None,
+            spawn_states: [1, 0, 2, 0, 3, 0, 1],
+            garbage_style: GarbageStyle::Cheese,
+            all_spin: true,
+            ..RulesConfig::default()
+        };
+        rules.piece_weights.set(PieceType::I, 8);
+        let mut lock_delay = LockDelay::default();
+        lock_delay.set_duration(Duration::from_millis(250));
+        app.insert_resource(Difficulty::Hard)
+            .insert_resource(rules)
+            .insert_resource(lock_delay)
+            .insert_resource(AutoShift {
+                das: Duration::from_millis(117),
+                arr: Duration::from_millis(17),
+                ..AutoShift::default()
+            });
+
+        // Saved during the entry delay, with no piece on the board
+        let (saved, mut resumed) = save_and_resume(&mut app, None);
+        assert_eq!(saved.active, None);
+        assert_eq!(saved.entry_delay, Some(Duration::from_millis(200)));
+
+        let world = &resumed.world;
+        assert_eq!(world.resource::<GameMap>(), app.world.resource::<GameMap>());
+        assert_eq!(world.resource::<Score>().value, 1234);
+        assert_eq!(
+            *world.resource::<Level>(),
+            Level {
+                value: 4,
+                lines_cleared_in_level: 3
+            }
+        );
+        assert_eq!(
+            world.resource::<EntryDelay>().remaining(),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(
+            world.resource::<HoldSlot>(),
+            app.world.resource::<HoldSlot>()
+        );
+        assert_eq!(world.resource::<PendingGarbage>().rows, 2);
+        assert_eq!(
+            world.resource::<SurvivalClock>().elapsed,
+            Duration::from_millis(61_500)
+        );
+        assert_eq!(world.resource::<ActivePuzzle>().0, Some(1));
+        assert_eq!(world.resource::<PieceLimit>().0, Some(25));
+        assert_eq!(world.resource::<PiecesPlaced>().0, 7);
+        assert_eq!(world.resource::<RhythmMode>().0, Some(120));
+        let metronome = world.resource::<Metronome>();
+        assert_eq!((metronome.locks, metronome.on_beat_locks), (5, 3));
+        assert_eq!(
+            metronome.timer.as_ref().map(Timer::elapsed),
+            Some(Duration::from_millis(150))
+        );
+
+        assert_eq!(*world.resource::<Difficulty>(), Difficulty::Hard);
+        assert_eq!(*world.resource::<RulesConfig>(), rules);
+        assert_eq!(
+            world.resource::<LockDelay>().timer.duration(),
+            Duration::from_millis(250)
+        );
+        let auto_shift = world.resource::<AutoShift>();
+        assert_eq!(
+            (auto_shift.das, auto_shift.arr),
+            (Duration::from_millis(117), Duration::from_millis(17))
+        );
+
+        // The queue and the RNG carry on with the same pieces
+        for _ in 0..10 {
+            assert_eq!(next_piece(&mut resumed), next_piece(&mut app));
+        }
+    }
+
+    #[test]
+    fn save_with_a_falling_piece_keeps_its_state_and_spot() {
+        let mut app = run_app();
+        let mut piece = Piece::new(PieceType::T);
+        piece.current_state = 3;
+        let position = Position { x: 5, y: 7 };
+
+        let (saved, resumed) = save_and_resume(&mut app, Some((piece, position)));
+        assert_eq!(saved.active, Some((piece, position)));
+        assert_eq!(saved.entry_delay, None);
+        assert_eq!(resumed.world.resource::<EntryDelay>().remaining(), None);
+    }
+
+    #[test]
+    fn pausing_writes_a_save_and_game_over_discards_it() {
+        let mut app = run_app();
+        app.init_resource::<EventLog>()
+            .insert_resource(Score { value: 321 })
+            .init_state::<GameState>()
+            .insert_resource(NextState(Some(GameState::Paused)))
+            .add_systems(OnEnter(GameState::Paused), save_game_on_pause)
+            .add_systems(OnEnter(GameState::GameOver), discard_saved_game);
+        app.update();
+
+        let text = fs::read_to_string(SAVE_FILE).unwrap();
+        assert_eq!(SavedGame::parse(&text).unwrap().score, 321);
+
+        app.insert_resource(NextState(Some(GameState::GameOver)));
+        app.update();
+        assert!(fs::read_to_string(SAVE_FILE).is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use crate::event_log::EventLog;
+use crate::game_constants::TEXTURE_SIZE;
+use crate::game_types::{GameMap, Presence};
+use crate::theme::ColorTheme;
+use bevy::prelude::*;
+use image::{ImageFormat, Rgba, RgbaImage};
+use std::fs;
+use std::io::Cursor;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Background of empty cells in an exported board
+const EMPTY_CELL: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+// Renders the board as a PNG with `cell_size` pixels per cell in the colors
+// of `theme`, without needing a window or the GPU
+pub fn render_board_png(
+    game_map: &GameMap,
+    theme: &ColorTheme,
+    cell_size: u32,
+) -> Result<Vec<u8>, image::ImageError> {
+    let width = game_map.width() as u32 * cell_size;
+    let height = game_map.height() as u32 * cell_size;
+    let image = RgbaImage::from_fn(width, height, |px, py| {
+        let cell = game_map.cell((px / cell_size) as isize, (py / cell_size) as isize);
+        match cell {
+            Some(Presence::Yes(color)) => Rgba(theme.color(color).as_rgba_u8()),
+            _ => EMPTY_CELL,
+        }
+    });
+
+    let mut bytes = Vec::new();
+    image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+    Ok(bytes)
+}
+
+// Saves the final board to a timestamped PNG when F12 is pressed on the game
+// over screen
+pub fn export_board_screenshot(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    game_map: Res<GameMap>,
+    theme: Res<ColorTheme>,
+    mut log: ResMut<EventLog>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F12) {
+        return;
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let path = format!("tetris_{}.png", timestamp);
+    let written = render_board_png(&game_map, &theme, TEXTURE_SIZE)
+        .map_err(|err| err.to_string())
+        .and_then(|bytes| fs::write(&path, bytes).map_err(|err| err.to_string()));
+    log.push(match written {
+        Ok(()) => format!("Saved board screenshot to {}", path),
+        Err(err) => format!("Could not save board screenshot: {}", err),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_color::GameColor;
+
+    #[test]
+    fn exported_png_has_one_block_of_pixels_per_cell() {
+        let game_map = GameMap::from_compact_string("..../..../r...").unwrap();
+        let theme = ColorTheme::default();
+        let bytes = render_board_png(&game_map, &theme, 4).unwrap();
+
+        let image = image::load_from_memory_with_format(&bytes, ImageFormat::Png)
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(image.dimensions(), (16, 12));
+        let red = Rgba(theme.color(GameColor::Red).as_rgba_u8());
+        assert_eq!(*image.get_pixel(0, 8), red);
+        assert_eq!(*image.get_pixel(3, 11), red);
+        assert_eq!(*image.get_pixel(4, 11), EMPTY_CELL);
+        assert_eq!(*image.get_pixel(0, 7), EMPTY_CELL);
+    }
+}
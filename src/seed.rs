@@ -0,0 +1,187 @@
+use crate::event_log::EventLog;
+use crate::game_constants::FONT_SIZE_SMALL;
+use crate::rng::GameRng;
+use arboard::Clipboard;
+use bevy::prelude::*;
+
+// Seed typed or pasted in the main menu for the next game
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Default)]
+pub struct SeedEntry {
+    pub editing: bool,
+    pub text: String,
+}
+
+impl SeedEntry {
+    // The entered seed, or None to start with a fresh random one
+    pub fn seed(&self) -> Option<u64> {
+        self.text.parse().ok()
+    }
+
+    pub fn label(&self) -> String {
+        match (self.editing, self.text.is_empty()) {
+            (true, _) => format!("{}_", self.text),
+            (false, true) => "random".to_string(),
+            (false, false) => self.text.clone(),
+        }
+    }
+
+    // Handles typing while the seed field is focused; Enter or Escape leaves it
+    pub fn edit(&mut self, keyboard_input: &ButtonInput<KeyCode>) {
+        let paste = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+            && keyboard_input.just_pressed(KeyCode::KeyV);
+        if paste {
+            // Without clipboard text the entry is left as it was
+            if let Ok(text) = Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+                self.push_digits(&text);
+            }
+        }
+
+        for key in keyboard_input.get_just_pressed() {
+            match key {
+                KeyCode::Backspace => {
+                    self.text.pop();
+                }
+                KeyCode::Enter | KeyCode::Escape => self.editing = false,
+                _ => {
+                    if let Some(digit) = digit_for_key(*key) {
+                        self.push_digits(&digit.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    // Appends digits, ignoring anything that would overflow a u64 seed
+    fn push_digits(&mut self, text: &str) {
+        for digit in text.chars().filter(char::is_ascii_digit) {
+            self.text.push(digit);
+            if self.seed().is_none() {
+                self.text.pop();
+                break;
+            }
+        }
+    }
+}
+
+fn digit_for_key(key: KeyCode) -> Option<u32> {
+    let digits = [
+        KeyCode::Digit0,
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+    digits.iter().position(|k| *k == key).map(|d| d as u32)
+}
+
+// Marker component for the seed readout
+#[derive(Component)]
+pub struct SeedDisplay;
+
+pub fn setup_seed_display(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: FONT_SIZE_SMALL,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        SeedDisplay,
+    ));
+}
+
+pub fn update_seed_display(rng: Res<GameRng>, mut query_text: Query<&mut Text, With<SeedDisplay>>) {
+    if rng.is_changed()
+        && let Some(mut text) = query_text.iter_mut().next()
+    {
+        text.sections[0].value = format!("Seed: {} (F5 copy)", rng.seed());
+    }
+}
+
+// Copies the current seed to the clipboard. The clipboard handle is kept
+// alive so the copied text stays available on platforms that require it.
+pub fn copy_seed(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    rng: Res<GameRng>,
+    mut clipboard: Local<Option<Clipboard>>,
+    mut log: ResMut<EventLog>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F5) {
+        return;
+    }
+
+    if clipboard.is_none() {
+        match Clipboard::new() {
+            Ok(handle) => *clipboard = Some(handle),
+            Err(err) => {
+                log.push(format!(
+                    "Clipboard unavailable, seed is {}: {}",
+                    rng.seed(),
+                    err
+                ));
+                return;
+            }
+        }
+    }
+    if let Some(handle) = clipboard.as_mut() {
+        log.push(match handle.set_text(rng.seed().to_string()) {
+            Ok(()) => format!("Copied seed {} to clipboard", rng.seed()),
+            Err(err) => format!("Could not copy seed {}: {}", rng.seed(), err),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::BagWeights;
+
+    #[test]
+    fn known_seed_is_displayed() {
+        let mut app = App::new();
+        app.insert_resource(GameRng::new(1234))
+            .add_systems(Startup, setup_seed_display)
+            .add_systems(Update, update_seed_display);
+        app.update();
+
+        let mut query = app.world.query_filtered::<&Text, With<SeedDisplay>>();
+        let text = query.single(&app.world);
+        assert_eq!(text.sections[0].value, "Seed: 1234 (F5 copy)");
+    }
+
+    #[test]
+    fn typed_seed_starts_the_same_game() {
+        let mut entry = SeedEntry {
+            editing: true,
+            text: String::new(),
+        };
+        for key in [KeyCode::Digit4, KeyCode::Digit2, KeyCode::Enter] {
+            let mut keyboard_input = ButtonInput::<KeyCode>::default();
+            keyboard_input.press(key);
+            entry.edit(&keyboard_input);
+        }
+        assert!(!entry.editing);
+        assert_eq!(entry.seed(), Some(42));
+
+        let mut entered = GameRng::new(entry.seed().unwrap());
+        let mut expected = GameRng::new(42);
+        assert_eq!(entered.seed(), 42);
+        let weights = BagWeights::default();
+        for _ in 0..20 {
+            assert_eq!(entered.piece_type(&weights), expected.piece_type(&weights));
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use crate::analysis::board_metrics;
+use crate::components::{Piece, Position};
+use crate::game_types::GameMap;
+use crate::gravity_curve::GravityCurve;
+use crate::practice::GravityMultiplier;
+use crate::rng::GameRng;
+use crate::rotation::rotate_cw;
+use crate::rules::RulesConfig;
+use crate::{
+    Level, Score, award_lines, can_move, can_move_horizontally, gravity_for_level, piece_cells,
+    place_piece, spawn_position,
+};
+use std::time::Duration;
+
+// Frame length a headless run steps by
+const HEADLESS_FRAME: Duration = Duration::from_millis(16);
+
+// Logical inputs a simulation frame can receive
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GameAction {
+    MoveLeft,
+    MoveRight,
+    RotateCw,
+    SoftDrop,
+    HardDrop,
+}
+
+impl GameAction {
+    // Actions issued in the same frame apply in this order: rotations, then
+    // horizontal moves, then drops, whatever order they arrived in
+    fn phase(&self) -> u8 {
+        match self {
+            GameAction::RotateCw => 0,
+            GameAction::MoveLeft | GameAction::MoveRight => 1,
+            GameAction::SoftDrop | GameAction::HardDrop => 2,
+        }
+    }
+}
+
+// Board contents plus the falling piece, free of any Bevy world state
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Board {
+    pub map: GameMap,
+    pub active: Option<(Piece, Position)>,
+}
+
+impl Board {
+    // Landing cells of every rotation and column the active piece can be
+    // dropped straight down into from its current row without adding a hole.
+    // Rotations that land on the same cells are listed once. Without
+    // rotation only the piece's current orientation is tried.
+    pub fn clean_placements(&self, allow_rotation: bool) -> Vec<Vec<(isize, isize)>> {
+        let Some((piece, position)) = self.active else {
+            return Vec::new();
+        };
+        let holes_before = board_metrics(&self.map).holes;
+        let mut placements: Vec<Vec<(isize, isize)>> = Vec::new();
+        let states = if allow_rotation {
+            0..piece.states.len()
+        } else {
+            piece.current_state..piece.current_state + 1
+        };
+        for state in states {
+            let rotated = Piece {
+                current_state: state,
+                ..piece
+            };
+            for x in -3..self.map.width() as isize {
+                let mut landing = Position { x, y: position.y };
+                if !can_move(&rotated, &landing, landing.y, &self.map) {
+                    continue;
+                }
+                while can_move(&rotated, &landing, landing.y + 1, &self.map) {
+                    landing.y += 1;
+                }
+                let mut map = self.map.clone();
+                place_piece(&rotated, &landing, &mut map);
+                if board_metrics(&map).holes > holes_before {
+                    continue;
+                }
+                let mut cells = piece_cells(&rotated, &landing);
+                cells.sort_unstable();
+                if !placements.contains(&cells) {
+                    placements.push(cells);
+                }
+            }
+        }
+        placements
+    }
+}
+
+// Game state that only advances through `step`, so two simulations fed the
+// same seed and inputs always end up identical
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Simulation {
+    pub board: Board,
+    pub score: Score,
+    pub level: Level,
+    pub rules: RulesConfig,
+    pub rng: GameRng,
+    pub lock_delay: Duration,
+    pub game_over: bool,
+    gravity_elapsed: Duration,
+    lock_elapsed: Duration,
+    /// The active piece has not moved down since it spawned, so soft drop
+    /// cannot lock it early; see LockDelay::expire.
+    spawn_protected: bool,
+}
+
+impl Simulation {
+    pub fn new(seed: u64) -> Self {
+        Simulation {
+            board: Board::default(),
+            score: Score::default(),
+            level: Level::default(),
+            rules: RulesConfig::default(),
+            rng: GameRng::new(seed),
+            lock_delay: Duration::from_millis(500),
+            game_over: false,
+            gravity_elapsed: Duration::ZERO,
+            lock_elapsed: Duration::ZERO,
+            spawn_protected: true,
+        }
+    }
+
+    // Applies this frame's inputs rotate first, then moves, then drops, each
+    // group in the order given, then advances gravity and lock delay by dt
+    pub fn step(&mut self, inputs: &[GameAction], dt: Duration) {
+        if self.board.active.is_none() {
+            self.spawn();
+        }
+        let mut ordered = inputs.to_vec();
+        ordered.sort_by_key(GameAction::phase);
+        for action in &ordered {
+            if self.game_over {
+                return;
+            }
+            self.apply(*action);
+        }
+        if self.game_over {
+            return;
+        }
+        self.apply_gravity(dt);
+        self.update_lock(dt);
+    }
+
+    fn spawn(&mut self) {
+        if self.game_over {
+            return;
+        }
+        let piece_type = self.rng.piece_type(&self.rules.piece_weights);
+        let piece = Piece::for_rules(piece_type, &self.rules, &mut self.rng);
+        match spawn_position(&piece, &self.board.map, &self.rules, 0) {
+            Some(position) => self.board.active = Some((piece, position)),
+            None => self.game_over = true,
+        }
+        self.lock_elapsed = Duration::ZERO;
+        self.spawn_protected = true;
+    }
+
+    fn apply(&mut self, action: GameAction) {
+        let Some((mut piece, mut position)) = self.board.active else {
+            return;
+        };
+        let map = &self.board.map;
+
+        match action {
+            GameAction::MoveLeft | GameAction::MoveRight => {
+                let dx = if action == GameAction::MoveLeft {
+                    -1
+                } else {
+                    1
+                };
+                if can_move_horizontally(&piece, &position, position.x + dx, map) {
+                    position.x += dx;
+                    self.lock_elapsed = Duration::ZERO;
+                }
+            }
+            GameAction::RotateCw => {
+                if let Some(rotation) = rotate_cw(&piece, &position, map, &self.rules) {
+                    piece.current_state = rotation.state;
+                    position = rotation.position;
+                    self.lock_elapsed = Duration::ZERO;
+                }
+            }
+            GameAction::SoftDrop => {
+                if can_move(&piece, &position, position.y + 1, map) {
+                    position.y += 1;
+                    self.spawn_protected = false;
+                    self.score.value += self.rules.soft_drop_points;
+                }
+                if self.rules.soft_drop_locks
+                    && !self.spawn_protected
+                    && !can_move(&piece, &position, position.y + 1, map)
+                {
+                    self.lock(piece, position);
+                    return;
+                }
+            }
+            GameAction::HardDrop => {
+                let start_y = position.y;
+                while can_move(&piece, &position, position.y + 1, map) {
+                    position.y += 1;
+                }
+                self.score.value += (position.y - start_y) as u32;
+                self.lock(piece, position);
+                return;
+            }
+        }
+        self.board.active = Some((piece, position));
+    }
+
+    fn apply_gravity(&mut self, dt: Duration) {
+        let interval = gravity_for_level(
+            self.level.value,
+            &GravityCurve::default(),
+            &GravityMultiplier::default(),
+        );
+
+        // Each elapsed interval is one row, checked on its own, so a long dt
+        // cannot carry the piece past a ledge
+        self.gravity_elapsed += dt;
+        while self.gravity_elapsed >= interval {
+            self.gravity_elapsed -= interval;
+            let Some((piece, position)) = &mut self.board.active else {
+                continue;
+            };
+            if can_move(piece, position, position.y + 1, &self.board.map) {
+                position.y += 1;
+                self.spawn_protected = false;
+            }
+        }
+    }
+
+    fn update_lock(&mut self, dt: Duration) {
+        let Some((piece, position)) = self.board.active else {
+            return;
+        };
+        if can_move(&piece, &position, position.y + 1, &self.board.map) {
+            self.lock_elapsed = Duration::ZERO;
+            return;
+        }
+
+        self.lock_elapsed += dt;
+        if self.lock_elapsed >= self.lock_delay {
+            self.lock(piece, position);
+        }
+    }
+
+    fn lock(&mut self, piece: Piece, position: Position) {
+        place_piece(&piece, &position, &mut self.board.map);
+        let lines_cleared = self.board.map.clear_full_rows();
+        if lines_cleared > 0 {
+            award_lines(
+                lines_cleared as u32,
+                self.rules.lines_per_level,
+                &mut self.score,
+                &mut self.level,
+            );
+        }
+        self.board.active = None;
+        self.spawn();
+    }
+}
+
+// Plays up to `pieces` pieces with no window, hard dropping each one where
+// it spawns, and prints where the run ended up. The same seed always gives
+// the same result, so a run can be checked away from the game.
+pub fn run_headless(seed: u64, pieces: u32) {
+    let mut simulation = Simulation::new(seed);
+    let mut placed = 0;
+    while placed < pieces && !simulation.game_over {
+        simulation.step(&[GameAction::HardDrop], HEADLESS_FRAME);
+        placed += 1;
+    }
+    println!(
+        "Seed {}: {} pieces, score {}, level {}{}",
+        seed,
+        placed,
+        simulation.score.value,
+        simulation.level.value,
+        if simulation.game_over {
+            ", topped out"
+        } else {
+            ""
+        }
+    );
+    println!("{}", simulation.board.map.to_compact_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_types::{PieceType, Presence};
+
+    // A repeating script of inputs, one slice per frame
+    fn script(frame: usize) -> &'static [GameAction] {
+        const SCRIPT: [&[GameAction]; 6] = [
+            &[GameAction::MoveLeft],
+            &[GameAction::RotateCw, GameAction::MoveRight],
+            &[],
+            &[GameAction::SoftDrop],
+            &[GameAction::MoveRight, GameAction::MoveRight],
+            &[GameAction::HardDrop],
+        ];
+        SCRIPT[frame % SCRIPT.len()]
+    }
+
+    #[test]
+    fn same_seed_and_inputs_give_identical_simulations() {
+        let mut first = Simulation::new(42);
+        let mut second = Simulation::new(42);
+        for frame in 0..600 {
+            first.step(script(frame), HEADLESS_FRAME);
+            second.step(script(frame), HEADLESS_FRAME);
+        }
+
+        assert_eq!(first, second);
+        assert_eq!(format!("{:?}", first), format!("{:?}", second));
+        assert!(first.score.value > 0);
+    }
+
+    fn filled_cells(simulation: &Simulation) -> usize {
+        simulation
+            .board
+            .map
+            .rows
+            .iter()
+            .flatten()
+            .filter(|cell| **cell != Presence::No)
+            .count()
+    }
+
+    // Soft drops every frame long enough to reach the floor from the top
+    fn soft_drop_to_floor(soft_drop_locks: bool) -> Simulation {
+        let mut simulation = Simulation::new(3);
+        simulation.rules.soft_drop_locks = soft_drop_locks;
+        for _ in 0..simulation.board.map.height() + 2 {
+            simulation.step(&[GameAction::SoftDrop], Duration::from_millis(1));
+        }
+        simulation
+    }
+
+    #[test]
+    fn soft_drop_locks_on_touchdown_when_enabled() {
+        let simulation = soft_drop_to_floor(true);
+        assert_eq!(filled_cells(&simulation), 4);
+    }
+
+    #[test]
+    fn soft_drop_waits_for_lock_delay_when_disabled() {
+        let mut simulation = soft_drop_to_floor(false);
+        assert_eq!(filled_cells(&simulation), 0);
+
+        simulation.step(&[], simulation.lock_delay);
+        assert_eq!(filled_cells(&simulation), 4);
+    }
+
+    #[test]
+    fn clean_placements_skip_a_drop_that_leaves_a_hole() {
+        // An O over columns 0 and 1 rests on the block and covers a hole
+        let board = Board {
+            map: GameMap::from_compact_string("..../..../..../..../r...").unwrap(),
+            active: Some((Piece::new(PieceType::O), Position { x: 0, y: 0 })),
+        };
+        let placements = board.clean_placements(false);
+
+        let covers_hole = vec![(0, 2), (0, 3), (1, 2), (1, 3)];
+        assert!(!placements.contains(&covers_hole));
+        assert_eq!(
+            placements,
+            vec![
+                vec![(1, 3), (1, 4), (2, 3), (2, 4)],
+                vec![(2, 3), (2, 4), (3, 3), (3, 4)],
+            ]
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use crate::components::{Piece, Position};
+use crate::events::{PieceLocked, Spin};
+use crate::game_types::GameMap;
+use crate::survival::SurvivalMode;
+use crate::{Score, can_move, can_move_horizontally};
+use bevy::prelude::*;
+
+// Points for a spin, per row it clears and for one that clears nothing
+const SPIN_LINE_BONUS: u32 = 200;
+const SPIN_NO_LINE_BONUS: u32 = 100;
+
+// Immobility test: the piece cannot shift one cell left, right, up or down
+pub fn is_immobile(piece: &Piece, position: &Position, game_map: &GameMap) -> bool {
+    !can_move(piece, position, position.y + 1, game_map)
+        && !can_move(piece, position, position.y - 1, game_map)
+        && !can_move_horizontally(piece, position, position.x - 1, game_map)
+        && !can_move_horizontally(piece, position, position.x + 1, game_map)
+}
+
+// Scores every spin lock and announces it. Runs in the frame of the lock,
+// while the rows it completed are still on the board.
+pub fn award_spin_bonus(
+    game_map: Res<GameMap>,
+    survival: Res<SurvivalMode>,
+    mut locked: EventReader<PieceLocked>,
+    mut score: ResMut<Score>,
+    mut spins: EventWriter<Spin>,
+) {
+    for event in locked.read().filter(|event| event.spin) {
+        let lines = game_map.full_row_count() as u32;
+        if !survival.0 {
+            score.value += if lines > 0 {
+                lines * SPIN_LINE_BONUS
+            } else {
+                SPIN_NO_LINE_BONUS
+            };
+        }
+        spins.send(Spin {
+            piece_type: event.piece_type,
+            lines,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action_trace::ActionTrace;
+    use crate::debug::DebugSettings;
+    use crate::events::PieceHeld;
+    use crate::game_types::{BoardConfig, PieceType};
+    use crate::garbage::PendingGarbage;
+    use crate::hold::HoldSlot;
+    use crate::queue::PieceQueue;
+    use crate::rng::GameRng;
+    use crate::rules::RulesConfig;
+    use crate::{
+        EntryDelay, GameState, KickAttempt, LastKick, LastRotation, LockDelay, handle_input,
+        lock_grounded_piece,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn s_rotated_into_a_tight_slot_locks_as_a_spin() {
+        // The upright S at (1, 1) turns flat into a slot it cannot leave
+        let game_map = GameMap::from_compact_string("...a./...../a..a./.....").unwrap();
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .insert_resource(game_map)
+            .init_resource::<BoardConfig>()
+            .init_resource::<LockDelay>()
+            .insert_resource(RulesConfig {
+                all_spin: true,
+                ..RulesConfig::default()
+            })
+            .init_resource::<PieceQueue>()
+            .init_resource::<GameRng>()
+            .init_resource::<HoldSlot>()
+            .init_resource::<LastKick>()
+            .init_resource::<LastRotation>()
+            .init_resource::<KickAttempt>()
+            .init_resource::<ActionTrace>()
+            .init_resource::<EntryDelay>()
+            .init_resource::<PendingGarbage>()
+            .init_resource::<DebugSettings>()
+            .init_resource::<Score>()
+            .init_resource::<SurvivalMode>()
+            .init_resource::<NextState<GameState>>()
+            .add_event::<PieceHeld>()
+            .add_event::<PieceLocked>()
+            .add_event::<Spin>()
+            .add_systems(
+                Update,
+                (handle_input, lock_grounded_piece, award_spin_bonus).chain(),
+            );
+        let mut piece = Piece::new(PieceType::S);
+        piece.current_state = 3;
+        let entity = app.world.spawn((piece, Position { x: 1, y: 1 })).id();
+
+        app.world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::ArrowUp);
+        app.update();
+        let rotated = *app.world.get::<Piece>(entity).unwrap();
+        assert_eq!(rotated.current_state, 0);
+        assert!(is_immobile(
+            &rotated,
+            &Position { x: 1, y: 1 },
+            app.world.resource::<GameMap>()
+        ));
+
+        app.world.resource_mut::<ButtonInput<KeyCode>>().clear();
+        app.world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(1));
+        app.update();
+        let locked = app.world.resource::<Events<PieceLocked>>();
+        let locked: Vec<_> = locked.get_reader().read(locked).copied().collect();
+        assert_eq!(locked.len(), 1);
+        assert!(locked[0].spin);
+        let spins = app.world.resource::<Events<Spin>>();
+        let spins: Vec<_> = spins.get_reader().read(spins).copied().collect();
+        assert_eq!(spins.len(), 1);
+        assert_eq!(spins[0].piece_type, PieceType::S);
+        assert_eq!(spins[0].lines, 0);
+        assert_eq!(app.world.resource::<Score>().value, SPIN_NO_LINE_BONUS);
+    }
+}
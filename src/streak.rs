@@ -0,0 +1,76 @@
+use crate::GameState;
+use crate::analysis::board_metrics;
+use crate::events::LinesCleared;
+use crate::game_constants::FONT_SIZE_LOG;
+use crate::game_types::GameMap;
+use bevy::prelude::*;
+
+// Lines in a tetris, the clear the streak counts toward
+const TETRIS_LINES: u32 = 4;
+
+// Shows the streak readout during a run when on
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StreakHud(pub bool);
+
+// Clears of fewer than four lines since the last tetris
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ClearStreak {
+    pub small_clears: u32,
+}
+
+pub fn track_clear_streak(mut cleared: EventReader<LinesCleared>, mut streak: ResMut<ClearStreak>) {
+    for event in cleared.read() {
+        if event.count >= TETRIS_LINES {
+            streak.small_clears = 0;
+        } else {
+            streak.small_clears += 1;
+        }
+    }
+}
+
+// Marker component for the streak readout
+#[derive(Component)]
+pub struct StreakDisplay;
+
+pub fn setup_streak_display(mut commands: Commands) {
+    let mut text_bundle = TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: FONT_SIZE_LOG,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        top: Val::Px(164.0),
+        left: Val::Px(10.0),
+        ..default()
+    });
+    text_bundle.visibility = Visibility::Hidden;
+
+    commands.spawn((text_bundle, StreakDisplay));
+}
+
+pub fn update_streak_display(
+    game_state: Res<State<GameState>>,
+    hud: Res<StreakHud>,
+    streak: Res<ClearStreak>,
+    game_map: Res<GameMap>,
+    mut query_text: Query<(&mut Text, &mut Visibility), With<StreakDisplay>>,
+) {
+    if (game_state.is_changed() || hud.is_changed() || streak.is_changed() || game_map.is_changed())
+        && let Some((mut text, mut visibility)) = query_text.iter_mut().next()
+    {
+        let mut value = format!("Clears since last tetris: {}", streak.small_clears);
+        if board_metrics(&game_map).tetris_well.is_some() {
+            value.push_str("\nTetris ready!");
+        }
+        text.sections[0].value = value;
+        *visibility = if hud.0 && *game_state.get() != GameState::MainMenu {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
@@ -0,0 +1,100 @@
+use crate::GravityTimer;
+use crate::game_constants::MIN_GRAVITY_MS;
+use bevy::prelude::*;
+use std::time::Duration;
+
+// Gravity interval a survival run starts at
+const SURVIVAL_START_GRAVITY: Duration = Duration::from_millis(1000);
+// Every SURVIVAL_RAMP_SECS of survival, gravity gets this much faster
+const SURVIVAL_RAMP_FACTOR: f32 = 0.85;
+const SURVIVAL_RAMP_SECS: f32 = 15.0;
+
+// Survival mode: line clears score nothing and the run is measured by time
+// alive, with gravity speeding up over time rather than by level
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SurvivalMode(pub bool);
+
+// Time survived in the current run
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SurvivalClock {
+    pub elapsed: Duration,
+}
+
+// Gravity interval after surviving `elapsed`, never below MIN_GRAVITY_MS
+pub fn survival_gravity(elapsed: Duration) -> Duration {
+    let ramps = elapsed.as_secs_f32() / SURVIVAL_RAMP_SECS;
+    let secs = SURVIVAL_START_GRAVITY.as_secs_f32() * SURVIVAL_RAMP_FACTOR.powf(ramps);
+    let minimum = Duration::from_millis(MIN_GRAVITY_MS);
+    Duration::try_from_secs_f32(secs).map_or(minimum, |interval| interval.max(minimum))
+}
+
+// Formats a survival time as seconds with one decimal
+pub fn format_survival_time(elapsed: Duration) -> String {
+    format!("{:.1}s", elapsed.as_secs_f32())
+}
+
+// Advances the survival clock and ramps gravity with it
+pub fn tick_survival_clock(
+    time: Res<Time>,
+    survival: Res<SurvivalMode>,
+    mut clock: ResMut<SurvivalClock>,
+    mut gravity: ResMut<GravityTimer>,
+) {
+    if !survival.0 {
+        return;
+    }
+    clock.elapsed += time.delta();
+    gravity.timer.set_duration(survival_gravity(clock.elapsed));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_log::EventLog;
+    use crate::events::{LevelUp, LinesCleared, PerfectClear, PieceLocked};
+    use crate::game_types::GameMap;
+    use crate::garbage::PendingGarbage;
+    use crate::line_clear::{LineClearAnimation, RowCollapse};
+    use crate::rules::RulesConfig;
+    use crate::visual::VisualSettings;
+    use crate::{Level, Score, clear_lines};
+
+    #[test]
+    fn clears_score_nothing_while_the_clock_runs() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<EventLog>()
+            .insert_resource(SurvivalMode(true))
+            .init_resource::<SurvivalClock>()
+            .init_resource::<GravityTimer>()
+            .init_resource::<LineClearAnimation>()
+            .insert_resource(GameMap::from_compact_string("..../r.../gggg").unwrap())
+            .init_resource::<RulesConfig>()
+            .init_resource::<Score>()
+            .init_resource::<Level>()
+            .init_resource::<PendingGarbage>()
+            .init_resource::<VisualSettings>()
+            .init_resource::<RowCollapse>()
+            .add_event::<PieceLocked>()
+            .add_event::<LinesCleared>()
+            .add_event::<PerfectClear>()
+            .add_event::<LevelUp>()
+            .add_systems(Update, (clear_lines, tick_survival_clock));
+
+        for _ in 0..30 {
+            app.world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_millis(20));
+            app.update();
+        }
+        assert_eq!(
+            app.world.resource::<GameMap>().to_compact_string(),
+            "..../..../r..."
+        );
+        assert_eq!(app.world.resource::<Score>().value, 0);
+        assert_eq!(
+            app.world.resource::<SurvivalClock>().elapsed,
+            Duration::from_millis(600)
+        );
+    }
+}
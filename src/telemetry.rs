@@ -0,0 +1,170 @@
+use crate::action_trace::ActionTrace;
+use crate::components::Position;
+use crate::debug::DebugSettings;
+use crate::events::{GameOver, LevelUp, LinesCleared, PieceLocked, PieceSpawned};
+use crate::game_types::PieceType;
+use crate::simulation::GameAction;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+// Events kept in the telemetry buffer before the oldest are dropped
+const TELEMETRY_CAPACITY: usize = 256;
+
+// One discrete thing that happened in a run, as a single structured stream
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEvent {
+    Spawn,
+    /// A horizontal step on the board, -1 left or 1 right.
+    Move {
+        dx: isize,
+    },
+    Rotate,
+    Lock {
+        piece_type: PieceType,
+        position: Position,
+    },
+    LinesCleared {
+        count: u32,
+    },
+    LevelUp {
+        level: u32,
+    },
+    GameOver,
+}
+
+// Most recent GameEvents, oldest first, filled only while recording is on
+#[derive(Resource, Debug, Default)]
+pub struct TelemetryBuffer {
+    pub events: VecDeque<GameEvent>,
+}
+
+impl TelemetryBuffer {
+    pub fn push(&mut self, event: GameEvent) {
+        if self.events.len() == TELEMETRY_CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+}
+
+// Folds this frame's actions and the separate gameplay events into the
+// GameEvent stream: moves and rotations first, then locks, clears, level
+// ups, the next spawn and game over. Runs before the action trace closes
+// the frame.
+pub fn emit_game_events(
+    trace: Res<ActionTrace>,
+    mut spawned: EventReader<PieceSpawned>,
+    mut locked: EventReader<PieceLocked>,
+    mut lines_cleared: EventReader<LinesCleared>,
+    mut level_up: EventReader<LevelUp>,
+    mut game_over: EventReader<GameOver>,
+    mut events: EventWriter<GameEvent>,
+) {
+    for action in trace.frame() {
+        match action {
+            GameAction::MoveLeft => events.send(GameEvent::Move { dx: -1 }),
+            GameAction::MoveRight => events.send(GameEvent::Move { dx: 1 }),
+            GameAction::RotateCw => events.send(GameEvent::Rotate),
+            GameAction::SoftDrop | GameAction::HardDrop => continue,
+        };
+    }
+    for event in locked.read() {
+        events.send(GameEvent::Lock {
+            piece_type: event.piece_type,
+            position: event.position,
+        });
+    }
+    for event in lines_cleared.read() {
+        events.send(GameEvent::LinesCleared { count: event.count });
+    }
+    for event in level_up.read() {
+        events.send(GameEvent::LevelUp { level: event.level });
+    }
+    for _ in spawned.read() {
+        events.send(GameEvent::Spawn);
+    }
+    for _ in game_over.read() {
+        events.send(GameEvent::GameOver);
+    }
+}
+
+// Subscriber that keeps the stream in the telemetry buffer while recording
+pub fn record_telemetry(
+    settings: Res<DebugSettings>,
+    mut events: EventReader<GameEvent>,
+    mut buffer: ResMut<TelemetryBuffer>,
+) {
+    if !settings.record_telemetry {
+        events.clear();
+        return;
+    }
+    for event in events.read() {
+        buffer.push(*event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recorded_stream_follows_a_scripted_run_in_order() {
+        let mut app = App::new();
+        app.init_resource::<ActionTrace>()
+            .insert_resource(DebugSettings {
+                record_telemetry: true,
+                ..DebugSettings::default()
+            })
+            .init_resource::<TelemetryBuffer>()
+            .add_event::<PieceSpawned>()
+            .add_event::<PieceLocked>()
+            .add_event::<LinesCleared>()
+            .add_event::<LevelUp>()
+            .add_event::<GameOver>()
+            .add_event::<GameEvent>()
+            .add_systems(Update, (emit_game_events, record_telemetry).chain());
+        let frame = |app: &mut App, actions: &[GameAction]| {
+            for &action in actions {
+                app.world.resource_mut::<ActionTrace>().record(action);
+            }
+            app.update();
+            app.world.resource_mut::<ActionTrace>().end_frame();
+        };
+        let landing = Position { x: 0, y: 16 };
+
+        app.world.send_event(PieceSpawned);
+        frame(&mut app, &[]);
+        frame(&mut app, &[GameAction::RotateCw, GameAction::MoveLeft]);
+        app.world.send_event(PieceLocked {
+            piece_type: PieceType::I,
+            position: landing,
+            cells: [(0, 17), (1, 17), (2, 17), (3, 17)],
+            spin: false,
+        });
+        frame(&mut app, &[GameAction::MoveRight, GameAction::HardDrop]);
+        app.world.send_event(LinesCleared { count: 1 });
+        app.world.send_event(LevelUp { level: 1 });
+        app.world.send_event(PieceSpawned);
+        frame(&mut app, &[]);
+        app.world.send_event(GameOver);
+        frame(&mut app, &[]);
+
+        assert_eq!(
+            app.world.resource::<TelemetryBuffer>().events,
+            [
+                GameEvent::Spawn,
+                GameEvent::Rotate,
+                GameEvent::Move { dx: -1 },
+                GameEvent::Move { dx: 1 },
+                GameEvent::Lock {
+                    piece_type: PieceType::I,
+                    position: landing,
+                },
+                GameEvent::LinesCleared { count: 1 },
+                GameEvent::LevelUp { level: 1 },
+                GameEvent::Spawn,
+                GameEvent::GameOver,
+            ]
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use crate::event_log::EventLog;
+use crate::game_color::GameColor;
+use bevy::prelude::*;
+use std::fs;
+
+const COLOR_THEME_FILE: &str = "color_theme.txt";
+
+// Gameboy greens, darkest to lightest
+const GAMEBOY_DARKEST: (u8, u8, u8) = (15, 56, 15);
+const GAMEBOY_DARK: (u8, u8, u8) = (48, 98, 48);
+const GAMEBOY_LIGHT: (u8, u8, u8) = (139, 172, 15);
+const GAMEBOY_LIGHTEST: (u8, u8, u8) = (155, 188, 15);
+
+// Palette every GameColor, the background included, is drawn with
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorTheme {
+    #[default]
+    Guideline,
+    Gameboy,
+    Neon,
+}
+
+impl ColorTheme {
+    const ALL: [ColorTheme; 3] = [ColorTheme::Guideline, ColorTheme::Gameboy, ColorTheme::Neon];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorTheme::Guideline => "Guideline",
+            ColorTheme::Gameboy => "Gameboy",
+            ColorTheme::Neon => "Neon",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ColorTheme> {
+        ColorTheme::ALL
+            .into_iter()
+            .find(|theme| theme.name() == name.trim())
+    }
+
+    pub fn cycled(self) -> Self {
+        match self {
+            ColorTheme::Guideline => ColorTheme::Gameboy,
+            ColorTheme::Gameboy => ColorTheme::Neon,
+            ColorTheme::Neon => ColorTheme::Guideline,
+        }
+    }
+
+    pub fn color(&self, game_color: GameColor) -> Color {
+        let (r, g, b) = match self {
+            ColorTheme::Guideline => guideline_rgb(game_color),
+            ColorTheme::Gameboy => gameboy_rgb(game_color),
+            ColorTheme::Neon => neon_rgb(game_color),
+        };
+        Color::rgb_u8(r, g, b)
+    }
+
+    // Color behind the board
+    pub fn background(&self) -> Color {
+        self.color(GameColor::Gray)
+    }
+}
+
+fn guideline_rgb(game_color: GameColor) -> (u8, u8, u8) {
+    match game_color {
+        GameColor::Red => (255, 0, 0),
+        GameColor::Green => (0, 255, 0),
+        GameColor::Blue => (0, 0, 255),
+        GameColor::Yellow => (255, 255, 0),
+        GameColor::Cyan => (0, 255, 255),
+        GameColor::Orange => (255, 165, 0),
+        GameColor::Purple => (128, 0, 128),
+        GameColor::Gray => (128, 128, 128),
+        GameColor::DarkGray => (64, 64, 64),
+        GameColor::Pink => (255, 192, 203),
+    }
+}
+
+// Four greens only: the lightest is the background, pieces take the others
+fn gameboy_rgb(game_color: GameColor) -> (u8, u8, u8) {
+    match game_color {
+        GameColor::Gray => GAMEBOY_LIGHTEST,
+        GameColor::Yellow | GameColor::Cyan | GameColor::Pink => GAMEBOY_LIGHT,
+        GameColor::Red | GameColor::Orange | GameColor::Green => GAMEBOY_DARK,
+        GameColor::Blue | GameColor::Purple | GameColor::DarkGray => GAMEBOY_DARKEST,
+    }
+}
+
+// Saturated colors on a near-black background
+fn neon_rgb(game_color: GameColor) -> (u8, u8, u8) {
+    match game_color {
+        GameColor::Red => (255, 20, 90),
+        GameColor::Green => (57, 255, 20),
+        GameColor::Blue => (0, 150, 255),
+        GameColor::Yellow => (255, 240, 0),
+        GameColor::Cyan => (0, 255, 240),
+        GameColor::Orange => (255, 120, 0),
+        GameColor::Purple => (190, 0, 255),
+        GameColor::Gray => (10, 10, 20),
+        GameColor::DarkGray => (60, 60, 80),
+        GameColor::Pink => (255, 60, 200),
+    }
+}
+
+// Restores the theme saved by the last session, if any
+pub fn load_color_theme(mut theme: ResMut<ColorTheme>, mut clear_color: ResMut<ClearColor>) {
+    if let Some(saved) = fs::read_to_string(COLOR_THEME_FILE)
+        .ok()
+        .and_then(|text| ColorTheme::from_name(&text))
+    {
+        *theme = saved;
+    }
+    clear_color.0 = theme.background();
+}
+
+// Repaints the background and saves the choice whenever the theme changes
+pub fn apply_color_theme(
+    theme: Res<ColorTheme>,
+    mut clear_color: ResMut<ClearColor>,
+    mut log: ResMut<EventLog>,
+) {
+    if !theme.is_changed() || theme.is_added() {
+        return;
+    }
+    clear_color.0 = theme.background();
+    if let Err(err) = fs::write(COLOR_THEME_FILE, theme.name()) {
+        log.push(format!("Could not save color theme: {}", err));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gameboy_draws_red_and_blue_in_its_greens() {
+        let greens = [
+            GAMEBOY_DARKEST,
+            GAMEBOY_DARK,
+            GAMEBOY_LIGHT,
+            GAMEBOY_LIGHTEST,
+        ]
+        .map(|(r, g, b)| Color::rgb_u8(r, g, b));
+        let theme = ColorTheme::Guideline.cycled();
+        assert_eq!(theme, ColorTheme::Gameboy);
+        for game_color in [GameColor::Red, GameColor::Blue] {
+            let color = theme.color(game_color);
+            assert!(greens.contains(&color), "{:?} is {:?}", game_color, color);
+            assert!(color.g() > color.r() && color.g() > color.b());
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use crate::GameState;
+use crate::game_constants::FONT_SIZE_SMALL;
+use crate::game_types::BoardConfig;
+use crate::pause::PAUSE_KEY;
+use bevy::prelude::*;
+
+// Size in pixels of one on-screen button
+const BUTTON_SIZE: f32 = 56.0;
+
+// On-screen buttons along the bottom for playing with a mouse or touch screen
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TouchControls(pub bool);
+
+// An on-screen button and the game action it stands for
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchButton {
+    Left,
+    Right,
+    Rotate,
+    SoftDrop,
+    HardDrop,
+    Hold,
+    Pause,
+}
+
+impl TouchButton {
+    const ALL: [TouchButton; 7] = [
+        TouchButton::Left,
+        TouchButton::Right,
+        TouchButton::Rotate,
+        TouchButton::SoftDrop,
+        TouchButton::HardDrop,
+        TouchButton::Hold,
+        TouchButton::Pause,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            TouchButton::Left => "<",
+            TouchButton::Right => ">",
+            TouchButton::Rotate => "Rotate",
+            TouchButton::SoftDrop => "Down",
+            TouchButton::HardDrop => "Drop",
+            TouchButton::Hold => "Hold",
+            TouchButton::Pause => "Pause",
+        }
+    }
+
+    // The action a name in the mouse bindings file stands for
+    pub fn from_name(name: &str) -> Option<TouchButton> {
+        match name {
+            "left" => Some(TouchButton::Left),
+            "right" => Some(TouchButton::Right),
+            "rotate" => Some(TouchButton::Rotate),
+            "soft_drop" => Some(TouchButton::SoftDrop),
+            "hard_drop" => Some(TouchButton::HardDrop),
+            "hold" => Some(TouchButton::Hold),
+            "pause" => Some(TouchButton::Pause),
+            _ => None,
+        }
+    }
+
+    // Key the button stands in for, so the keyboard systems, DAS included,
+    // handle it like a key press
+    pub fn key(&self, board: &BoardConfig) -> KeyCode {
+        match self {
+            TouchButton::Left => KeyCode::ArrowLeft,
+            TouchButton::Right => KeyCode::ArrowRight,
+            TouchButton::Rotate => board.rotate_key(),
+            TouchButton::SoftDrop => board.soft_drop_key(),
+            TouchButton::HardDrop => KeyCode::Space,
+            TouchButton::Hold => KeyCode::KeyC,
+            TouchButton::Pause => PAUSE_KEY,
+        }
+    }
+}
+
+// Key a touch button is holding down, released when the button is let go
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HeldKey(pub Option<KeyCode>);
+
+// Marker component for the row of touch buttons
+#[derive(Component)]
+pub struct TouchButtonRow;
+
+pub fn setup_touch_buttons(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(40.0),
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    // The buttons are wider than the board window, so they
+                    // wrap onto a second row
+                    flex_wrap: FlexWrap::Wrap,
+                    column_gap: Val::Px(8.0),
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            TouchButtonRow,
+        ))
+        .with_children(|row| {
+            for button in TouchButton::ALL {
+                row.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(BUTTON_SIZE),
+                            height: Val::Px(BUTTON_SIZE),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                        ..default()
+                    },
+                    button,
+                    HeldKey::default(),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        button.label(),
+                        TextStyle {
+                            font_size: FONT_SIZE_SMALL,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    ));
+                });
+            }
+        });
+}
+
+// Presses a button's key while the button is held and releases it after
+pub fn press_touch_buttons(
+    board: Res<BoardConfig>,
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut query_buttons: Query<(&Interaction, &TouchButton, &mut HeldKey)>,
+) {
+    for (interaction, button, mut held) in query_buttons.iter_mut() {
+        match (*interaction == Interaction::Pressed, held.0) {
+            (true, None) => {
+                let key = button.key(&board);
+                keyboard_input.press(key);
+                held.0 = Some(key);
+            }
+            (false, Some(key)) => {
+                keyboard_input.release(key);
+                held.0 = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+// Shows the buttons during play when touch controls are on, and while
+// paused so the pause button can resume
+pub fn update_touch_button_visibility(
+    game_state: Res<State<GameState>>,
+    touch_controls: Res<TouchControls>,
+    mut query_row: Query<&mut Visibility, With<TouchButtonRow>>,
+) {
+    if !game_state.is_changed() && !touch_controls.is_changed() {
+        return;
+    }
+    for mut visibility in query_row.iter_mut() {
+        let in_run = matches!(game_state.get(), GameState::Playing | GameState::Paused);
+        *visibility = if touch_controls.0 && in_run {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action_trace::ActionTrace;
+    use crate::components::{Piece, Position};
+    use crate::game_types::{GameMap, PieceType};
+    use crate::simulation::GameAction;
+    use crate::{AutoShift, LockDelay, auto_shift_piece};
+
+    #[test]
+    fn clicking_left_dispatches_move_left_until_released() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<AutoShift>()
+            .init_resource::<GameMap>()
+            .init_resource::<BoardConfig>()
+            .init_resource::<LockDelay>()
+            .init_resource::<ActionTrace>()
+            .add_systems(Update, (press_touch_buttons, auto_shift_piece).chain());
+        let piece = app
+            .world
+            .spawn((Position { x: 3, y: 5 }, Piece::new(PieceType::T)))
+            .id();
+        let button = app
+            .world
+            .spawn((Interaction::Pressed, TouchButton::Left, HeldKey::default()))
+            .id();
+
+        app.update();
+        assert_eq!(
+            app.world.resource::<ActionTrace>().frame(),
+            [GameAction::MoveLeft]
+        );
+        assert_eq!(app.world.get::<Position>(piece).unwrap().x, 2);
+        assert!(
+            app.world
+                .resource::<ButtonInput<KeyCode>>()
+                .pressed(KeyCode::ArrowLeft)
+        );
+
+        *app.world.get_mut::<Interaction>(button).unwrap() = Interaction::None;
+        app.update();
+        assert!(
+            !app.world
+                .resource::<ButtonInput<KeyCode>>()
+                .pressed(KeyCode::ArrowLeft)
+        );
+        assert_eq!(app.world.get::<HeldKey>(button).unwrap().0, None);
+    }
+
+    #[test]
+    fn pause_button_pauses_and_resumes_the_run() {
+        let mut app = App::new();
+        app.init_state::<GameState>()
+            .insert_resource(NextState(Some(GameState::Playing)))
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<BoardConfig>()
+            .add_systems(
+                Update,
+                (press_touch_buttons, crate::pause::toggle_pause).chain(),
+            );
+        assert_eq!(TouchButton::from_name("pause"), Some(TouchButton::Pause));
+        let button = app
+            .world
+            .spawn((Interaction::None, TouchButton::Pause, HeldKey::default()))
+            .id();
+        app.update();
+
+        let tap = |app: &mut App| {
+            for interaction in [Interaction::Pressed, Interaction::None] {
+                *app.world.get_mut::<Interaction>(button).unwrap() = interaction;
+                app.update();
+                app.world.resource_mut::<ButtonInput<KeyCode>>().clear();
+            }
+            *app.world.resource::<State<GameState>>().get()
+        };
+        assert_eq!(tap(&mut app), GameState::Paused);
+        assert_eq!(tap(&mut app), GameState::Playing);
+    }
+}
@@ -0,0 +1,110 @@
+use crate::GameState;
+use bevy::prelude::*;
+use std::time::Duration;
+
+// How long the screen takes to dip to black and back for a state change, or
+// None to switch without a fade
+pub fn fade_duration(before: GameState, after: GameState) -> Option<Duration> {
+    match (before, after) {
+        (GameState::MainMenu, GameState::Playing) => Some(Duration::from_millis(400)),
+        (GameState::Playing, GameState::GameOver) => Some(Duration::from_millis(800)),
+        _ => None,
+    }
+}
+
+// Overlay opacity at a point in the fade: 0 at both ends, fully black halfway
+pub fn fade_alpha(progress: f32) -> f32 {
+    1.0 - (2.0 * progress.clamp(0.0, 1.0) - 1.0).abs()
+}
+
+// Timer of the fade in progress, if any
+#[derive(Resource, Debug, Default)]
+pub struct FadeTransition {
+    timer: Option<Timer>,
+}
+
+// Marker component for the full-screen fade overlay
+#[derive(Component)]
+pub struct FadeOverlay;
+
+pub fn setup_fade_overlay(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.0).into(),
+            z_index: ZIndex::Global(i32::MAX),
+            ..default()
+        },
+        FadeOverlay,
+    ));
+}
+
+// Starts a fade for every state change that has one configured
+pub fn start_fade(
+    mut transitions: EventReader<StateTransitionEvent<GameState>>,
+    mut fade: ResMut<FadeTransition>,
+) {
+    for transition in transitions.read() {
+        if let Some(duration) = fade_duration(transition.before, transition.after) {
+            fade.timer = Some(Timer::new(duration, TimerMode::Once));
+        }
+    }
+}
+
+pub fn animate_fade(
+    time: Res<Time>,
+    mut fade: ResMut<FadeTransition>,
+    mut query_overlay: Query<&mut BackgroundColor, With<FadeOverlay>>,
+) {
+    let Some(timer) = fade.timer.as_mut() else {
+        return;
+    };
+    timer.tick(time.delta());
+    let alpha = fade_alpha(timer.fraction());
+    if timer.finished() {
+        fade.timer = None;
+    }
+    for mut color in query_overlay.iter_mut() {
+        color.0.set_a(alpha);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlay_fades_in_and_back_out_over_the_configured_duration() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<FadeTransition>()
+            .add_event::<StateTransitionEvent<GameState>>()
+            .add_systems(Startup, setup_fade_overlay)
+            .add_systems(Update, (start_fade, animate_fade).chain());
+        app.world.send_event(StateTransitionEvent {
+            before: GameState::MainMenu,
+            after: GameState::Playing,
+        });
+        let duration = fade_duration(GameState::MainMenu, GameState::Playing).unwrap();
+        let alpha_after = |app: &mut App, dt: Duration| {
+            app.world.resource_mut::<Time>().advance_by(dt);
+            app.update();
+            let mut query = app
+                .world
+                .query_filtered::<&BackgroundColor, With<FadeOverlay>>();
+            query.single(&app.world).0.a()
+        };
+
+        assert_eq!(alpha_after(&mut app, Duration::ZERO), 0.0);
+        let quarter = alpha_after(&mut app, duration / 4);
+        assert!(quarter > 0.0 && quarter < 1.0, "alpha {}", quarter);
+        assert_eq!(alpha_after(&mut app, duration / 4), 1.0);
+        assert_eq!(alpha_after(&mut app, duration / 2), 0.0);
+        assert!(app.world.resource::<FadeTransition>().timer.is_none());
+    }
+}
@@ -0,0 +1,45 @@
+use crate::game_constants::HEIGHT;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+// Keeps text readable on tiny windows and avoids a giant HUD on 4K displays
+const MIN_UI_SCALE: f32 = 0.5;
+const MAX_UI_SCALE: f32 = 3.0;
+
+// UI scale for a window of the given height, relative to the default window
+pub fn scale_for_height(window_height: f32) -> f32 {
+    (window_height / HEIGHT as f32).clamp(MIN_UI_SCALE, MAX_UI_SCALE)
+}
+
+// Scales every UI node, font sizes and pixel offsets alike, with the window
+pub fn update_ui_scale(
+    query_window: Query<&Window, With<PrimaryWindow>>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    let Ok(window) = query_window.get_single() else {
+        return;
+    };
+    let scale = scale_for_height(window.height());
+    if ui_scale.0 != scale {
+        ui_scale.0 = scale;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_constants::FONT_SIZE_HUD;
+
+    #[test]
+    fn font_sizes_scale_in_proportion_to_window_height() {
+        let small = scale_for_height(HEIGHT as f32 * 0.75);
+        let large = scale_for_height(HEIGHT as f32 * 2.0);
+        assert_eq!(scale_for_height(HEIGHT as f32), 1.0);
+        assert_eq!(FONT_SIZE_HUD * small, FONT_SIZE_HUD * 0.75);
+        assert_eq!(FONT_SIZE_HUD * large, FONT_SIZE_HUD * 2.0);
+        assert_eq!(
+            (FONT_SIZE_HUD * large) / (FONT_SIZE_HUD * small),
+            2.0 / 0.75
+        );
+    }
+}
@@ -0,0 +1,507 @@
+use crate::components::{Piece, Position, VisualOffset};
+use crate::game_constants::{FONT_SIZE_SMALL, Z_GHOST, Z_OVERLAY, Z_TRAJECTORY};
+use crate::game_types::{BoardConfig, GameMap, Presence};
+use crate::line_clear::LineClearStyle;
+use crate::{block_sprite, drop_distance, piece_cells};
+use bevy::prelude::*;
+use std::time::Duration;
+
+// Seconds the drawn piece takes to catch up one cell
+const MOVE_INTERPOLATION_SECS: f32 = 0.05;
+// Jumps longer than this (hold swaps, teleports) snap instead of sliding
+const MAX_INTERPOLATED_CELLS: f32 = 2.0;
+
+// Alpha of a filled ghost cell
+const GHOST_ALPHA: f32 = 0.3;
+// Outline thickness as a fraction of the cell size
+const GHOST_OUTLINE_WIDTH: f32 = 0.1;
+// Trajectory line thickness as a fraction of the cell size, and its alpha
+const TRAJECTORY_WIDTH: f32 = 0.08;
+const TRAJECTORY_ALPHA: f32 = 0.35;
+
+// How the landing preview of the active piece is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GhostStyle {
+    #[default]
+    SolidTransparent,
+    /// Only the outer border of the footprint, in the piece color.
+    Outline,
+}
+
+impl GhostStyle {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GhostStyle::SolidTransparent => "solid",
+            GhostStyle::Outline => "outline",
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            GhostStyle::SolidTransparent => GhostStyle::Outline,
+            GhostStyle::Outline => GhostStyle::SolidTransparent,
+        }
+    }
+}
+
+// Purely cosmetic rendering options
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VisualSettings {
+    pub smooth_movement: bool,
+    pub ghost_style: GhostStyle,
+    pub line_clear_style: LineClearStyle,
+    pub show_trajectory: bool,
+    pub glide_soft_drop: bool,
+    pub show_drop_distance: bool,
+    /// How long rows above a clear take to fall into place; zero drops them
+    /// instantly.
+    pub collapse_duration: Duration,
+    /// Stop drawing the ghost once the level passes this, whatever its
+    /// style; None always draws it.
+    pub ghost_hidden_above: Option<u32>,
+    /// Faintly light the rows a hard drop from where the piece is would
+    /// complete.
+    pub preview_completed_rows: bool,
+}
+
+impl Default for VisualSettings {
+    fn default() -> Self {
+        VisualSettings {
+            smooth_movement: true,
+            ghost_style: GhostStyle::default(),
+            line_clear_style: LineClearStyle::default(),
+            show_trajectory: false,
+            glide_soft_drop: true,
+            show_drop_distance: false,
+            collapse_duration: Duration::ZERO,
+            ghost_hidden_above: None,
+            preview_completed_rows: false,
+        }
+    }
+}
+
+// Levels the menu offers for hiding the ghost, None for never
+const GHOST_LEVEL_LIMIT_CHOICES: [Option<u32>; 5] = [None, Some(5), Some(10), Some(15), Some(20)];
+
+impl VisualSettings {
+    pub fn shows_ghost(&self, level: u32) -> bool {
+        self.ghost_hidden_above.is_none_or(|limit| level <= limit)
+    }
+
+    // Steps ghost_hidden_above to the next menu choice, wrapping around
+    pub fn cycle_ghost_level_limit(&mut self) {
+        let next = GHOST_LEVEL_LIMIT_CHOICES
+            .iter()
+            .position(|&choice| choice == self.ghost_hidden_above)
+            .map_or(0, |index| (index + 1) % GHOST_LEVEL_LIMIT_CHOICES.len());
+        self.ghost_hidden_above = GHOST_LEVEL_LIMIT_CHOICES[next];
+    }
+
+    pub fn ghost_level_limit_name(&self) -> String {
+        match self.ghost_hidden_above {
+            Some(level) => format!("above level {}", level),
+            None => String::from("never"),
+        }
+    }
+}
+
+// Share of the way to white a cell in a row the piece would complete is lit
+const COMPLETION_PREVIEW_LIGHTEN: f32 = 0.35;
+
+// Rows the active piece would complete if it were hard dropped now, found by
+// placing it where the ghost lands
+pub fn rows_completed_on_drop(
+    piece: &Piece,
+    position: &Position,
+    game_map: &GameMap,
+) -> Vec<usize> {
+    let landing = Position {
+        x: position.x,
+        y: position.y + drop_distance(piece, position, game_map),
+    };
+    game_map.rows_completed_by(&piece_cells(piece, &landing))
+}
+
+// Lightens a board cell in a row the piece would complete
+pub fn light_completing_cell(sprite: &mut SpriteBundle) {
+    let [r, g, b, a] = sprite.sprite.color.as_rgba_f32();
+    let lighten = |channel: f32| channel + (1.0 - channel) * COMPLETION_PREVIEW_LIGHTEN;
+    sprite.sprite.color = Color::rgba(lighten(r), lighten(g), lighten(b), a);
+}
+
+// Sprites for the ghost covering `cells`, drawn just behind the active piece
+pub fn ghost_sprites(
+    board: &BoardConfig,
+    cells: &[(isize, isize)],
+    color: Color,
+    style: GhostStyle,
+) -> Vec<SpriteBundle> {
+    let mut sprites = Vec::new();
+    for &(x, y) in cells {
+        let mut cell = block_sprite(board, x, y, color);
+        cell.transform.translation.z = Z_GHOST;
+        match style {
+            GhostStyle::SolidTransparent => {
+                cell.sprite.color.set_a(GHOST_ALPHA);
+                sprites.push(cell);
+            }
+            GhostStyle::Outline => {
+                let thickness = board.cell_size * GHOST_OUTLINE_WIDTH;
+                let inset = (board.cell_size - thickness) / 2.0;
+                for (dx, dy) in [(0, -1), (0, 1), (-1, 0), (1, 0)] {
+                    // Edges shared with another ghost cell are interior
+                    if cells.contains(&(x + dx, y + dy)) {
+                        continue;
+                    }
+                    let direction =
+                        Vec2::new(board.screen_dx(dx) as f32, board.screen_dy(dy) as f32);
+                    let mut edge = cell.clone();
+                    edge.transform.translation += (direction * inset).extend(0.0);
+                    edge.sprite.custom_size = Some(if direction.x == 0.0 {
+                        Vec2::new(board.cell_size, thickness)
+                    } else {
+                        Vec2::new(thickness, board.cell_size)
+                    });
+                    sprites.push(edge);
+                }
+            }
+        }
+    }
+    sprites
+}
+
+// Turns grid moves into a visual offset that decays back to zero, so the
+// piece glides between cells while collision stays on the integer grid. With
+// glide_soft_drop off, rows dropped while soft drop is held snap instead.
+pub fn interpolate_piece_motion(
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    settings: Res<VisualSettings>,
+    board: Res<BoardConfig>,
+    mut query_piece: Query<(&Position, &mut VisualOffset)>,
+) {
+    let snap_rows = !settings.glide_soft_drop && keyboard_input.pressed(board.soft_drop_key());
+    for (position, mut visual) in query_piece.iter_mut() {
+        let moved = Vec2::new(
+            (visual.last.x - position.x) as f32,
+            (visual.last.y - position.y) as f32,
+        );
+        visual.last = *position;
+
+        if !settings.smooth_movement {
+            visual.offset = Vec2::ZERO;
+            continue;
+        }
+
+        visual.offset += moved;
+        if snap_rows {
+            visual.offset.y = 0.0;
+        }
+        if visual.offset.length() > MAX_INTERPOLATED_CELLS {
+            visual.offset = Vec2::ZERO;
+        }
+
+        let step = time.delta_seconds() / MOVE_INTERPOLATION_SECS;
+        let distance = visual.offset.length();
+        visual.offset = if distance <= step {
+            Vec2::ZERO
+        } else {
+            visual.offset - visual.offset / distance * step
+        };
+    }
+}
+
+// Faint vertical lines from the bottom cell of each piece column down to the
+// matching ghost cell `drop` rows below, clipped to the visible rows
+pub fn trajectory_sprites(
+    board: &BoardConfig,
+    piece_cells: &[(isize, isize)],
+    drop: isize,
+    color: Color,
+) -> Vec<SpriteBundle> {
+    let mut sprites = Vec::new();
+    for &(x, y) in piece_cells {
+        // Only the lowest cell of each column starts a line
+        if drop <= 0 || piece_cells.contains(&(x, y + 1)) {
+            continue;
+        }
+        // The line covers the empty rows between the piece and its ghost
+        let first = (y + 1).max(board.scroll_row as isize);
+        let last = (y + drop - 1).min((board.scroll_row + board.visible_height) as isize - 1);
+        if first > last {
+            continue;
+        }
+
+        let top = board.cell_translation(x, first);
+        let bottom = board.cell_translation(x, last);
+        let length = (top.y - bottom.y).abs() + board.cell_size;
+        let mut line = block_sprite(board, x, first, color);
+        line.transform.translation = ((top + bottom) / 2.0).extend(Z_TRAJECTORY);
+        line.sprite.custom_size = Some(Vec2::new(board.cell_size * TRAJECTORY_WIDTH, length));
+        line.sprite.color.set_a(TRAJECTORY_ALPHA);
+        sprites.push(line);
+    }
+    sprites
+}
+
+// Scroll row that keeps half a window of headroom above the highest filled
+// row, resting on the bottom of the board until the stack grows into view
+pub fn scroll_row_for(board: &BoardConfig, game_map: &GameMap) -> usize {
+    let stack_top = game_map
+        .rows
+        .iter()
+        .position(|row| row.iter().any(|cell| *cell != Presence::No))
+        .unwrap_or(game_map.height());
+    let headroom = board.visible_height / 2;
+    let max_scroll = board.height.saturating_sub(board.visible_height);
+    stack_top.saturating_sub(headroom).min(max_scroll)
+}
+
+// Moves the visible window of a tall board to follow the stack
+pub fn update_board_scroll(game_map: Res<GameMap>, mut board: ResMut<BoardConfig>) {
+    if !game_map.is_changed() {
+        return;
+    }
+    let scroll_row = scroll_row_for(&board, &game_map);
+    if board.scroll_row != scroll_row {
+        board.scroll_row = scroll_row;
+    }
+}
+
+// Marker component for the hard drop distance shown beside the piece
+#[derive(Component)]
+pub struct DropDistanceLabel;
+
+pub fn setup_drop_distance_label(mut commands: Commands) {
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: FONT_SIZE_SMALL,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        DropDistanceLabel,
+    ));
+}
+
+// Shows how many rows a hard drop would move the piece, just right of its
+// lowest cell
+pub fn update_drop_distance_label(
+    settings: Res<VisualSettings>,
+    board: Res<BoardConfig>,
+    game_map: Res<GameMap>,
+    query_piece: Query<(&Piece, &Position)>,
+    mut query_label: Query<(&mut Text, &mut Transform, &mut Visibility), With<DropDistanceLabel>>,
+) {
+    let Ok((mut text, mut transform, mut visibility)) = query_label.get_single_mut() else {
+        return;
+    };
+    let piece = query_piece
+        .get_single()
+        .ok()
+        .filter(|_| settings.show_drop_distance);
+    let Some((piece, position)) = piece else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    let cells = piece_cells(piece, position);
+    let right = cells.iter().map(|&(x, _)| x).max().unwrap_or(position.x);
+    let bottom = cells.iter().map(|&(_, y)| y).max().unwrap_or(position.y);
+    *visibility = Visibility::Visible;
+    text.sections[0].value = drop_distance(piece, position, &game_map).to_string();
+    transform.translation = board.cell_translation(right + 1, bottom).extend(Z_OVERLAY);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_color::GameColor;
+    use crate::game_types::PieceType;
+
+    // Runs one frame of interpolation `dt` after the last one
+    fn step(app: &mut App, dt: Duration) -> Vec2 {
+        app.world.resource_mut::<Time>().advance_by(dt);
+        app.update();
+        let mut query = app.world.query::<&VisualOffset>();
+        query.single(&app.world).offset
+    }
+
+    #[test]
+    fn move_offset_starts_nonzero_and_decays_to_zero() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<VisualSettings>()
+            .init_resource::<BoardConfig>()
+            .add_systems(Update, interpolate_piece_motion);
+        let start = Position { x: 4, y: 0 };
+        let piece = app.world.spawn((start, VisualOffset::new(start))).id();
+
+        app.world.get_mut::<Position>(piece).unwrap().x = 5;
+        let first = step(&mut app, Duration::from_millis(5));
+        assert!(first.x < 0.0, "offset {:?}", first);
+
+        let mut offset = first;
+        for _ in 0..30 {
+            offset = step(&mut app, Duration::from_millis(16));
+        }
+        assert_eq!(offset, Vec2::ZERO);
+    }
+
+    #[test]
+    fn outline_ghost_draws_edge_sprites_instead_of_cells() {
+        let board = BoardConfig::default();
+        // A 2x2 footprint has two outer edges per cell
+        let cells = [(4, 10), (5, 10), (4, 11), (5, 11)];
+        let thickness = board.cell_size * GHOST_OUTLINE_WIDTH;
+
+        let solid = ghost_sprites(&board, &cells, Color::RED, GhostStyle::SolidTransparent);
+        assert_eq!(solid.len(), 4);
+        assert!(
+            solid
+                .iter()
+                .all(|cell| cell.sprite.color.a() == GHOST_ALPHA)
+        );
+
+        let outline = ghost_sprites(&board, &cells, Color::RED, GhostStyle::Outline);
+        assert_eq!(outline.len(), 8);
+        for edge in &outline {
+            let size = edge.sprite.custom_size.unwrap();
+            assert_eq!(size.min_element(), thickness);
+            assert_eq!(size.max_element(), board.cell_size);
+            assert_eq!(edge.sprite.color.a(), 1.0);
+        }
+    }
+
+    #[test]
+    fn trajectory_lines_run_from_the_piece_to_its_landing_row() {
+        let board = BoardConfig::default();
+        let game_map = GameMap::default();
+        let piece = Piece::new(PieceType::T);
+        let position = Position { x: 3, y: 2 };
+        let drop = drop_distance(&piece, &position, &game_map);
+        let cells = piece_cells(&piece, &position);
+
+        let lines = trajectory_sprites(&board, &cells, drop, Color::RED);
+        // The flat bottom of a spawn-state T is three cells wide, in row 3
+        assert_eq!(lines.len(), 3);
+        let half_cell = board.cell_size / 2.0;
+        for (line, x) in lines.iter().zip(3..) {
+            let center = line.transform.translation;
+            let half_length = line.sprite.custom_size.unwrap().y / 2.0;
+            let below_piece = board.cell_translation(x, 4);
+            let landing = board.cell_translation(x, 3 + drop);
+            assert_eq!(center.x, below_piece.x);
+            assert_eq!(center.y + half_length, below_piece.y + half_cell);
+            assert_eq!(center.y - half_length, landing.y + half_cell);
+        }
+    }
+
+    // Visual offset right after a one-row soft drop, with gliding on or off
+    fn soft_drop_offset(glide_soft_drop: bool) -> Vec2 {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .insert_resource(VisualSettings {
+                glide_soft_drop,
+                ..VisualSettings::default()
+            })
+            .init_resource::<BoardConfig>()
+            .add_systems(Update, interpolate_piece_motion);
+        let start = Position { x: 4, y: 3 };
+        let piece = app.world.spawn((start, VisualOffset::new(start))).id();
+
+        let soft_drop_key = BoardConfig::default().soft_drop_key();
+        app.world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(soft_drop_key);
+        app.world.get_mut::<Position>(piece).unwrap().y += 1;
+        let offset = step(&mut app, Duration::from_millis(5));
+        assert_eq!(app.world.get::<Position>(piece).unwrap().y, 4);
+        offset
+    }
+
+    #[test]
+    fn gliding_soft_drop_animates_while_the_row_moves_at_once() {
+        assert!(soft_drop_offset(true).y < 0.0);
+        assert_eq!(soft_drop_offset(false).y, 0.0);
+    }
+
+    #[test]
+    fn drop_distance_label_shows_the_depth_of_the_gap_below() {
+        let mut game_map = GameMap::default();
+        // A stack in columns 4 and 5 whose top is row 12, eight rows under
+        // the bottom of an O piece resting its lower cells on row 3
+        for y in 12..game_map.height() as isize {
+            game_map.set_cell(4, y, Presence::Yes(GameColor::Gray));
+            game_map.set_cell(5, y, Presence::Yes(GameColor::Gray));
+        }
+        let mut app = App::new();
+        app.insert_resource(VisualSettings {
+            show_drop_distance: true,
+            ..VisualSettings::default()
+        })
+        .init_resource::<BoardConfig>()
+        .insert_resource(game_map)
+        .add_systems(Startup, setup_drop_distance_label)
+        .add_systems(Update, update_drop_distance_label);
+        app.world
+            .spawn((Piece::new(PieceType::O), Position { x: 3, y: 2 }));
+        app.update();
+
+        let mut query = app
+            .world
+            .query_filtered::<(&Text, &Visibility), With<DropDistanceLabel>>();
+        let (text, visibility) = query.single(&app.world);
+        assert_eq!(text.sections[0].value, "8");
+        assert_eq!(*visibility, Visibility::Visible);
+    }
+
+    // The default board with its bottom row filled but for columns 3 to 6,
+    // the gap a flat I piece fills, and for every column in `skip`
+    fn bottom_row_with_gap(skip: &[isize]) -> GameMap {
+        let mut game_map = GameMap::default();
+        let bottom = game_map.height() as isize - 1;
+        for x in (0..game_map.width() as isize).filter(|x| !(3..7).contains(x) && !skip.contains(x))
+        {
+            game_map.set_cell(x, bottom, Presence::Yes(GameColor::Gray));
+        }
+        game_map
+    }
+
+    #[test]
+    fn flat_i_over_an_almost_full_bottom_row_flags_that_row() {
+        let piece = Piece::new(PieceType::I);
+        let position = Position { x: 3, y: 0 };
+        let game_map = bottom_row_with_gap(&[]);
+        let bottom = game_map.height() - 1;
+        assert_eq!(
+            rows_completed_on_drop(&piece, &position, &game_map),
+            [bottom]
+        );
+
+        // Another hole in the row leaves it incomplete
+        let game_map = bottom_row_with_gap(&[8]);
+        assert!(rows_completed_on_drop(&piece, &position, &game_map).is_empty());
+    }
+
+    #[test]
+    fn blocked_cell_in_the_row_does_not_stop_the_flag() {
+        let piece = Piece::new(PieceType::I);
+        let position = Position { x: 3, y: 0 };
+        let mut game_map = bottom_row_with_gap(&[8]);
+        let bottom = game_map.height() - 1;
+        game_map.block_cell(8, bottom);
+        assert_eq!(
+            rows_completed_on_drop(&piece, &position, &game_map),
+            [bottom]
+        );
+    }
+}